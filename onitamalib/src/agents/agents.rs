@@ -1,8 +1,17 @@
+use std::sync::Arc;
+
 use enum_iterator::IntoEnumIterator;
 use instant::Duration;
+use rand::thread_rng;
 use serde::{Deserialize, Serialize};
 
+use crate::agents::heuristics::EvalWeights;
+use crate::agents::mcts::MctsTree;
+use crate::agents::progress::{SearchInfo, SearchObserver};
 use crate::agents::{alphabeta, greedy, minimax, montecarlo};
+#[cfg(feature = "parallel")]
+use crate::agents::{mcts, parallel};
+use crate::book::OpeningBook;
 use crate::{GameState, Move};
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, Hash, IntoEnumIterator)]
@@ -12,30 +21,245 @@ pub enum AiAgent {
     HybridMonteCarlo,
     Minimax,
     Alphabeta,
+    MonteCarloTreeSearch,
 }
 
 impl AiAgent {
+    // The hybrid agent mixes alphabeta scores with playout counts, so it keeps to pawn counts
+    pub fn weights(&self) -> EvalWeights {
+        match self {
+            AiAgent::PureMonteCarlo | AiAgent::HybridMonteCarlo | AiAgent::MonteCarloTreeSearch => {
+                EvalWeights::pawns_only()
+            }
+            AiAgent::Greedy | AiAgent::Minimax | AiAgent::Alphabeta => EvalWeights::default(),
+        }
+    }
     pub fn play_move(&self, state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+        self.play_move_with_weights(state, duration, &self.weights())
+    }
+    pub fn play_move_with_weights(
+        &self,
+        state: &GameState,
+        duration: Duration,
+        weights: &EvalWeights,
+    ) -> Option<(Move, i64)> {
         match self {
-            AiAgent::Greedy => greedy::greedy_agent(state),
+            AiAgent::Greedy => greedy::greedy_agent(state, weights),
             AiAgent::PureMonteCarlo => montecarlo::pure_montecarlo_agent(state, duration),
-            AiAgent::HybridMonteCarlo => montecarlo::hybrid_hard_montecarlo_agent(state, duration),
-            AiAgent::Minimax => minimax::iterative_deepening(state, duration),
-            AiAgent::Alphabeta => alphabeta::iterative_deepening(state, duration),
+            AiAgent::HybridMonteCarlo => {
+                montecarlo::hybrid_hard_montecarlo_agent(state, duration, weights)
+            }
+            AiAgent::Minimax => minimax::iterative_deepening(state, duration, weights),
+            AiAgent::Alphabeta => alphabeta::iterative_deepening(state, duration, weights),
+            AiAgent::MonteCarloTreeSearch => MctsTree::default().play_move(state, duration),
+        }
+    }
+    // Greedy looks a fixed distance ahead and has nothing to report
+    pub fn play_move_observed(
+        &self,
+        state: &GameState,
+        duration: Duration,
+        observer: &mut dyn SearchObserver,
+    ) -> Option<(Move, i64)> {
+        let weights = self.weights();
+        match self {
+            AiAgent::Greedy => greedy::greedy_agent(state, &weights),
+            AiAgent::PureMonteCarlo => {
+                montecarlo::pure_montecarlo_observed(state, duration, observer)
+            }
+            AiAgent::HybridMonteCarlo => {
+                montecarlo::hybrid_hard_montecarlo_observed(state, duration, &weights, observer)
+            }
+            AiAgent::Minimax => {
+                minimax::iterative_deepening_observed(state, duration, &weights, observer)
+            }
+            AiAgent::Alphabeta => {
+                alphabeta::iterative_deepening_observed(state, duration, &weights, observer)
+            }
+            AiAgent::MonteCarloTreeSearch => {
+                MctsTree::default().play_move_observed(state, duration, observer)
+            }
+        }
+    }
+    // Greedy and Minimax don't have parallel versions and stay on one thread
+    #[cfg(feature = "parallel")]
+    pub fn play_move_threads(
+        &self,
+        state: &GameState,
+        duration: Duration,
+        threads: usize,
+    ) -> Option<(Move, i64)> {
+        let weights = self.weights();
+        match self {
+            AiAgent::Greedy | AiAgent::Minimax => self.play_move(state, duration),
+            AiAgent::PureMonteCarlo => parallel::pure_montecarlo_threads(state, duration, threads),
+            AiAgent::HybridMonteCarlo => {
+                parallel::hybrid_hard_montecarlo_threads(state, duration, &weights, threads)
+            }
+            AiAgent::Alphabeta => parallel::alphabeta_threads(state, duration, &weights, threads),
+            AiAgent::MonteCarloTreeSearch => {
+                let mut trees: Vec<MctsTree> = vec![];
+                mcts::resize_trees(&mut trees, threads);
+                parallel::mcts_threads(state, duration, &mut trees)
+            }
+        }
+    }
+    #[cfg(feature = "parallel")]
+    pub fn play_move_threads_observed(
+        &self,
+        state: &GameState,
+        duration: Duration,
+        threads: usize,
+        observer: &mut (dyn SearchObserver + Send),
+    ) -> Option<(Move, i64)> {
+        let weights = self.weights();
+        match self {
+            AiAgent::Greedy | AiAgent::Minimax => {
+                self.play_move_observed(state, duration, observer)
+            }
+            AiAgent::PureMonteCarlo => {
+                parallel::pure_montecarlo_threads_observed(state, duration, threads, observer)
+            }
+            AiAgent::HybridMonteCarlo => parallel::hybrid_hard_montecarlo_threads_observed(
+                state, duration, &weights, threads, observer,
+            ),
+            AiAgent::Alphabeta => {
+                parallel::alphabeta_threads_observed(state, duration, &weights, threads, observer)
+            }
+            AiAgent::MonteCarloTreeSearch => {
+                let mut trees: Vec<MctsTree> = vec![];
+                mcts::resize_trees(&mut trees, threads);
+                parallel::mcts_threads_observed(state, duration, &mut trees, observer)
+            }
+        }
+    }
+}
+
+/// An agent playing one side of a game, keeping any search tree between its turns
+pub struct AgentSession {
+    agent: AiAgent,
+    // One tree per search thread, there is always at least one
+    trees: Vec<MctsTree>,
+    // Played from while it has the position, before searching
+    book: Option<Arc<OpeningBook>>,
+}
+
+impl AgentSession {
+    pub fn new(agent: AiAgent) -> AgentSession {
+        AgentSession {
+            agent,
+            trees: vec![MctsTree::default()],
+            book: None,
+        }
+    }
+    pub fn with_book(mut self, book: Arc<OpeningBook>) -> AgentSession {
+        self.book = Some(book);
+        self
+    }
+    pub fn agent(&self) -> AiAgent {
+        self.agent
+    }
+    fn book_move(&self, state: &GameState) -> Option<(Move, i64)> {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => {
+                return None;
+            }
+        };
+        let game_move = self.book.as_ref()?.choose(board, &mut thread_rng())?;
+        log::info!("Playing book move");
+        Some((game_move, 0))
+    }
+    pub fn play_move(&mut self, state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+        self.play_move_observed(state, duration, &mut |_: &SearchInfo| {})
+    }
+    // Book moves aren't searched, so nothing is reported for them
+    pub fn play_move_observed(
+        &mut self,
+        state: &GameState,
+        duration: Duration,
+        observer: &mut dyn SearchObserver,
+    ) -> Option<(Move, i64)> {
+        if let Some(result) = self.book_move(state) {
+            return Some(result);
+        }
+        match self.agent {
+            AiAgent::MonteCarloTreeSearch => {
+                self.trees[0].play_move_observed(state, duration, observer)
+            }
+            agent => agent.play_move_observed(state, duration, observer),
+        }
+    }
+    #[cfg(feature = "parallel")]
+    pub fn play_move_threads(
+        &mut self,
+        state: &GameState,
+        duration: Duration,
+        threads: usize,
+    ) -> Option<(Move, i64)> {
+        self.play_move_threads_observed(state, duration, threads, &mut |_: &SearchInfo| {})
+    }
+    #[cfg(feature = "parallel")]
+    pub fn play_move_threads_observed(
+        &mut self,
+        state: &GameState,
+        duration: Duration,
+        threads: usize,
+        observer: &mut (dyn SearchObserver + Send),
+    ) -> Option<(Move, i64)> {
+        if let Some(result) = self.book_move(state) {
+            return Some(result);
+        }
+        match self.agent {
+            AiAgent::MonteCarloTreeSearch => {
+                mcts::resize_trees(&mut self.trees, threads);
+                parallel::mcts_threads_observed(state, duration, &mut self.trees, observer)
+            }
+            agent => agent.play_move_threads_observed(state, duration, threads, observer),
         }
     }
 }
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "web")] {
+        use std::cell::RefCell;
+
         use wasm_bindgen::prelude::*;
         use crate::MoveRequest;
 
+        thread_local! {
+            // Played from by move requests that ask for it
+            static BOOK: RefCell<Option<Arc<OpeningBook>>> = RefCell::new(None);
+        }
+
+        #[wasm_bindgen(js_name = loadOpeningBook)]
+        pub fn load_opening_book(data: &[u8]) -> Result<(), JsValue> {
+            let book = OpeningBook::read_from(&mut &data[..])
+                .map_err(|err| JsValue::from_str(&err))?;
+            BOOK.with(|loaded| *loaded.borrow_mut() = Some(Arc::new(book)));
+            Ok(())
+        }
+
         #[wasm_bindgen(js_name = agentMove)]
-        pub fn agent_move(request: &JsValue) -> JsValue {
-            let MoveRequest { state, agent } = request.into_serde().unwrap();
+        pub fn agent_move(request: &JsValue, on_progress: Option<js_sys::Function>) -> JsValue {
+            let MoveRequest { state, agent, book } = request.into_serde().unwrap();
+            let mut session = AgentSession::new(agent);
+            if let (true, Some(book)) = (book, BOOK.with(|loaded| loaded.borrow().clone())) {
+                session = session.with_book(book);
+            }
             let duration = Duration::from_millis(1000);
-            let (game_move, _) = agent.play_move(&state, duration).unwrap();
+            // Called with each depth's SearchInfo and a summary such as "depth 7, +14, PV: ..."
+            let mut report = |info: &SearchInfo| {
+                if let Some(on_progress) = &on_progress {
+                    let this = JsValue::null();
+                    let info_js = JsValue::from_serde(info).unwrap();
+                    let summary = JsValue::from_str(&info.to_string());
+                    if let Err(err) = on_progress.call2(&this, &info_js, &summary) {
+                        log::error!("Failed to call on_progress: {:?}", err);
+                    }
+                }
+            };
+            let (game_move, _) = session.play_move_observed(&state, duration, &mut report).unwrap();
             JsValue::from_serde(&game_move).unwrap()
         }
 
@@ -44,7 +268,11 @@ cfg_if::cfg_if! {
             // Used by training mode to request scoring of all possible moves
             let state: GameState = request.into_serde().unwrap();
             let duration = Duration::from_millis(1000);
-            let ranked_moves = montecarlo::hybrid_hard_montecarlo_rank_moves(&state, duration);
+            let ranked_moves = montecarlo::hybrid_hard_montecarlo_rank_moves(
+                &state,
+                duration,
+                &EvalWeights::pawns_only(),
+            );
             JsValue::from_serde(&ranked_moves).unwrap()
         }
    }
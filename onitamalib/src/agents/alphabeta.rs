@@ -1,22 +1,223 @@
 use std::cmp;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::Arc;
 
 use instant::{Duration, Instant};
 
-use crate::models::{GameState, Move, Player};
+use crate::agents::heuristics::EvalWeights;
+use crate::agents::ordering::{table_move_first, MoveOrdering};
+use crate::agents::progress::{SearchInfo, SearchObserver};
+use crate::agents::transposition::{Bound, Table, TableEntry, TranspositionTable};
+use crate::models::{Board, GameState, Move, Player};
+use crate::tablebase::{self, Tablebases};
 
-const MAX_DEPTH: u16 = 50;
+pub(crate) const MAX_DEPTH: u16 = 50;
+// Long enough to stand in for no time limit
+const UNLIMITED_TIME: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+// Nodes searched between checks of the limits, a power of two
+const POLL_NODES: u64 = 1024;
 
-pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+// State shared between the iterations of one iterative deepening search
+#[derive(Default)]
+pub struct Search<T: Table = TranspositionTable> {
+    weights: EvalWeights,
+    table: T,
+    ordering: MoveOrdering,
+    // Set by another thread to end the search early, as if the deadline passed
+    stop: Option<Arc<AtomicBool>>,
+    // Endgames solved exactly, looked up instead of searched
+    tablebases: Tablebases,
+    // Searching stops after this many nodes, as if the deadline passed
+    max_nodes: Option<u64>,
+    // When the depth being searched has to be abandoned
+    deadline: Option<Instant>,
+    // Set once a limit is hit, leaving the current depth unfinished
+    aborted: bool,
+    pub nodes: u64,
+}
+
+// When to stop an iterative deepening search, any limit left out doesn't apply
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SearchLimits {
+    pub time: Option<Duration>,
+    pub depth: Option<u16>,
+    pub nodes: Option<u64>,
+}
+
+impl Search {
+    pub fn new(weights: EvalWeights) -> Search {
+        Search::with_table(weights, TranspositionTable::default())
+    }
+}
+
+impl<T: Table> Search<T> {
+    pub fn with_table(weights: EvalWeights, table: T) -> Search<T> {
+        Search {
+            weights,
+            table,
+            ordering: MoveOrdering::new(),
+            stop: None,
+            tablebases: tablebase::installed(),
+            max_nodes: None,
+            deadline: None,
+            aborted: false,
+            nodes: 0,
+        }
+    }
+    pub fn stop_on(mut self, stop: Arc<AtomicBool>) -> Search<T> {
+        self.stop = Some(stop);
+        self
+    }
+    pub fn limit_nodes(mut self, nodes: u64) -> Search<T> {
+        self.max_nodes = Some(nodes);
+        self
+    }
+    fn timed_out(&self) -> bool {
+        let stopped = match &self.stop {
+            Some(stop) => stop.load(atomic::Ordering::Relaxed),
+            None => false,
+        };
+        let out_of_nodes = match self.max_nodes {
+            Some(max_nodes) => self.nodes >= max_nodes,
+            None => false,
+        };
+        let past_deadline = match self.deadline {
+            Some(deadline) => Instant::now() > deadline,
+            None => false,
+        };
+        stopped || out_of_nodes || past_deadline
+    }
+    // The best line from the position, following the table's best moves
+    fn principal_variation(&self, state: &GameState, length: u16) -> Vec<Move> {
+        let mut line = vec![];
+        let mut state = *state;
+        while let GameState::Playing { board } = state {
+            if line.len() >= length as usize {
+                break;
+            }
+            let game_move = match self.table.probe(board.zobrist_hash()) {
+                Some(TableEntry {
+                    best_move: Some(game_move),
+                    ..
+                }) => game_move,
+                _ => {
+                    break;
+                }
+            };
+            // Another position can have overwritten the entry
+            state = match board.try_move(game_move) {
+                Ok(next) => next,
+                Err(_) => {
+                    break;
+                }
+            };
+            line.push(game_move);
+        }
+        line
+    }
+    // What a completed depth found, with the line continuing from its best move
+    fn info(
+        &self,
+        state: &GameState,
+        depth: u16,
+        result: (Move, i64),
+        start: Instant,
+    ) -> SearchInfo {
+        let (game_move, score) = result;
+        let mut pv = vec![game_move];
+        if let GameState::Playing { board } = state {
+            if let Ok(next) = board.try_move(game_move) {
+                pv.extend(self.principal_variation(&next, depth.saturating_sub(1)));
+            }
+        }
+        SearchInfo {
+            depth,
+            score,
+            nodes: self.nodes,
+            time: start.elapsed().as_millis() as u64,
+            pv,
+        }
+    }
+}
+
+pub fn iterative_deepening(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+) -> Option<(Move, i64)> {
+    iterative_deepening_observed(state, duration, weights, &mut |_: &SearchInfo| {})
+}
+
+pub fn iterative_deepening_observed(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
+    let deadline = Instant::now() + duration;
+    let mut search = Search::new(*weights);
+    deepen_observed(state, deadline, 1, MAX_DEPTH - 1, &mut search, observer)
+        .map(|(_, result)| result)
+}
+
+// Searches until one of the limits is reached, telling the observer about each depth completed
+pub fn deepen_within<T: Table>(
+    state: &GameState,
+    limits: &SearchLimits,
+    search: &mut Search<T>,
+    observer: &mut dyn SearchObserver,
+) -> Option<(u16, (Move, i64))> {
+    let deadline = Instant::now() + limits.time.unwrap_or(UNLIMITED_TIME);
+    if let Some(nodes) = limits.nodes {
+        search.max_nodes = Some(search.nodes + nodes);
+    }
+    let last_depth = limits.depth.unwrap_or(MAX_DEPTH - 1).min(MAX_DEPTH - 1);
+    deepen_observed(state, deadline, 1, last_depth, search, observer)
+}
+
+// Searches one depth at a time from first_depth, returning the deepest completed search
+#[cfg(feature = "parallel")]
+pub(crate) fn deepen<T: Table>(
+    state: &GameState,
+    deadline: Instant,
+    first_depth: u16,
+    search: &mut Search<T>,
+) -> Option<(u16, (Move, i64))> {
+    let mut ignore = |_: &SearchInfo| {};
+    deepen_observed(
+        state,
+        deadline,
+        first_depth,
+        MAX_DEPTH - 1,
+        search,
+        &mut ignore,
+    )
+}
+
+pub(crate) fn deepen_observed<T: Table>(
+    state: &GameState,
+    deadline: Instant,
+    first_depth: u16,
+    last_depth: u16,
+    search: &mut Search<T>,
+    observer: &mut dyn SearchObserver,
+) -> Option<(u16, (Move, i64))> {
     let start = Instant::now();
-    let deadline = start + duration;
-    let mut result: Option<(Move, i64)> = None;
-    for depth in 1..MAX_DEPTH {
-        if let Some((_, val)) = result {
+    if let GameState::Playing { board } = state {
+        if let Some((game_move, value)) = search.tablebases.best_move(board) {
+            let result = (game_move, value.score(board.turn));
+            observer.on_depth(&search.info(state, MAX_DEPTH, result, start));
+            return Some((MAX_DEPTH, result));
+        }
+    }
+    let mut result: Option<(u16, (Move, i64))> = None;
+    for depth in first_depth..=last_depth {
+        if let Some((_, (_, val))) = result {
             if val == i64::MAX || val == i64::MIN {
                 break;
             }
         }
-        match optimal_move_deadline(state, depth, deadline) {
+        match optimal_move_deadline(state, depth, deadline, search) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -26,17 +227,23 @@ pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Mov
                 break;
             }
             Some(val) => {
-                result = Some(val);
+                observer.on_depth(&search.info(state, depth, val, start));
+                result = Some((depth, val));
             }
         };
     }
-    return result;
+    result
 }
 
 #[cfg(test)]
-pub fn iterative_deepening_just_depth(state: &GameState, duration: Duration) -> Option<u16> {
+pub fn iterative_deepening_just_depth(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+) -> Option<u16> {
     let start = Instant::now();
     let deadline = start + duration;
+    let mut search = Search::new(*weights);
     let mut result: Option<(Move, i64)> = None;
     for depth in 1..MAX_DEPTH {
         if let Some((_, val)) = result {
@@ -44,7 +251,7 @@ pub fn iterative_deepening_just_depth(state: &GameState, duration: Duration) ->
                 break;
             }
         }
-        match optimal_move_deadline(state, depth, deadline) {
+        match optimal_move_deadline(state, depth, deadline, &mut search) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -61,8 +268,12 @@ pub fn iterative_deepening_just_depth(state: &GameState, duration: Duration) ->
     return None;
 }
 
-fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Option<(Move, i64)> {
-    let timedout = || Instant::now() > deadline;
+fn optimal_move_deadline<T: Table>(
+    state: &GameState,
+    depth: u16,
+    deadline: Instant,
+    search: &mut Search<T>,
+) -> Option<(Move, i64)> {
     let board = match state {
         GameState::Playing { board } => board,
         GameState::Finished { .. } => {
@@ -72,21 +283,36 @@ fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Op
     if depth == 0 {
         return None;
     }
-    if timedout() {
+    search.deadline = Some(deadline);
+    search.aborted = false;
+    if search.timed_out() {
         return None;
     }
-    let mut game_moves = board.legal_moves().into_iter();
+    let hash = board.zobrist_hash();
+    let table_move = search.table.probe(hash).and_then(|entry| entry.best_move);
+    let mut game_moves = ordered_moves(board, table_move, depth, 0, search).into_iter();
     let mut best_move = game_moves.next().expect("No legal moves");
     let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+    let mut best_score = minimax(&state, depth - 1, 1, i64::MIN, i64::MAX, search);
+    if search.aborted {
+        return None;
+    }
     for game_move in game_moves {
-        if timedout() {
+        if search.timed_out() {
             return None;
         }
         let state = board
             .try_move(game_move)
             .expect("generated illegal move in loop");
-        let expected_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+        // Only need to know whether this move beats the best so far
+        let (alpha, beta) = match board.turn {
+            Player::Red => (best_score, i64::MAX),
+            Player::Blue => (i64::MIN, best_score),
+        };
+        let expected_score = minimax(&state, depth - 1, 1, alpha, beta, search);
+        if search.aborted {
+            return None;
+        }
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -99,15 +325,27 @@ fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Op
             _ => {}
         };
     }
+    search.table.store(TableEntry {
+        hash,
+        depth,
+        score: best_score,
+        bound: Bound::Exact,
+        best_move: Some(best_move),
+    });
     return Some((best_move, best_score));
 }
 
-pub fn moves_scored_deepening(state: &GameState, duration: Duration) -> Option<Vec<(Move, i64)>> {
+pub fn moves_scored_deepening(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+) -> Option<Vec<(Move, i64)>> {
     let start = Instant::now();
     let deadline = start + duration;
+    let mut search = Search::new(*weights);
     let mut result: Option<Vec<(Move, i64)>> = None;
     for depth in 1..MAX_DEPTH {
-        match moves_scored_deadline(state, depth, deadline) {
+        match moves_scored_deadline(state, depth, deadline, &mut search) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -128,6 +366,7 @@ fn moves_scored_deadline(
     state: &GameState,
     depth: u16,
     deadline: Instant,
+    search: &mut Search,
 ) -> Option<Vec<(Move, i64)>> {
     let timedout = || Instant::now() > deadline;
     let board = match state {
@@ -143,6 +382,7 @@ fn moves_scored_deadline(
         return None;
     }
     let mut scored_moves: Vec<(Move, i64)> = vec![];
+    // Scores are returned in generation order, so don't reorder the root
     let game_moves = board.legal_moves().into_iter();
     for game_move in game_moves {
         if timedout() {
@@ -151,39 +391,91 @@ fn moves_scored_deadline(
         let state = board
             .try_move(game_move)
             .expect("generated illegal move in loop");
-        let expected_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+        let expected_score = minimax(&state, depth - 1, 1, i64::MIN, i64::MAX, search);
         scored_moves.push((game_move, expected_score));
     }
     return Some(scored_moves);
 }
 
-fn minimax(state: &GameState, depth: u16, mut alpha: i64, mut beta: i64) -> i64 {
+fn minimax<T: Table>(
+    state: &GameState,
+    depth: u16,
+    ply: usize,
+    mut alpha: i64,
+    mut beta: i64,
+    search: &mut Search<T>,
+) -> i64 {
+    search.nodes += 1;
+    if search.nodes & (POLL_NODES - 1) == 0 && search.timed_out() {
+        search.aborted = true;
+    }
+    // The unfinished depth's scores are thrown away, so leave the table as it was
+    if search.aborted {
+        return 0;
+    }
+    if let GameState::Playing { board } = state {
+        if let Some(value) = search.tablebases.probe(board) {
+            return value.score(board.turn);
+        }
+    }
     if depth == 0 {
-        return state.basic_value();
+        return state.evaluate(&search.weights);
     }
     let board = match state {
         GameState::Playing { board } => board,
         GameState::Finished { .. } => {
-            return state.basic_value();
+            return state.evaluate(&search.weights);
         }
     };
+    let hash = board.zobrist_hash();
+    let entry = search.table.probe(hash);
+    if let Some(entry) = entry {
+        if entry.depth >= depth {
+            match entry.bound {
+                Bound::Exact => {
+                    return entry.score;
+                }
+                Bound::Lower => {
+                    alpha = cmp::max(alpha, entry.score);
+                }
+                Bound::Upper => {
+                    beta = cmp::min(beta, entry.score);
+                }
+            };
+            if alpha >= beta {
+                return entry.score;
+            }
+        }
+    }
+    let (alpha_searched, beta_searched) = (alpha, beta);
     let mut value = match board.turn {
         Player::Red => i64::MIN,
         Player::Blue => i64::MAX,
     };
-    let legal_moves = board.legal_moves().into_iter();
+    let table_move = entry.and_then(|entry| entry.best_move);
+    let legal_moves = ordered_moves(board, table_move, depth, ply, search);
+    let mut best_move = legal_moves[0];
     for game_move in legal_moves {
         let state = board.try_move(game_move).expect("illegal move generated");
-        let next_val = minimax(&state, depth - 1, alpha, beta);
-        value = match board.turn {
-            Player::Red => cmp::max(value, next_val),
-            Player::Blue => cmp::min(value, next_val),
+        let next_val = minimax(&state, depth - 1, ply + 1, alpha, beta, search);
+        if search.aborted {
+            return 0;
+        }
+        let improved = match board.turn {
+            Player::Red => next_val > value,
+            Player::Blue => next_val < value,
         };
+        if improved {
+            value = next_val;
+            best_move = game_move;
+        }
         match board.turn {
             Player::Red if value >= beta => {
+                search.ordering.record_cutoff(board, game_move, depth, ply);
                 break;
             }
             Player::Blue if value <= alpha => {
+                search.ordering.record_cutoff(board, game_move, depth, ply);
                 break;
             }
             Player::Red => {
@@ -194,10 +486,66 @@ fn minimax(state: &GameState, depth: u16, mut alpha: i64, mut beta: i64) -> i64
             }
         };
     }
-    return value;
+    let bound = if value <= alpha_searched {
+        Bound::Upper
+    } else if value >= beta_searched {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    let best_move = match bound {
+        // Failing low doesn't tell us which move was best, so keep the previous one
+        Bound::Upper => entry.and_then(|entry| entry.best_move),
+        _ => Some(best_move),
+    };
+    search.table.store(TableEntry {
+        hash,
+        depth,
+        score: value,
+        bound,
+        best_move,
+    });
+    value
+}
+
+fn ordered_moves<T: Table>(
+    board: &Board,
+    table_move: Option<Move>,
+    depth: u16,
+    ply: usize,
+    search: &Search<T>,
+) -> Vec<Move> {
+    let mut legal_moves = board.legal_moves();
+    match depth {
+        // Just above the leaves, sorting costs more than the cutoffs it finds
+        1 => table_move_first(&mut legal_moves, table_move),
+        _ => search
+            .ordering
+            .order(board, &mut legal_moves, table_move, ply),
+    };
+    legal_moves
+}
+
+// Number of nodes searched to reach each depth, iterating as the agent would
+#[cfg(test)]
+pub fn nodes_per_depth(state: &GameState, max_depth: u16, ordered: bool) -> Vec<u64> {
+    let mut search: Search = Search::default();
+    if !ordered {
+        search.ordering = MoveOrdering::disabled();
+    }
+    let deadline = Instant::now() + Duration::from_secs(3600);
+    let mut nodes = vec![];
+    for depth in 1..=max_depth {
+        let before = search.nodes;
+        if optimal_move_deadline(state, depth, deadline, &mut search).is_none() {
+            break;
+        }
+        nodes.push(search.nodes - before);
+    }
+    nodes
 }
 
-pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
+pub fn optimal_move(state: &GameState, depth: u16, weights: &EvalWeights) -> Option<(Move, i64)> {
     let board = match state {
         GameState::Playing { board } => board,
         GameState::Finished { .. } => {
@@ -207,15 +555,16 @@ pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
     if depth == 0 {
         return None;
     }
+    let mut search = Search::new(*weights);
     let mut game_moves = board.legal_moves().into_iter();
     let mut best_move = game_moves.next().expect("No legal moves");
     let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+    let mut best_score = minimax(&state, depth - 1, 1, i64::MIN, i64::MAX, &mut search);
     for game_move in game_moves {
         let state = board
             .try_move(game_move)
             .expect("generated illegal move in loop");
-        let expected_score = minimax(&state, depth - 1, i64::MIN, i64::MAX);
+        let expected_score = minimax(&state, depth - 1, 1, i64::MIN, i64::MAX, &mut search);
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -0,0 +1,94 @@
+// Finds the best move for a position on its own, for scripts and bots that don't play a whole game
+use std::sync::Arc;
+
+use instant::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::heuristics::EvalWeights;
+use crate::agents::montecarlo;
+use crate::agents::progress::SearchInfo;
+use crate::book::OpeningBook;
+use crate::{AgentSession, AiAgent, GameState, Move, Player};
+
+// Thinking time when the request doesn't give one, in milliseconds
+const DEFAULT_TIME: u64 = 1000;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRequest {
+    pub state: GameState,
+    pub agent: AiAgent,
+    // Time budget in milliseconds, split between the agent and ranking every move
+    #[serde(default = "default_time")]
+    pub time: u64,
+    // Answer from the opening book while the position is in it
+    #[serde(default)]
+    pub book: bool,
+}
+
+fn default_time() -> u64 {
+    DEFAULT_TIME
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedMove {
+    pub game_move: Move,
+    pub score: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Analysis {
+    // The agent's choice and its score, positive favours red
+    pub best_move: Move,
+    pub score: i64,
+    // Every legal move scored by the hybrid Monte Carlo ranking, best for the player to move first
+    pub ranked: Vec<RankedMove>,
+    // The last progress the agent reported, the deepest depth for agents that deepen
+    pub search: Option<SearchInfo>,
+}
+
+// The book is only played from when the request asks for it
+pub fn analyse(
+    request: &AnalysisRequest,
+    book: Option<Arc<OpeningBook>>,
+) -> Result<Analysis, String> {
+    let turn = match request.state {
+        GameState::Playing { board } => board.turn,
+        GameState::Finished { .. } => {
+            return Err("Game is already finished".to_string());
+        }
+    };
+    let duration = Duration::from_millis(request.time) / 2;
+    let mut session = AgentSession::new(request.agent);
+    if let (true, Some(book)) = (request.book, book) {
+        session = session.with_book(book);
+    }
+    let mut search = None;
+    let (best_move, score) = session
+        .play_move_observed(&request.state, duration, &mut |info: &SearchInfo| {
+            search = Some(info.clone());
+        })
+        .ok_or_else(|| "No moves available".to_string())?;
+    let ranked = montecarlo::hybrid_hard_montecarlo_rank_moves(
+        &request.state,
+        duration,
+        &EvalWeights::pawns_only(),
+    )
+    .ok_or_else(|| "No moves available".to_string())?;
+    let mut ranked: Vec<RankedMove> = ranked
+        .into_iter()
+        .map(|(game_move, score)| RankedMove { game_move, score })
+        .collect();
+    match turn {
+        Player::Red => ranked.sort_by_key(|ranked| std::cmp::Reverse(ranked.score)),
+        Player::Blue => ranked.sort_by_key(|ranked| ranked.score),
+    };
+    Ok(Analysis {
+        best_move,
+        score,
+        ranked,
+        search,
+    })
+}
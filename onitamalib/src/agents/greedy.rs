@@ -1,8 +1,9 @@
+use crate::agents::heuristics::EvalWeights;
 use crate::minimax;
 use crate::models::{GameState, Move, Player};
 use rand::prelude::*;
 
-pub fn greedy_agent(state: &GameState) -> Option<(Move, i64)> {
+pub fn greedy_agent(state: &GameState, weights: &EvalWeights) -> Option<(Move, i64)> {
     let board = match state {
         GameState::Playing { board, .. } => Some(board),
         GameState::Finished { .. } => None,
@@ -14,7 +15,7 @@ pub fn greedy_agent(state: &GameState) -> Option<(Move, i64)> {
         .into_iter()
         .map(|game_move| {
             let state = board.try_move(game_move).unwrap();
-            (game_move, minimax::minimax(&state, 3))
+            (game_move, minimax::minimax(&state, 3, weights))
         })
         .collect();
     let key = |(_, score): &(Move, i64)| *score;
@@ -1,5 +1,8 @@
 // Red is maximizing player, Blue is minimizing
-use crate::{GameState, Player, Point};
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::{distance, goal_square, king_zone, move_mask, squares, Bitboard};
+use crate::{Board, Card, GameState, Player};
 
 fn value_from_pawn_count(count: usize) -> i64 {
     // Reserve one point for possibly distinguishing current player
@@ -13,29 +16,146 @@ fn value_from_pawn_count(count: usize) -> i64 {
         _ => panic!("Invalid number of pawns"),
     }
 }
+
+/// Weights for each term of the evaluation, a weight of zero skips that term
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalWeights {
+    // Multiplies the pawn count value
+    pub material: i64,
+    // Per step the king is closer to the opponent's temple arch than the opposing king is to ours
+    pub king_distance: i64,
+    // Per move available with the cards in hand
+    pub mobility: i64,
+    // Per move the side to move will have with the spare card, which it collects after moving
+    pub incoming_mobility: i64,
+    // Per attacked square on or around the opponent's king
+    pub king_attack: i64,
+    // For being the side to move
+    pub tempo: i64,
+}
+
+impl EvalWeights {
+    // Only counts pawns, the evaluation used before the other terms existed
+    pub const fn pawns_only() -> EvalWeights {
+        EvalWeights {
+            material: 1,
+            king_distance: 0,
+            mobility: 0,
+            incoming_mobility: 0,
+            king_attack: 0,
+            tempo: 0,
+        }
+    }
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            material: 10,
+            king_distance: 6,
+            mobility: 1,
+            incoming_mobility: 1,
+            king_attack: 4,
+            tempo: 3,
+        }
+    }
+}
+
 impl GameState {
     pub fn basic_value(&self) -> i64 {
+        self.evaluate(&EvalWeights::pawns_only())
+    }
+    pub fn evaluate(&self, weights: &EvalWeights) -> i64 {
         let board = match self {
             GameState::Playing { board } => board,
             GameState::Finished {
-                winner: Player::Blue,
+                winner: Some(Player::Blue),
                 ..
             } => {
                 return i64::MIN;
             }
             GameState::Finished {
-                winner: Player::Red,
+                winner: Some(Player::Red),
                 ..
             } => {
                 return i64::MAX;
             }
+            GameState::Finished { winner: None, .. } => {
+                return 0;
+            }
         };
-        let count_pieces = |acc, piece: &Option<Point>| match piece {
-            None => acc,
-            Some(_) => acc + 1,
-        };
-        let red_count = board.red_pawns.iter().fold(0, count_pieces);
-        let blue_count = board.blue_pawns.iter().fold(0, count_pieces);
-        return value_from_pawn_count(red_count) - value_from_pawn_count(blue_count);
+        board.evaluate(weights)
+    }
+}
+
+impl Board {
+    fn evaluate(&self, weights: &EvalWeights) -> i64 {
+        let mut value = 0;
+        if weights.material != 0 {
+            let red_count = self.pawn_count(Player::Red);
+            let blue_count = self.pawn_count(Player::Blue);
+            value += weights.material
+                * (value_from_pawn_count(red_count) - value_from_pawn_count(blue_count));
+        }
+        if weights.king_distance != 0 {
+            let king_distance = |player| {
+                let king = self.king(player).trailing_zeros();
+                distance(king, goal_square(player)) as i64
+            };
+            value +=
+                weights.king_distance * (king_distance(Player::Blue) - king_distance(Player::Red));
+        }
+        if weights.mobility != 0 {
+            let mobility = |player| self.mobility(player, self.hand(player)) as i64;
+            value += weights.mobility * (mobility(Player::Red) - mobility(Player::Blue));
+        }
+        if weights.incoming_mobility != 0 {
+            let incoming = self.mobility(self.turn, &[self.spare_card]) as i64;
+            value += weights.incoming_mobility * self.turn_sign() * incoming;
+        }
+        if weights.king_attack != 0 {
+            let attacked = |player: Player| {
+                let opponent_king = self.king(player.invert()).trailing_zeros();
+                let attacks = self.attacks(player, self.hand(player));
+                (attacks & king_zone(opponent_king)).count_ones() as i64
+            };
+            value += weights.king_attack * (attacked(Player::Red) - attacked(Player::Blue));
+        }
+        value + weights.tempo * self.turn_sign()
+    }
+    fn hand(&self, player: Player) -> &[Card; 2] {
+        match player {
+            Player::Red => &self.red_hand,
+            Player::Blue => &self.blue_hand,
+        }
+    }
+    fn turn_sign(&self) -> i64 {
+        match self.turn {
+            Player::Red => 1,
+            Player::Blue => -1,
+        }
+    }
+    // Number of moves the player could make with the given cards
+    fn mobility(&self, player: Player, cards: &[Card]) -> u32 {
+        let pieces = self.pieces(player);
+        let mut count = 0;
+        for card in cards {
+            for src in squares(pieces) {
+                count += (move_mask(*card, player, src) & !pieces).count_ones();
+            }
+        }
+        count
+    }
+    // Squares the player's pieces could move to with the given cards
+    fn attacks(&self, player: Player, cards: &[Card]) -> Bitboard {
+        let pieces = self.pieces(player);
+        let mut attacks = 0;
+        for card in cards {
+            for src in squares(pieces) {
+                attacks |= move_mask(*card, player, src);
+            }
+        }
+        attacks
     }
 }
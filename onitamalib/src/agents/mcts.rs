@@ -0,0 +1,425 @@
+// Monte Carlo tree search with UCT selection
+// Each node's wins are counted for the player who made the move leading to it
+use instant::{Duration, Instant};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::progress::{SearchInfo, SearchObserver, REPORT_INTERVAL};
+use crate::bitboard::{goal_square, move_mask, point_of, squares};
+use crate::tablebase::{self, table_move, Tablebases};
+use crate::{Board, GameState, Move, Player};
+
+const ITERATIONS_PER_TIME_CHECK: u32 = 50;
+// Nodes the trees of one session hold between them, a long-lived server keeps many sessions.
+// Trees stop expanding at their share, playouts continue from the leaves
+const SESSION_NODES: usize = 200_000;
+// Scale of the returned score, a certain Red win scores SCORE_SCALE
+const SCORE_SCALE: f64 = 1000.0;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PlayoutPolicy {
+    // Uniformly random moves
+    Random,
+    // Takes a winning move whenever one exists, otherwise random
+    WinningMoves,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MctsConfig {
+    pub exploration: f64,
+    pub playout: PlayoutPolicy,
+    // Playouts longer than this are scored as a draw
+    pub max_playout_turns: u32,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f64::consts::SQRT_2,
+            playout: PlayoutPolicy::WinningMoves,
+            max_playout_turns: 1000,
+        }
+    }
+}
+
+struct Node {
+    state: GameState,
+    game_move: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u32,
+    wins: f64,
+}
+
+impl Node {
+    fn new(state: GameState, game_move: Option<Move>, parent: Option<usize>) -> Node {
+        let untried = match state {
+            GameState::Playing { board } => board.legal_moves(),
+            GameState::Finished { .. } => vec![],
+        };
+        Node {
+            state,
+            game_move,
+            parent,
+            children: vec![],
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+/// Search tree kept between turns, so playouts from earlier searches aren't thrown away
+pub struct MctsTree {
+    config: MctsConfig,
+    // Nodes are stored in an arena, the root is always the first node
+    nodes: Vec<Node>,
+    // Ends playouts early, taken afresh for each search
+    tablebases: Tablebases,
+    max_nodes: usize,
+}
+
+impl MctsTree {
+    pub fn new(config: MctsConfig) -> MctsTree {
+        MctsTree {
+            config,
+            nodes: vec![],
+            tablebases: Tablebases::default(),
+            max_nodes: SESSION_NODES,
+        }
+    }
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> MctsTree {
+        self.max_nodes = max_nodes;
+        self
+    }
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.first().map_or(0, |root| root.visits)
+    }
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn play_move(&mut self, state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+        self.play_move_observed(state, duration, &mut |_: &SearchInfo| {})
+    }
+    pub fn play_move_observed(
+        &mut self,
+        state: &GameState,
+        duration: Duration,
+        observer: &mut dyn SearchObserver,
+    ) -> Option<(Move, i64)> {
+        let board = match state {
+            GameState::Playing { board } => *board,
+            GameState::Finished { .. } => {
+                return None;
+            }
+        };
+        if let Some(result) = table_move(state) {
+            return Some(result);
+        }
+        self.search(state, Instant::now() + duration, observer);
+        best_move(&board, self.root_stats())
+    }
+    // Runs iterations from the state until the deadline, reporting every REPORT_INTERVAL
+    pub(crate) fn search(
+        &mut self,
+        state: &GameState,
+        deadline: Instant,
+        observer: &mut dyn SearchObserver,
+    ) {
+        let start = Instant::now();
+        self.advance(state);
+        self.tablebases = tablebase::installed();
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "web")] {
+                let mut rng = rand_mt::Mt::default();
+            } else {
+                let mut rng = thread_rng();
+            }
+        }
+        let mut iterations = 0u64;
+        let mut next_report = start + REPORT_INTERVAL;
+        while Instant::now() < deadline {
+            for _ in 0..ITERATIONS_PER_TIME_CHECK {
+                self.iterate(&mut rng);
+                iterations += 1;
+            }
+            if Instant::now() >= next_report {
+                next_report += REPORT_INTERVAL;
+                if let Some(info) = self.info(start) {
+                    observer.on_depth(&info);
+                }
+            }
+        }
+        log::info!(
+            "MCTS ran {} iterations, tree has {} nodes",
+            iterations,
+            self.nodes.len()
+        );
+    }
+    // Visits and wins of each move from the root
+    pub(crate) fn root_stats(&self) -> Vec<(Move, u32, f64)> {
+        let root = match self.nodes.first() {
+            Some(root) => root,
+            None => {
+                return vec![];
+            }
+        };
+        root.children
+            .iter()
+            .filter_map(|idx| {
+                let child = &self.nodes[*idx];
+                Some((child.game_move?, child.visits, child.wins))
+            })
+            .collect()
+    }
+    // The most visited line from the root, scored by its first move
+    fn info(&self, start: Instant) -> Option<SearchInfo> {
+        let board = match self.nodes.first()?.state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => {
+                return None;
+            }
+        };
+        let mut pv = vec![];
+        let mut idx = 0;
+        while let Some(child) = self.nodes[idx]
+            .children
+            .iter()
+            .copied()
+            .max_by_key(|child| self.nodes[*child].visits)
+        {
+            pv.extend(self.nodes[child].game_move);
+            idx = child;
+        }
+        if pv.is_empty() {
+            return None;
+        }
+        let (_, score) = best_move(&board, self.root_stats())?;
+        Some(SearchInfo {
+            depth: pv.len() as u16,
+            score,
+            nodes: self.root_visits() as u64,
+            time: start.elapsed().as_millis() as u64,
+            pv,
+        })
+    }
+    // Makes the node for the state the new root, keeping its subtree
+    fn advance(&mut self, state: &GameState) {
+        let found = match self.nodes.first() {
+            None => None,
+            Some(root) if root.state == *state => Some(0),
+            Some(root) => root
+                .children
+                .iter()
+                .chain(
+                    root.children
+                        .iter()
+                        .flat_map(|idx| &self.nodes[*idx].children),
+                )
+                .copied()
+                .find(|idx| self.nodes[*idx].state == *state),
+        };
+        match found {
+            None => {
+                self.nodes = vec![Node::new(*state, None, None)];
+            }
+            Some(0) => {}
+            Some(idx) => {
+                self.rebuild_from(idx);
+            }
+        };
+    }
+    // Copies the subtree below idx into a fresh arena
+    fn rebuild_from(&mut self, idx: usize) {
+        let mut old_nodes: Vec<Option<Node>> = self.nodes.drain(..).map(Some).collect();
+        let mut root = old_nodes[idx].take().expect("Node visited twice");
+        root.parent = None;
+        root.game_move = None;
+        self.nodes.push(root);
+        let mut next = 0;
+        while next < self.nodes.len() {
+            let old_children = std::mem::take(&mut self.nodes[next].children);
+            let mut children = Vec::with_capacity(old_children.len());
+            for old_idx in old_children {
+                let mut child = old_nodes[old_idx].take().expect("Node visited twice");
+                child.parent = Some(next);
+                children.push(self.nodes.len());
+                self.nodes.push(child);
+            }
+            self.nodes[next].children = children;
+            next += 1;
+        }
+    }
+    fn iterate<R: Rng>(&mut self, rng: &mut R) {
+        let leaf = self.select_and_expand(rng);
+        let winner = simulate(
+            self.nodes[leaf].state,
+            self.config.playout,
+            self.config.max_playout_turns,
+            &self.tablebases,
+            rng,
+        );
+        self.backpropagate(leaf, winner);
+    }
+    fn select_and_expand<R: Rng>(&mut self, rng: &mut R) -> usize {
+        let mut idx = 0;
+        loop {
+            let node = &self.nodes[idx];
+            if !node.untried.is_empty() && self.nodes.len() < self.max_nodes {
+                return self.expand(idx, rng);
+            }
+            if node.children.is_empty() {
+                return idx;
+            }
+            idx = self.select_child(idx);
+        }
+    }
+    fn expand<R: Rng>(&mut self, idx: usize, rng: &mut R) -> usize {
+        let node = &mut self.nodes[idx];
+        let choice = rng.gen_range(0..node.untried.len());
+        let game_move = node.untried.swap_remove(choice);
+        let board = match node.state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Expanded a finished game"),
+        };
+        let state = board
+            .try_move(game_move)
+            .expect("MCTS generated illegal move");
+        let child = self.nodes.len();
+        self.nodes
+            .push(Node::new(state, Some(game_move), Some(idx)));
+        self.nodes[idx].children.push(child);
+        child
+    }
+    fn select_child(&self, idx: usize) -> usize {
+        let node = &self.nodes[idx];
+        let log_visits = (node.visits.max(1) as f64).ln();
+        let uct = |child: &Node| {
+            if child.visits == 0 {
+                return f64::INFINITY;
+            }
+            let visits = child.visits as f64;
+            child.wins / visits + self.config.exploration * (log_visits / visits).sqrt()
+        };
+        let mut best = node.children[0];
+        let mut best_value = f64::NEG_INFINITY;
+        for child in node.children.iter() {
+            let value = uct(&self.nodes[*child]);
+            if value > best_value {
+                best = *child;
+                best_value = value;
+            }
+        }
+        best
+    }
+    fn backpropagate(&mut self, leaf: usize, winner: Option<Player>) {
+        let mut idx = Some(leaf);
+        while let Some(current) = idx {
+            let parent = self.nodes[current].parent;
+            // The player who moved into this node is the one to move at its parent
+            let mover = parent.and_then(|parent| match self.nodes[parent].state {
+                GameState::Playing { board } => Some(board.turn),
+                GameState::Finished { .. } => None,
+            });
+            let node = &mut self.nodes[current];
+            node.visits += 1;
+            node.wins += match (winner, mover) {
+                (None, _) | (_, None) => 0.5,
+                (Some(winner), Some(mover)) if winner == mover => 1.0,
+                _ => 0.0,
+            };
+            idx = parent;
+        }
+    }
+}
+
+// The most visited move, scored from Red's perspective like the other agents.
+// A search stopped before its first iteration plays the first legal move, scored as even
+pub(crate) fn best_move(board: &Board, stats: Vec<(Move, u32, f64)>) -> Option<(Move, i64)> {
+    let (game_move, visits, wins) = match stats.into_iter().max_by_key(|(_, visits, _)| *visits) {
+        Some(best) => best,
+        None => {
+            return board.legal_moves().first().map(|game_move| (*game_move, 0));
+        }
+    };
+    let win_rate = wins / visits.max(1) as f64;
+    let score = match board.turn {
+        Player::Red => (2.0 * win_rate - 1.0) * SCORE_SCALE,
+        Player::Blue => (1.0 - 2.0 * win_rate) * SCORE_SCALE,
+    };
+    Some((game_move, score as i64))
+}
+
+impl Default for MctsTree {
+    fn default() -> Self {
+        MctsTree::new(MctsConfig::default())
+    }
+}
+
+// One tree per thread, splitting a session's nodes between them
+#[cfg(feature = "parallel")]
+pub(crate) fn resize_trees(trees: &mut Vec<MctsTree>, threads: usize) {
+    let max_nodes = SESSION_NODES / threads.max(1);
+    trees.resize_with(threads.max(1), || {
+        MctsTree::default().with_max_nodes(max_nodes)
+    });
+    for tree in trees.iter_mut() {
+        tree.max_nodes = max_nodes;
+    }
+}
+
+// Plays out the game with the given policy, returning the winner or None for a draw or if it ran too long
+fn simulate<R: Rng>(
+    state: GameState,
+    policy: PlayoutPolicy,
+    max_turns: u32,
+    tablebases: &Tablebases,
+    rng: &mut R,
+) -> Option<Player> {
+    let mut state = state;
+    for _ in 0..max_turns {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { winner, .. } => {
+                return winner;
+            }
+        };
+        if let Some(value) = tablebases.probe(&board) {
+            return value.winner(board.turn);
+        }
+        let game_move = match policy {
+            PlayoutPolicy::Random => board.random_legal_move(rng),
+            PlayoutPolicy::WinningMoves => match winning_move(&board) {
+                Some(game_move) => game_move,
+                None => board.random_legal_move(rng),
+            },
+        };
+        state = state.try_move(game_move).expect("MCTS played illegal move");
+    }
+    None
+}
+
+// A move capturing the opponent's king or moving our king onto their temple arch
+fn winning_move(board: &Board) -> Option<Move> {
+    let pieces = board.pieces(board.turn);
+    let own_king = board.king(board.turn) & pieces;
+    let targets = board.king(board.turn.invert()) | (1 << goal_square(board.turn));
+    for card in board.player_hand() {
+        for src in squares(pieces) {
+            let mut destinations = move_mask(*card, board.turn, src) & targets & !pieces;
+            if own_king & (1 << src) == 0 {
+                destinations &= board.king(board.turn.invert());
+            }
+            if let Some(dst) = squares(destinations).next() {
+                return Some(Move::Move {
+                    card: *card,
+                    src: point_of(src),
+                    dst: point_of(dst),
+                });
+            }
+        }
+    }
+    None
+}
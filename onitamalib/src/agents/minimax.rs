@@ -1,14 +1,31 @@
 use instant::{Duration, Instant};
 
+use crate::agents::heuristics::EvalWeights;
+use crate::agents::progress::{SearchInfo, SearchObserver};
 use crate::models::{GameState, Move, Player};
 
 const MAX_DEPTH: u16 = 50;
-pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+pub fn iterative_deepening(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+) -> Option<(Move, i64)> {
+    iterative_deepening_observed(state, duration, weights, &mut |_: &SearchInfo| {})
+}
+
+// Without a table there's no line to follow past the best move
+pub fn iterative_deepening_observed(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
     let start = Instant::now();
     let deadline = start + duration;
+    let mut nodes = 0;
     let mut result: Option<(Move, i64)> = None;
     for depth in 1..MAX_DEPTH {
-        match optimal_move_deadline(state, depth, deadline) {
+        match optimal_move_deadline(state, depth, deadline, weights, &mut nodes) {
             None => {
                 log::info!(
                     "Timeout at depth {}, took {}ms",
@@ -18,6 +35,14 @@ pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Mov
                 break;
             }
             Some(val) => {
+                let (game_move, score) = val;
+                observer.on_depth(&SearchInfo {
+                    depth,
+                    score,
+                    nodes,
+                    time: start.elapsed().as_millis() as u64,
+                    pv: vec![game_move],
+                });
                 result = Some(val);
             }
         };
@@ -25,7 +50,13 @@ pub fn iterative_deepening(state: &GameState, duration: Duration) -> Option<(Mov
     return result;
 }
 
-fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Option<(Move, i64)> {
+fn optimal_move_deadline(
+    state: &GameState,
+    depth: u16,
+    deadline: Instant,
+    weights: &EvalWeights,
+    nodes: &mut u64,
+) -> Option<(Move, i64)> {
     let timedout = || Instant::now() > deadline;
     let board = match state {
         GameState::Playing { board } => board,
@@ -42,13 +73,13 @@ fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Op
     let mut game_moves = board.legal_moves().into_iter();
     let mut best_move = game_moves.next().expect("No legal moves");
     let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1);
+    let mut best_score = minimax_counted(&state, depth - 1, weights, nodes);
     for game_move in game_moves {
         if timedout() {
             return None;
         }
         let state = board.try_move(game_move).expect("generated illegal move");
-        let expected_score = minimax(&state, depth - 1);
+        let expected_score = minimax_counted(&state, depth - 1, weights, nodes);
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -64,7 +95,7 @@ fn optimal_move_deadline(state: &GameState, depth: u16, deadline: Instant) -> Op
     return Some((best_move, best_score));
 }
 
-pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
+pub fn optimal_move(state: &GameState, depth: u16, weights: &EvalWeights) -> Option<(Move, i64)> {
     let board = match state {
         GameState::Playing { board } => board,
         GameState::Finished { .. } => {
@@ -77,10 +108,10 @@ pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
     let mut game_moves = board.legal_moves().into_iter();
     let mut best_move = game_moves.next()?;
     let state = board.try_move(best_move).expect("generated illegal move");
-    let mut best_score = minimax(&state, depth - 1);
+    let mut best_score = minimax(&state, depth - 1, weights);
     for game_move in game_moves {
         let state = board.try_move(game_move).expect("generated illegal move");
-        let expected_score = minimax(&state, depth - 1);
+        let expected_score = minimax(&state, depth - 1, weights);
         match board.turn {
             Player::Red if expected_score > best_score => {
                 best_move = game_move;
@@ -96,19 +127,24 @@ pub fn optimal_move(state: &GameState, depth: u16) -> Option<(Move, i64)> {
     return Some((best_move, best_score));
 }
 
-pub fn minimax(state: &GameState, depth: u16) -> i64 {
+pub fn minimax(state: &GameState, depth: u16, weights: &EvalWeights) -> i64 {
+    minimax_counted(state, depth, weights, &mut 0)
+}
+
+fn minimax_counted(state: &GameState, depth: u16, weights: &EvalWeights, nodes: &mut u64) -> i64 {
+    *nodes += 1;
     if depth == 0 {
-        return state.basic_value();
+        return state.evaluate(weights);
     }
     let board = match state {
         GameState::Playing { board } => board,
         GameState::Finished { .. } => {
-            return state.basic_value();
+            return state.evaluate(weights);
         }
     };
     let expected_scores = board.legal_moves().into_iter().map(|game_move| {
         let state = board.try_move(game_move).expect("illegal move generated");
-        minimax(&state, depth - 1)
+        minimax_counted(&state, depth - 1, weights, nodes)
     });
     match board.turn {
         Player::Red => expected_scores.max().expect("no expected_scores"),
@@ -1,9 +1,18 @@
 pub mod agents;
 pub mod alphabeta;
+pub mod analysis;
 pub mod greedy;
 pub mod heuristics;
+pub mod mcts;
 pub mod minimax;
 pub mod montecarlo;
 pub mod move_gen;
+pub mod ordering;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod progress;
+pub mod transposition;
 
-pub use agents::AiAgent;
+pub use agents::{AgentSession, AiAgent};
+pub use analysis::{analyse, Analysis, AnalysisRequest, RankedMove};
+pub use progress::{SearchInfo, SearchObserver};
@@ -3,12 +3,22 @@ use std::cell::Cell;
 use instant::{Duration, Instant};
 use rand::prelude::*;
 
+use crate::agents::heuristics::EvalWeights;
+use crate::agents::progress::{SearchInfo, SearchObserver, REPORT_INTERVAL};
+use crate::tablebase::{self, table_move, Tablebases};
 use crate::{alphabeta, Board, GameState, Move, Player};
 
-pub fn hybrid_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+pub fn hybrid_montecarlo_agent(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+) -> Option<(Move, i64)> {
     log::debug!("Game State: {:?}", state);
+    if let Some(result) = table_move(state) {
+        return Some(result);
+    }
     let alphabeta_duration = duration / 2;
-    let moves_scored = match alphabeta::moves_scored_deepening(state, alphabeta_duration) {
+    let moves_scored = match alphabeta::moves_scored_deepening(state, alphabeta_duration, weights) {
         None => {
             return None;
         }
@@ -80,10 +90,41 @@ pub fn hybrid_montecarlo_agent(state: &GameState, duration: Duration) -> Option<
             },
         )
 }
-pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+pub fn hybrid_hard_montecarlo_agent(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+) -> Option<(Move, i64)> {
+    hybrid_hard_montecarlo_observed(state, duration, weights, &mut |_: &SearchInfo| {})
+}
+
+// Only the playouts are reported, alphabeta's share of the time just narrows the moves
+pub fn hybrid_hard_montecarlo_observed(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
+    hybrid_hard_montecarlo_with(state, duration, weights, observer, montecarlo_observed)
+}
+
+// Scores moves still in contention using the given playout runner
+pub(crate) fn hybrid_hard_montecarlo_with<F>(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    observer: &mut dyn SearchObserver,
+    montecarlo: F,
+) -> Option<(Move, i64)>
+where
+    F: FnOnce(&Board, Vec<Move>, Duration, &mut dyn SearchObserver) -> Vec<(Move, i64)>,
+{
     log::debug!("Game State: {:?}", state);
+    if let Some(result) = table_move(state) {
+        return Some(result);
+    }
     let alphabeta_duration = duration / 2;
-    let moves_scored = match alphabeta::moves_scored_deepening(state, alphabeta_duration) {
+    let moves_scored = match alphabeta::moves_scored_deepening(state, alphabeta_duration, weights) {
         None => {
             return None;
         }
@@ -146,7 +187,7 @@ pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Op
         return Some((moves[0], 0));
     }
     let monte_carlo_duration = duration / 2;
-    let scored_moves = montecarlo(board, moves, monte_carlo_duration);
+    let scored_moves = montecarlo(board, moves, monte_carlo_duration, observer);
     let compare = match board.turn {
         Player::Red => |a, b| a > b,
         Player::Blue => |a, b| a < b,
@@ -168,16 +209,17 @@ pub fn hybrid_hard_montecarlo_agent(state: &GameState, duration: Duration) -> Op
 pub fn hybrid_hard_montecarlo_rank_moves(
     state: &GameState,
     duration: Duration,
+    weights: &EvalWeights,
 ) -> Option<Vec<(Move, i64)>> {
     log::debug!("Game State: {:?}", state);
     let alphabeta_duration = duration / 2;
-    let alphabeta_scored_moves = match alphabeta::moves_scored_deepening(state, alphabeta_duration)
-    {
-        None => {
-            return None;
-        }
-        Some(val) => val,
-    };
+    let alphabeta_scored_moves =
+        match alphabeta::moves_scored_deepening(state, alphabeta_duration, weights) {
+            None => {
+                return None;
+            }
+            Some(val) => val,
+        };
     let board = match state {
         GameState::Playing { board } => board,
         GameState::Finished { .. } => {
@@ -212,7 +254,17 @@ pub fn hybrid_hard_montecarlo_rank_moves(
 
 const ITERATIONS_PER_TIME_CHECK: u8 = 50;
 
-fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move, i64)> {
+pub(crate) fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move, i64)> {
+    montecarlo_observed(board, moves, duration, &mut |_: &SearchInfo| {})
+}
+
+// Reports the best move so far every REPORT_INTERVAL
+pub(crate) fn montecarlo_observed(
+    board: &Board,
+    moves: Vec<Move>,
+    duration: Duration,
+    observer: &mut dyn SearchObserver,
+) -> Vec<(Move, i64)> {
     let start = Instant::now();
     let deadline = start + duration;
     let timedout = || Instant::now() > deadline;
@@ -220,6 +272,7 @@ fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move,
         .into_iter()
         .map(|game_move| (game_move, Cell::new(0i64)))
         .collect();
+    let tablebases = tablebase::installed();
     let mut simulations = 0u64;
     cfg_if::cfg_if! {
         if #[cfg(feature = "web")] {
@@ -228,13 +281,14 @@ fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move,
             let mut rng = thread_rng();
         }
     }
+    let mut next_report = start + REPORT_INTERVAL;
     while !timedout() {
         for _ in 0..ITERATIONS_PER_TIME_CHECK {
             for (game_move, score) in results.iter() {
                 simulations += 1;
                 let state = board.try_move(*game_move).expect("illegal move");
                 let new_score = score.get()
-                    + match simulate(state, &mut rng) {
+                    + match simulate(state, &tablebases, &mut rng) {
                         Some(Player::Red) => 1,
                         Some(Player::Blue) => -1,
                         None => 0,
@@ -242,6 +296,22 @@ fn montecarlo(board: &Board, moves: Vec<Move>, duration: Duration) -> Vec<(Move,
                 score.set(new_score);
             }
         }
+        if Instant::now() >= next_report {
+            next_report += REPORT_INTERVAL;
+            let scored: Vec<(Move, i64)> = results
+                .iter()
+                .map(|(game_move, score)| (*game_move, score.get()))
+                .collect();
+            if let Some((game_move, score)) = best_scored(board, scored) {
+                observer.on_depth(&SearchInfo {
+                    depth: 1,
+                    score,
+                    nodes: simulations,
+                    time: start.elapsed().as_millis() as u64,
+                    pv: vec![game_move],
+                });
+            }
+        }
     }
     log::info!("Monte-carlo timed out after {} simulations", simulations);
     return results
@@ -259,6 +329,7 @@ pub fn montecarlo_count_simulations(board: &Board, moves: Vec<Move>, duration: D
         .into_iter()
         .map(|game_move| (game_move, Cell::new(0i64)))
         .collect();
+    let tablebases = tablebase::installed();
     let mut simulations = 0u64;
     cfg_if::cfg_if! {
         if #[cfg(feature = "web")] {
@@ -273,7 +344,7 @@ pub fn montecarlo_count_simulations(board: &Board, moves: Vec<Move>, duration: D
                 simulations += 1;
                 let state = board.try_move(*game_move).expect("illegal move");
                 let new_score = score.get()
-                    + match simulate(state, &mut rng) {
+                    + match simulate(state, &tablebases, &mut rng) {
                         Some(Player::Red) => 1,
                         Some(Player::Blue) => -1,
                         None => 0,
@@ -287,12 +358,40 @@ pub fn montecarlo_count_simulations(board: &Board, moves: Vec<Move>, duration: D
 }
 
 pub fn pure_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(Move, i64)> {
+    pure_montecarlo_observed(state, duration, &mut |_: &SearchInfo| {})
+}
+
+pub fn pure_montecarlo_observed(
+    state: &GameState,
+    duration: Duration,
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
+    pure_montecarlo_with(state, duration, observer, montecarlo_observed)
+}
+
+pub(crate) fn pure_montecarlo_with<F>(
+    state: &GameState,
+    duration: Duration,
+    observer: &mut dyn SearchObserver,
+    montecarlo: F,
+) -> Option<(Move, i64)>
+where
+    F: FnOnce(&Board, Vec<Move>, Duration, &mut dyn SearchObserver) -> Vec<(Move, i64)>,
+{
+    if let Some(result) = table_move(state) {
+        return Some(result);
+    }
     let board = match state {
         GameState::Playing { board, .. } => Some(*board),
         GameState::Finished { .. } => None,
     }?;
     let moves = board.legal_moves();
-    let scored_moves = montecarlo(&board, moves, duration);
+    let scored_moves = montecarlo(&board, moves, duration, observer);
+    best_scored(&board, scored_moves)
+}
+
+// The move scoring best for the player to move
+fn best_scored(board: &Board, scored_moves: Vec<(Move, i64)>) -> Option<(Move, i64)> {
     let compare = match board.turn {
         Player::Red => |a, b| a > b,
         Player::Blue => |a, b| a < b,
@@ -308,15 +407,19 @@ pub fn pure_montecarlo_agent(state: &GameState, duration: Duration) -> Option<(M
 }
 
 // Choose random moves and return the player that one, or None if loop
-fn simulate<R: Rng>(state: GameState, rng: &mut R) -> Option<Player> {
+// Stops early once the position is in a tablebase, returning its winner with best play
+fn simulate<R: Rng>(state: GameState, tablebases: &Tablebases, rng: &mut R) -> Option<Player> {
     let mut state = state;
     for _ in 0..1000 {
         let board = match state {
             GameState::Playing { board } => board,
             GameState::Finished { winner, .. } => {
-                return Some(winner);
+                return winner;
             }
         };
+        if let Some(value) = tablebases.probe(&board) {
+            return value.winner(board.turn);
+        }
         let game_move = board.random_legal_move(rng);
         state = state
             .try_move(game_move)
@@ -1,68 +1,67 @@
-use crate::models::{Board, Move, Player};
+use crate::bitboard::{move_mask, point_of, squares};
+use crate::models::{Board, Move};
 use rand::prelude::*;
 
 impl Board {
     pub fn legal_moves(&self) -> Vec<Move> {
+        let player_pieces = self.pieces(self.turn);
+        let opponent_pieces = self.pieces(self.turn.invert());
+        // Captures are searched first
         let mut moves = vec![];
-        let pieces = self.player_pieces();
+        let mut quiet_moves = vec![];
         for card in self.player_hand() {
-            for src in self.player_pieces().iter().filter_map(|p| *p) {
-                for offset in card.moves() {
-                    let offset = match self.turn {
-                        Player::Red => offset,
-                        Player::Blue => -offset,
+            for src in squares(player_pieces) {
+                let destinations = move_mask(*card, self.turn, src) & !player_pieces;
+                for dst in squares(destinations) {
+                    let game_move = Move::Move {
+                        card: *card,
+                        src: point_of(src),
+                        dst: point_of(dst),
+                    };
+                    match opponent_pieces & (1 << dst) {
+                        0 => quiet_moves.push(game_move),
+                        _ => moves.push(game_move),
                     };
-                    let dst = src + offset;
-                    if dst.in_bounds() && !pieces.contains(&Some(dst)) {
-                        moves.push(Move::Move {
-                            card: *card,
-                            src,
-                            dst,
-                        });
-                    }
                 }
             }
         }
-        if moves.len() > 0 {
-            let opponent_pieces = self.opponent_pieces();
-            let key = |game_move: &Move| match game_move {
-                Move::Move { dst, .. } => match opponent_pieces.contains(&Some(*dst)) {
-                    true => 0,
-                    false => 1,
-                },
-                Move::Discard { .. } => 0,
-            };
-            moves.sort_by_cached_key(key);
+        moves.append(&mut quiet_moves);
+        if !moves.is_empty() {
             return moves;
         }
         // No moves, have to discard
-        return self
-            .player_hand()
+        self.player_hand()
             .iter()
             .map(|card| Move::Discard { card: *card })
-            .collect();
+            .collect()
     }
     pub fn random_legal_move<R: Rng>(&self, rng: &mut R) -> Move {
         let mut cards = *self.player_hand();
         cards.shuffle(rng);
-        let mut player_pieces = self.player_pieces();
-        player_pieces.shuffle(rng);
+        let player_pieces = self.pieces(self.turn);
+        let mut sources = [0u32; 5];
+        let mut source_count = 0;
+        for src in squares(player_pieces) {
+            sources[source_count] = src;
+            source_count += 1;
+        }
+        let sources = &mut sources[..source_count];
+        sources.shuffle(rng);
         for card in cards {
-            let mut moves = card.moves();
-            moves.shuffle(rng);
-            for src in player_pieces.iter().filter_map(|p| *p) {
-                for offset in moves.iter() {
-                    let offset = match self.turn {
-                        Player::Red => *offset,
-                        Player::Blue => -*offset,
-                    };
-                    let dst = src + offset;
-                    if dst.in_bounds() && !player_pieces.contains(&Some(dst)) {
-                        return Move::Move { card, src, dst };
-                    }
+            for src in sources.iter() {
+                let destinations = move_mask(card, self.turn, *src) & !player_pieces;
+                if destinations == 0 {
+                    continue;
                 }
+                let choice = rng.gen_range(0..destinations.count_ones()) as usize;
+                let dst = squares(destinations).nth(choice).unwrap();
+                return Move::Move {
+                    card,
+                    src: point_of(*src),
+                    dst: point_of(dst),
+                };
             }
         }
-        return Move::Discard { card: cards[0] };
+        Move::Discard { card: cards[0] }
     }
 }
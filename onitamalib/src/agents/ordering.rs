@@ -0,0 +1,111 @@
+use std::cmp::Reverse;
+
+use crate::bitboard::{bit_of, goal_square, player_index, square_of};
+use crate::models::{Board, Move};
+
+const KING_CAPTURE_SCORE: u32 = 4_000_000;
+const TEMPLE_SCORE: u32 = 3_000_000;
+const CAPTURE_SCORE: u32 = 2_000_000;
+const KILLER_SCORES: [u32; 2] = [1_500_000, 1_400_000];
+// Keeps history scores below killer moves
+const HISTORY_LIMIT: u32 = 1_000_000;
+
+/// Orders moves so alphabeta finds cutoffs early:
+/// the table's best move, then wins and captures, then killer moves and
+/// finally quiet moves by how often they caused cutoffs elsewhere in the tree.
+pub struct MoveOrdering {
+    // Two quiet moves per ply that recently caused a beta cutoff
+    killers: Vec<[Option<Move>; 2]>,
+    // Indexed by player, source square and destination square
+    history: [[[u32; 25]; 25]; 2],
+    enabled: bool,
+}
+
+impl MoveOrdering {
+    pub fn new() -> MoveOrdering {
+        MoveOrdering {
+            killers: vec![],
+            history: [[[0; 25]; 25]; 2],
+            enabled: true,
+        }
+    }
+    // Only puts the table move first, leaving everything else in generation order
+    #[cfg(test)]
+    pub fn disabled() -> MoveOrdering {
+        MoveOrdering {
+            enabled: false,
+            ..MoveOrdering::new()
+        }
+    }
+    pub fn order(&self, board: &Board, moves: &mut [Move], table_move: Option<Move>, ply: usize) {
+        if self.enabled {
+            moves.sort_by_cached_key(|game_move| Reverse(self.score(board, game_move, ply)));
+        }
+        table_move_first(moves, table_move);
+    }
+    fn score(&self, board: &Board, game_move: &Move, ply: usize) -> u32 {
+        let (src, dst) = match game_move {
+            Move::Move { src, dst, .. } => (*src, *dst),
+            Move::Discard { .. } => {
+                return 0;
+            }
+        };
+        let opponent = board.turn.invert();
+        let dst_bit = bit_of(dst);
+        if board.king(opponent) & dst_bit != 0 {
+            return KING_CAPTURE_SCORE;
+        }
+        let moving_king = board.king(board.turn) & bit_of(src) != 0;
+        if moving_king && square_of(dst) == goal_square(board.turn) {
+            return TEMPLE_SCORE;
+        }
+        if board.pieces(opponent) & dst_bit != 0 {
+            return CAPTURE_SCORE;
+        }
+        if let Some(killers) = self.killers.get(ply) {
+            for (killer, score) in killers.iter().zip(KILLER_SCORES) {
+                if *killer == Some(*game_move) {
+                    return score;
+                }
+            }
+        }
+        self.history[player_index(board.turn)][square_of(src) as usize][square_of(dst) as usize]
+    }
+    // Called when a move caused a cutoff, only quiet moves are remembered
+    pub fn record_cutoff(&mut self, board: &Board, game_move: Move, depth: u16, ply: usize) {
+        let (src, dst) = match game_move {
+            Move::Move { src, dst, .. } => (src, dst),
+            Move::Discard { .. } => {
+                return;
+            }
+        };
+        if board.pieces(board.turn.invert()) & bit_of(dst) != 0 {
+            return;
+        }
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(game_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(game_move);
+        }
+        let history = &mut self.history[player_index(board.turn)][square_of(src) as usize]
+            [square_of(dst) as usize];
+        *history = (*history + (depth as u32) * (depth as u32)).min(HISTORY_LIMIT);
+    }
+}
+
+pub fn table_move_first(moves: &mut [Move], table_move: Option<Move>) {
+    if let Some(table_move) = table_move {
+        if let Some(idx) = moves.iter().position(|m| *m == table_move) {
+            moves[..=idx].rotate_right(1);
+        }
+    }
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering::new()
+    }
+}
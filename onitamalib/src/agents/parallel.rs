@@ -0,0 +1,234 @@
+// Multi-threaded versions of the agents, for native builds only
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use instant::{Duration, Instant};
+
+use crate::agents::alphabeta::{self, Search};
+use crate::agents::heuristics::EvalWeights;
+use crate::agents::mcts::{self, MctsTree};
+use crate::agents::montecarlo;
+use crate::agents::progress::{SearchInfo, SearchObserver};
+use crate::agents::transposition::{SharedTranspositionTable, TABLE_BITS};
+use crate::{Board, GameState, Move};
+
+/// Lazy SMP: every thread runs its own iterative deepening, sharing one transposition table.
+/// Helper threads start one depth further on every other thread, so they fill in the table
+/// ahead of the main thread rather than repeating its work.
+pub fn alphabeta_threads(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    threads: usize,
+) -> Option<(Move, i64)> {
+    let mut ignore = |_: &SearchInfo| {};
+    alphabeta_threads_observed(state, duration, weights, threads, &mut ignore)
+}
+
+// Only the main thread's depths are reported, the helpers run ahead of it out of order
+pub fn alphabeta_threads_observed(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    threads: usize,
+    observer: &mut (dyn SearchObserver + Send),
+) -> Option<(Move, i64)> {
+    if threads <= 1 {
+        return alphabeta::iterative_deepening_observed(state, duration, weights, observer);
+    }
+    let deadline = Instant::now() + duration;
+    let table = Arc::new(SharedTranspositionTable::new(TABLE_BITS));
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut observer = Some(observer);
+    let results: Vec<Option<(u16, (Move, i64))>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|idx| {
+                let table = Arc::clone(&table);
+                let stop = Arc::clone(&stop);
+                let observer = observer.take();
+                scope.spawn(move || {
+                    let mut search = Search::with_table(*weights, table).stop_on(Arc::clone(&stop));
+                    let first_depth = 1 + (idx % 2) as u16;
+                    let result = match observer {
+                        Some(observer) => alphabeta::deepen_observed(
+                            state,
+                            deadline,
+                            first_depth,
+                            alphabeta::MAX_DEPTH - 1,
+                            &mut search,
+                            observer,
+                        ),
+                        None => alphabeta::deepen(state, deadline, first_depth, &mut search),
+                    };
+                    // Helpers stop when the main thread does, such as when it finds a forced win
+                    if idx == 0 {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    result
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Search thread panicked"))
+            .collect()
+    });
+    // Take the deepest completed search, preferring the main thread's on ties
+    let mut best: Option<(u16, (Move, i64))> = None;
+    for result in results.into_iter().flatten() {
+        match best {
+            Some((depth, _)) if depth >= result.0 => {}
+            _ => {
+                best = Some(result);
+            }
+        };
+    }
+    best.map(|(_, result)| result)
+}
+
+/// Root-parallel playouts: each thread scores every move, and the scores are summed
+pub fn montecarlo_threads(
+    board: &Board,
+    moves: Vec<Move>,
+    duration: Duration,
+    threads: usize,
+) -> Vec<(Move, i64)> {
+    let mut ignore = |_: &SearchInfo| {};
+    montecarlo_threads_observed(board, moves, duration, threads, &mut ignore)
+}
+
+// The calling thread runs its share of the playouts, and only its own are reported
+fn montecarlo_threads_observed(
+    board: &Board,
+    moves: Vec<Move>,
+    duration: Duration,
+    threads: usize,
+    observer: &mut dyn SearchObserver,
+) -> Vec<(Move, i64)> {
+    if threads <= 1 {
+        return montecarlo::montecarlo_observed(board, moves, duration, observer);
+    }
+    let results: Vec<Vec<(Move, i64)>> = thread::scope(|scope| {
+        let handles: Vec<_> = (1..threads)
+            .map(|_| {
+                let moves = moves.clone();
+                scope.spawn(move || montecarlo::montecarlo(board, moves, duration))
+            })
+            .collect();
+        let own = montecarlo::montecarlo_observed(board, moves.clone(), duration, observer);
+        std::iter::once(own)
+            .chain(
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("Playout thread panicked")),
+            )
+            .collect()
+    });
+    let mut totals: Vec<(Move, i64)> = moves.into_iter().map(|game_move| (game_move, 0)).collect();
+    for scored_moves in results {
+        for ((_, total), (_, score)) in totals.iter_mut().zip(scored_moves) {
+            *total += score;
+        }
+    }
+    totals
+}
+
+pub fn pure_montecarlo_threads(
+    state: &GameState,
+    duration: Duration,
+    threads: usize,
+) -> Option<(Move, i64)> {
+    pure_montecarlo_threads_observed(state, duration, threads, &mut |_: &SearchInfo| {})
+}
+
+pub fn pure_montecarlo_threads_observed(
+    state: &GameState,
+    duration: Duration,
+    threads: usize,
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
+    montecarlo::pure_montecarlo_with(
+        state,
+        duration,
+        observer,
+        |board, moves, duration, observer| {
+            montecarlo_threads_observed(board, moves, duration, threads, observer)
+        },
+    )
+}
+
+// Only the playouts run in parallel, alphabeta's share of the time stays single-threaded
+pub fn hybrid_hard_montecarlo_threads(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    threads: usize,
+) -> Option<(Move, i64)> {
+    let mut ignore = |_: &SearchInfo| {};
+    hybrid_hard_montecarlo_threads_observed(state, duration, weights, threads, &mut ignore)
+}
+
+pub fn hybrid_hard_montecarlo_threads_observed(
+    state: &GameState,
+    duration: Duration,
+    weights: &EvalWeights,
+    threads: usize,
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
+    montecarlo::hybrid_hard_montecarlo_with(
+        state,
+        duration,
+        weights,
+        observer,
+        |board, moves, duration, observer| {
+            montecarlo_threads_observed(board, moves, duration, threads, observer)
+        },
+    )
+}
+
+/// Root-parallel tree search: one tree per thread, with root statistics merged at the end.
+/// Trees are kept by the caller so each one is reused on the next turn.
+pub fn mcts_threads(
+    state: &GameState,
+    duration: Duration,
+    trees: &mut [MctsTree],
+) -> Option<(Move, i64)> {
+    mcts_threads_observed(state, duration, trees, &mut |_: &SearchInfo| {})
+}
+
+// The calling thread searches the first tree, and only that tree is reported
+pub fn mcts_threads_observed(
+    state: &GameState,
+    duration: Duration,
+    trees: &mut [MctsTree],
+    observer: &mut dyn SearchObserver,
+) -> Option<(Move, i64)> {
+    let board = match state {
+        GameState::Playing { board } => *board,
+        GameState::Finished { .. } => {
+            return None;
+        }
+    };
+    let deadline = Instant::now() + duration;
+    thread::scope(|scope| {
+        let (own, helpers) = trees.split_first_mut().expect("No search trees");
+        for tree in helpers.iter_mut() {
+            scope.spawn(move || tree.search(state, deadline, &mut |_: &SearchInfo| {}));
+        }
+        own.search(state, deadline, observer);
+    });
+    let mut stats: Vec<(Move, u32, f64)> = vec![];
+    for tree in trees.iter() {
+        for (game_move, visits, wins) in tree.root_stats() {
+            match stats.iter_mut().find(|(merged, _, _)| *merged == game_move) {
+                Some((_, total_visits, total_wins)) => {
+                    *total_visits += visits;
+                    *total_wins += wins;
+                }
+                None => stats.push((game_move, visits, wins)),
+            };
+        }
+    }
+    mcts::best_move(&board, stats)
+}
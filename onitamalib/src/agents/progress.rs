@@ -0,0 +1,62 @@
+// Progress of a search, reported as iterative deepening completes each depth,
+// or every so often by the agents running playouts
+use std::fmt;
+
+use instant::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::Move;
+
+// How often the playout agents report, they have no depths to finish
+pub(crate) const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchInfo {
+    // For the playout agents, the length of the line they are most sure of
+    pub depth: u16,
+    // Positive favours red, i64::MAX and i64::MIN are forced wins for red and blue
+    pub score: i64,
+    // Positions searched, or playouts run
+    pub nodes: u64,
+    // Milliseconds since the search started
+    pub time: u64,
+    // The best line found, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+}
+
+// Reads as "depth 7, +14, PV: Tiger c1-c3 Frog a5-b4"
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "depth {}, ", self.depth)?;
+        match self.score {
+            i64::MAX => write!(f, "red wins")?,
+            i64::MIN => write!(f, "blue wins")?,
+            score => write!(f, "{:+}", score)?,
+        };
+        let line: Vec<String> = self
+            .pv
+            .iter()
+            .map(|game_move| game_move.to_string())
+            .collect();
+        write!(f, ", PV: {}", line.join(" "))
+    }
+}
+
+/// Told about each depth a search completes, or the playouts so far,
+/// any closure taking a SearchInfo will do
+pub trait SearchObserver {
+    fn on_depth(&mut self, info: &SearchInfo);
+}
+
+impl<F: FnMut(&SearchInfo)> SearchObserver for F {
+    fn on_depth(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(feature = "parallel")]
+use std::sync::{Arc, Mutex};
+
+use crate::models::Move;
+
+// 2^18 entries, roughly 8MB
+pub const TABLE_BITS: u32 = 18;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Bound {
+    Exact,
+    // The score is at least this value (search failed high)
+    Lower,
+    // The score is at most this value (search failed low)
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TableEntry {
+    pub hash: u64,
+    pub depth: u16,
+    pub score: i64,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+// Storage for search results, either owned by one search or shared between threads
+pub trait Table {
+    fn probe(&self, hash: u64) -> Option<TableEntry>;
+    fn store(&mut self, entry: TableEntry);
+}
+
+// Deeper searches of another position aren't replaced
+fn replaces(existing: &Option<TableEntry>, entry: &TableEntry) -> bool {
+    match existing {
+        Some(existing) => existing.hash == entry.hash || existing.depth <= entry.depth,
+        None => true,
+    }
+}
+
+/// Fixed-size table of search results keyed by zobrist hash.
+/// Each hash maps to one slot, and deeper searches win when two positions collide.
+pub struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+    mask: u64,
+}
+
+impl TranspositionTable {
+    pub fn new(bits: u32) -> TranspositionTable {
+        let size = 1usize << bits;
+        TranspositionTable {
+            entries: vec![None; size],
+            mask: (size - 1) as u64,
+        }
+    }
+    pub fn probe(&self, hash: u64) -> Option<TableEntry> {
+        match self.entries[(hash & self.mask) as usize] {
+            Some(entry) if entry.hash == hash => Some(entry),
+            _ => None,
+        }
+    }
+    pub fn store(&mut self, entry: TableEntry) {
+        let slot = &mut self.entries[(entry.hash & self.mask) as usize];
+        if replaces(slot, &entry) {
+            *slot = Some(entry);
+        }
+    }
+}
+
+impl Table for TranspositionTable {
+    fn probe(&self, hash: u64) -> Option<TableEntry> {
+        TranspositionTable::probe(self, hash)
+    }
+    fn store(&mut self, entry: TableEntry) {
+        TranspositionTable::store(self, entry)
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(TABLE_BITS)
+    }
+}
+
+/// Transposition table that several search threads read and write at once.
+/// Each slot has its own lock, so threads only contend when they hit the same slot.
+#[cfg(feature = "parallel")]
+pub struct SharedTranspositionTable {
+    entries: Vec<Mutex<Option<TableEntry>>>,
+    mask: u64,
+}
+
+#[cfg(feature = "parallel")]
+impl SharedTranspositionTable {
+    pub fn new(bits: u32) -> SharedTranspositionTable {
+        let size = 1usize << bits;
+        SharedTranspositionTable {
+            entries: (0..size).map(|_| Mutex::new(None)).collect(),
+            mask: (size - 1) as u64,
+        }
+    }
+    fn slot(&self, hash: u64) -> &Mutex<Option<TableEntry>> {
+        &self.entries[(hash & self.mask) as usize]
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Table for Arc<SharedTranspositionTable> {
+    fn probe(&self, hash: u64) -> Option<TableEntry> {
+        match *self.slot(hash).lock().expect("Table lock poisoned") {
+            Some(entry) if entry.hash == hash => Some(entry),
+            _ => None,
+        }
+    }
+    fn store(&mut self, entry: TableEntry) {
+        let mut slot = self.slot(entry.hash).lock().expect("Table lock poisoned");
+        if replaces(&slot, &entry) {
+            *slot = Some(entry);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::env;
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 
@@ -6,12 +7,13 @@ use enum_iterator::IntoEnumIterator;
 use indicatif::ProgressBar;
 use instant::Duration;
 
-use onitamalib::{AiAgent, GameState, Player};
+use onitamalib::history::PositionHistory;
+use onitamalib::{AgentSession, AiAgent, GameState, Player, SearchInfo};
 use std::thread::JoinHandle;
 
 const TURN_DURATION: Duration = Duration::from_millis(100);
 const MATCH_REPEATS: u64 = 10;
-const MAX_TURNS: u64 = 250;
+const MAX_TURNS: u32 = 250;
 const PARALLELISM: u64 = 14;
 
 struct Match {
@@ -26,28 +28,41 @@ impl Match {
             Player::Blue => self.blue,
         }
     }
-    pub fn winner(&self) -> Option<AiAgent> {
+    // The winning agent, or None for a draw, printing each move's deepest search when verbose
+    pub fn winner(&self, pb: &ProgressBar, verbose: bool) -> Option<AiAgent> {
         let mut state = GameState::new();
-        let mut iteration = 0u64;
+        // Drawn by repetition, or once MAX_TURNS moves have been played
+        let mut history = PositionHistory::new(&state, Some(MAX_TURNS));
+        // Sessions keep search trees between turns for agents that reuse them
+        let mut red = AgentSession::new(self.red);
+        let mut blue = AgentSession::new(self.blue);
         loop {
-            iteration += 1;
-            if iteration > MAX_TURNS {
-                // println!("\nDeadlock between {:?} and {:?}\n\n", self.blue, self.red);
-                return None;
-            }
             let board = match state {
                 GameState::Playing { board } => board,
                 GameState::Finished { winner, .. } => {
-                    return Some(self.agent_from_player(winner));
+                    return winner.map(|winner| self.agent_from_player(winner));
                 }
             };
-            let agent = self.agent_from_player(board.turn);
-            let (game_move, _) = agent.play_move(&state, TURN_DURATION).unwrap();
-            state = state.try_move(game_move).unwrap();
+            let session = match board.turn {
+                Player::Red => &mut red,
+                Player::Blue => &mut blue,
+            };
+            let mut deepest: Option<SearchInfo> = None;
+            let mut report = |info: &SearchInfo| deepest = Some(info.clone());
+            let (game_move, _) = session
+                .play_move_observed(&state, TURN_DURATION, &mut report)
+                .unwrap();
+            if let (true, Some(info)) = (verbose, deepest) {
+                let agent = self.agent_from_player(board.turn);
+                pb.println(format!("{:?} as {:?}: {}", agent, board.turn, info));
+            }
+            state = history.record(state.try_move(game_move).unwrap());
         }
     }
 }
 fn main() {
+    // With --verbose, alphabeta's searches are printed as "depth 7, +14, PV: ..."
+    let verbose = env::args().any(|arg| arg == "--verbose");
     let mut matches: Vec<Match> = vec![];
     for red in AiAgent::into_enum_iter() {
         for blue in AiAgent::into_enum_iter() {
@@ -64,7 +79,7 @@ fn main() {
     let (tx, rx) = mpsc::channel();
     let handles: Vec<JoinHandle<()>> = (0..PARALLELISM)
         .map(|idx| {
-            let (matches, tx) = (Arc::clone(&matches), tx.clone());
+            let (matches, tx, pb) = (Arc::clone(&matches), tx.clone(), pb.clone());
             thread::spawn(move || {
                 loop {
                     // println!("{}: Getting lock", idx);
@@ -78,7 +93,7 @@ fn main() {
                     // println!("{}: Got matches", idx);
                     drop(c_matches);
                     // println!("{}: Dropped", idx);
-                    let winner = ai_match.winner();
+                    let winner = ai_match.winner(&pb, verbose);
                     // println!("{}: Determined winner", idx);
                     tx.send(winner).unwrap();
                     // println!("{}: Sent", idx);
@@ -89,9 +104,12 @@ fn main() {
     drop(tx);
     pb.tick();
     let mut wins: HashMap<AiAgent, u64> = HashMap::new();
+    let mut draws = 0u64;
     for winner in rx.into_iter() {
         let winner = match winner {
             None => {
+                draws += 1;
+                pb.inc(1);
                 continue;
             }
             Some(winner) => winner,
@@ -110,4 +128,5 @@ fn main() {
     for (agent, won) in wins.iter() {
         println!("{:?} win {}", agent, won);
     }
+    println!("Draws {}", draws);
 }
@@ -0,0 +1,157 @@
+use std::env;
+use std::path::Path;
+use std::process;
+
+use instant::{Duration, Instant};
+use rand::prelude::*;
+
+use onitamalib::alphabeta;
+use onitamalib::book::OpeningBook;
+use onitamalib::heuristics::EvalWeights;
+use onitamalib::history::PositionHistory;
+use onitamalib::{Board, GameState, Move, Player};
+
+const USAGE: &str = "Usage: book <games> <ms per move> <plies> <file>
+Plays games against itself on random deals, and records the moves of the first plies of each
+game with how the game ended. Moves are added to the book in the file if it already exists, for
+example:
+    book 1000 100 8 openings.book";
+
+// Games still going after this many moves are drawn
+const MOVE_LIMIT: u32 = 200;
+// Opening moves are picked from this many of the best
+const VARIETY: usize = 3;
+
+fn exit_usage(message: String) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+// One of the best few moves that doesn't lose, so the book covers more than one line
+fn opening_move<R: Rng>(
+    board: &Board,
+    duration: Duration,
+    weights: &EvalWeights,
+    rng: &mut R,
+) -> Option<Move> {
+    let state = GameState::Playing { board: *board };
+    let scored = alphabeta::moves_scored_deepening(&state, duration, weights)?;
+    let mut scored: Vec<(Move, i64)> = scored
+        .into_iter()
+        .map(|(game_move, score)| match board.turn {
+            Player::Red => (game_move, score),
+            Player::Blue => (game_move, score.saturating_neg()),
+        })
+        .collect();
+    scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    let best = scored.first()?.1;
+    let candidates: Vec<Move> = scored
+        .iter()
+        .take(VARIETY)
+        .filter(|(_, score)| *score == best || *score > i64::MIN + 1)
+        .map(|(game_move, _)| *game_move)
+        .collect();
+    candidates.choose(rng).copied()
+}
+
+// Plays a game, returning the opening positions with the moves made from them, and the winner
+fn play_game<R: Rng>(
+    duration: Duration,
+    plies: usize,
+    weights: &EvalWeights,
+    rng: &mut R,
+) -> (Vec<(Board, Move)>, Option<Player>) {
+    let mut state = GameState::new();
+    let mut history = PositionHistory::new(&state, Some(MOVE_LIMIT));
+    let mut opening = vec![];
+    loop {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { winner, .. } => {
+                return (opening, winner);
+            }
+        };
+        let game_move = match opening.len() < plies {
+            true => opening_move(&board, duration, weights, rng),
+            false => alphabeta::iterative_deepening(&state, duration, weights)
+                .map(|(game_move, _)| game_move),
+        };
+        let game_move = match game_move {
+            Some(game_move) => game_move,
+            None => {
+                return (opening, None);
+            }
+        };
+        if opening.len() < plies {
+            opening.push((board, game_move));
+        }
+        let next = board
+            .try_move(game_move)
+            .expect("Agent played an illegal move");
+        state = history.record(next);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (games, millis, plies, path) = match args.as_slice() {
+        [games, millis, plies, path] => (games, millis, plies, Path::new(path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let games: usize = match games.parse() {
+        Ok(games) => games,
+        Err(_) => exit_usage(format!("Invalid number of games: {}", games)),
+    };
+    let duration = match millis.parse() {
+        Ok(millis) => Duration::from_millis(millis),
+        Err(_) => exit_usage(format!("Invalid time per move: {}", millis)),
+    };
+    let plies: usize = match plies.parse() {
+        Ok(plies) => plies,
+        Err(_) => exit_usage(format!("Invalid number of plies: {}", plies)),
+    };
+    let mut book = match path.exists() {
+        true => match OpeningBook::load(path) {
+            Ok(book) => book,
+            Err(err) => {
+                eprintln!("Failed to load {}: {}", path.display(), err);
+                process::exit(1);
+            }
+        },
+        false => OpeningBook::new(),
+    };
+    let weights = EvalWeights::default();
+    let mut rng = thread_rng();
+    let start = Instant::now();
+    let mut wins = [0; 3];
+    for game in 1..=games {
+        let (opening, winner) = play_game(duration, plies, &weights, &mut rng);
+        for (board, game_move) in opening {
+            book.record(&board, game_move, winner);
+        }
+        match winner {
+            Some(Player::Red) => wins[0] += 1,
+            Some(Player::Blue) => wins[1] += 1,
+            None => wins[2] += 1,
+        };
+        println!(
+            "Game {}/{}: {:?}, {} positions",
+            game,
+            games,
+            winner,
+            book.len()
+        );
+    }
+    if let Err(err) = book.save(path) {
+        eprintln!("Failed to save book: {}", err);
+        process::exit(1);
+    }
+    println!("Red wins: {}", wins[0]);
+    println!("Blue wins: {}", wins[1]);
+    println!("Draws: {}", wins[2]);
+    println!("Positions: {}", book.len());
+    println!("Time: {}ms", start.elapsed().as_millis());
+}
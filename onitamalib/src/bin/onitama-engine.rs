@@ -0,0 +1,266 @@
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use instant::Duration;
+
+use onitamalib::alphabeta::{self, Search, SearchLimits};
+use onitamalib::heuristics::EvalWeights;
+use onitamalib::history::PositionHistory;
+use onitamalib::{Board, GameState, Move, Player, SearchInfo};
+
+// A line-based protocol modelled on UCI, described in ENGINE.md
+const START_LAYOUT: &str = "ppkpp/5/5/5/PPKPP";
+// Share of the remaining clock spent on a move when playing with a clock
+const CLOCK_SHARE: u32 = 20;
+
+struct Engine {
+    state: GameState,
+    // The search in progress, with the flag that stops it and its limits
+    searching: Option<(Arc<AtomicBool>, JoinHandle<()>, SearchLimits)>,
+}
+
+fn describe_score(score: i64, turn: Player) -> String {
+    let score = match turn {
+        Player::Red => score,
+        Player::Blue => score.saturating_neg(),
+    };
+    match score {
+        i64::MAX => "win".to_string(),
+        score if score <= i64::MIN + 1 => "loss".to_string(),
+        score => format!("cp {}", score),
+    }
+}
+
+fn describe_line(line: &[Move]) -> String {
+    let moves: Vec<String> = line.iter().map(|game_move| game_move.to_string()).collect();
+    moves.join(" ")
+}
+
+// Moves are two words each, `Tiger c1-c3` or `discard Tiger`
+fn parse_moves(words: &[&str]) -> Result<Vec<Move>, String> {
+    words
+        .chunks(2)
+        .map(|pair| match pair {
+            [card, squares] => format!("{} {}", card, squares).parse(),
+            _ => Err(format!("Incomplete move: {}", pair[0])),
+        })
+        .collect()
+}
+
+fn parse_number<N: std::str::FromStr>(name: &str, value: Option<&&str>) -> Result<N, String> {
+    match value.map(|value| value.parse()) {
+        Some(Ok(number)) => Ok(number),
+        _ => Err(format!("Invalid value for {}", name)),
+    }
+}
+
+fn parse_limits(words: &[&str], turn: Player) -> Result<SearchLimits, String> {
+    let mut limits = SearchLimits::default();
+    let mut clock: Option<u64> = None;
+    let mut increment: u64 = 0;
+    let mut words = words.iter();
+    while let Some(word) = words.next() {
+        match (*word, turn) {
+            ("movetime", _) => {
+                let millis = parse_number(word, words.next())?;
+                limits.time = Some(Duration::from_millis(millis));
+            }
+            ("depth", _) => {
+                limits.depth = Some(parse_number(word, words.next())?);
+            }
+            ("nodes", _) => {
+                limits.nodes = Some(parse_number(word, words.next())?);
+            }
+            ("infinite", _) => {}
+            ("rtime", Player::Red) | ("btime", Player::Blue) => {
+                clock = Some(parse_number(word, words.next())?);
+            }
+            ("rinc", Player::Red) | ("binc", Player::Blue) => {
+                increment = parse_number(word, words.next())?;
+            }
+            ("rtime", _) | ("btime", _) | ("rinc", _) | ("binc", _) => {
+                parse_number::<u64>(word, words.next())?;
+            }
+            _ => {
+                return Err(format!("Unknown go option: {}", word));
+            }
+        };
+    }
+    if let (None, Some(clock)) = (limits.time, clock) {
+        let millis = clock / CLOCK_SHARE as u64 + increment / 2;
+        limits.time = Some(Duration::from_millis(millis.min(clock)));
+    }
+    Ok(limits)
+}
+
+impl Engine {
+    fn new() -> Engine {
+        Engine {
+            state: GameState::new(),
+            searching: None,
+        }
+    }
+    fn stop(&mut self) {
+        if let Some((stop, handle, _)) = self.searching.take() {
+            stop.store(true, Ordering::Relaxed);
+            handle.join().expect("Search thread panicked");
+        }
+    }
+    fn position(&mut self, words: &[&str]) -> Result<(), String> {
+        let (notation, rest) = match words {
+            ["startpos", red, blue, spare, rest @ ..] => (
+                format!("{} {} {} {} r", START_LAYOUT, red, blue, spare),
+                rest,
+            ),
+            ["fen", layout, red, blue, spare, turn, rest @ ..] => (
+                format!("{} {} {} {} {}", layout, red, blue, spare, turn),
+                rest,
+            ),
+            _ => {
+                return Err("Expected position startpos <deal> or position fen <board>".to_string());
+            }
+        };
+        let board: Board = notation.parse()?;
+        let moves = match rest {
+            [] => vec![],
+            ["moves", moves @ ..] => parse_moves(moves)?,
+            _ => {
+                return Err(format!("Unexpected {} after the position", rest[0]));
+            }
+        };
+        let mut state = GameState::Playing { board };
+        // Repetitions end the game, so the history is needed as well as the position
+        let mut history = PositionHistory::new(&state, None);
+        for game_move in moves {
+            let board = match state {
+                GameState::Playing { board } => board,
+                GameState::Finished { .. } => {
+                    return Err(format!("Game is over before {}", game_move));
+                }
+            };
+            let next = board
+                .try_move(game_move)
+                .map_err(|err| format!("{}: {}", game_move, err))?;
+            state = history.record(next);
+        }
+        self.state = state;
+        Ok(())
+    }
+    fn go(&mut self, words: &[&str]) -> Result<(), String> {
+        let turn = match self.state {
+            GameState::Playing { board } => board.turn,
+            GameState::Finished { .. } => {
+                println!("bestmove none");
+                return Ok(());
+            }
+        };
+        let limits = parse_limits(words, turn)?;
+        let state = self.state;
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut search = Search::new(EvalWeights::default()).stop_on(stop.clone());
+        let handle = thread::spawn(move || {
+            let mut report = |info: &SearchInfo| {
+                println!(
+                    "info depth {} score {} nodes {} time {} pv {}",
+                    info.depth,
+                    describe_score(info.score, turn),
+                    info.nodes,
+                    info.time,
+                    describe_line(&info.pv)
+                );
+            };
+            let result = alphabeta::deepen_within(&state, &limits, &mut search, &mut report);
+            // Stopped before the first depth finished, any legal move will do
+            let game_move = match (result, state) {
+                (Some((_, (game_move, _))), _) => game_move,
+                (None, GameState::Playing { board }) => board.legal_moves()[0],
+                (None, GameState::Finished { .. }) => unreachable!("Searched a finished game"),
+            };
+            println!("bestmove {}", game_move);
+        });
+        self.searching = Some((stop, handle, limits));
+        Ok(())
+    }
+    // Handles a command, returning false once the engine should exit
+    fn handle(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => {
+                return true;
+            }
+        };
+        let result = match command {
+            "uci" => {
+                println!("id name onitama-engine");
+                println!("id author Jack Adamson");
+                println!("uciok");
+                Ok(())
+            }
+            "isready" => {
+                println!("readyok");
+                Ok(())
+            }
+            "ucinewgame" => {
+                self.stop();
+                *self = Engine::new();
+                Ok(())
+            }
+            "position" => {
+                self.stop();
+                self.position(args)
+            }
+            "go" => {
+                self.stop();
+                self.go(args)
+            }
+            "stop" => {
+                self.stop();
+                Ok(())
+            }
+            "d" => {
+                match self.state {
+                    GameState::Playing { board } => println!("{}", board),
+                    GameState::Finished { board, winner, .. } => {
+                        println!("{} finished, winner {:?}", board, winner)
+                    }
+                };
+                Ok(())
+            }
+            "quit" => {
+                self.stop();
+                return false;
+            }
+            _ => Err(format!("Unknown command: {}", command)),
+        };
+        if let Err(err) = result {
+            println!("info string {}", err);
+        }
+        true
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if !engine.handle(&line) {
+            return;
+        }
+    }
+    // Input closed, let any search with limits finish before exiting
+    match engine.searching.take() {
+        Some((_, handle, limits)) if limits != SearchLimits::default() => {
+            handle.join().expect("Search thread panicked");
+        }
+        searching => {
+            engine.searching = searching;
+            engine.stop();
+        }
+    };
+}
@@ -0,0 +1,52 @@
+use std::env;
+use std::process;
+
+use instant::Instant;
+
+use onitamalib::perft::divide;
+use onitamalib::Board;
+
+const USAGE: &str = "Usage: perft <depth> <position>
+Counts the positions reached after depth moves, for each move from the position.
+The position is in board notation, for example:
+    perft 4 ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (depth, position) = match args.split_first() {
+        Some((depth, position)) if !position.is_empty() => (depth, position.join(" ")),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let depth: u32 = match depth.parse() {
+        Ok(depth) => depth,
+        Err(_) => {
+            eprintln!("Invalid depth: {}\n\n{}", depth, USAGE);
+            process::exit(2);
+        }
+    };
+    let board: Board = match position.parse() {
+        Ok(board) => board,
+        Err(err) => {
+            eprintln!("Invalid position: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    let start = Instant::now();
+    let counts = divide(&board, depth);
+    let elapsed = start.elapsed();
+    for (game_move, count) in counts.iter() {
+        println!("{}: {}", game_move, count);
+    }
+    let total: u64 = counts.iter().map(|(_, count)| count).sum();
+    println!();
+    println!("Moves: {}", counts.len());
+    println!("Nodes: {}", total);
+    println!(
+        "Time: {}ms ({:.0} nodes/s)",
+        elapsed.as_millis(),
+        total as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
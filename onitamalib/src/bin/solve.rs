@@ -0,0 +1,101 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use instant::{Duration, Instant};
+
+use onitamalib::solver::{Outcome, Solver};
+use onitamalib::tablebase::{self, Tablebase};
+use onitamalib::Board;
+
+const USAGE: &str =
+    "Usage: solve <deal> [--checkpoint <file>] [--tablebase <file>]... [--max-nodes <count>]
+Finds how the game ends with perfect play from the opening of a deal, and the best first moves.
+The deal is Red's hand, Blue's hand and the spare card, as in board notation, for example:
+    solve \"Tiger,Crab Frog,Eel Ox\" --checkpoint tiger-crab-frog-eel-ox.ckpt
+A whole position in board notation can be given instead of the deal.
+Solved positions are saved to the checkpoint file every minute, and a solve started again with
+the same file resumes from them. Tablebases for the deal settle endgames without searching them.";
+
+fn exit_usage(message: String) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn describe(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Win(winner) => format!("{:?} wins", winner),
+        Outcome::Draw => "Draw".to_string(),
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let deal = match args.next() {
+        Some(deal) => deal,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let mut solver = Solver::new();
+    let mut checkpoint = None;
+    while let Some(flag) = args.next() {
+        let value = match args.next() {
+            Some(value) => value,
+            None => exit_usage(format!("Missing value for {}", flag)),
+        };
+        match flag.as_str() {
+            "--checkpoint" => {
+                checkpoint = Some(PathBuf::from(value));
+            }
+            "--tablebase" => match Tablebase::load(value.as_ref()) {
+                Ok(table) => tablebase::install(table),
+                Err(err) => exit_usage(format!("Invalid tablebase {}: {}", value, err)),
+            },
+            "--max-nodes" => match value.parse() {
+                Ok(max_nodes) => {
+                    solver = solver.max_nodes(max_nodes);
+                }
+                Err(_) => exit_usage(format!("Invalid node count: {}", value)),
+            },
+            _ => exit_usage(format!("Unknown option: {}", flag)),
+        };
+    }
+    solver = solver.with_tablebases(tablebase::installed());
+    if let Some(path) = checkpoint {
+        solver = match solver.checkpoint_to(path, Duration::from_secs(60)) {
+            Ok(solver) => solver,
+            Err(err) => exit_usage(format!("Invalid checkpoint: {}", err)),
+        };
+    }
+    let position = match deal.contains('/') {
+        true => deal,
+        false => format!("ppkpp/5/5/5/PPKPP {} r", deal),
+    };
+    let board: Board = match position.parse() {
+        Ok(board) => board,
+        Err(err) => exit_usage(format!("Invalid deal: {}", err)),
+    };
+    let start = Instant::now();
+    let solution = solver.solve(&board, |game_move, outcome| {
+        println!("{}: {}", game_move, describe(outcome));
+    });
+    let solution = match solution {
+        Ok(solution) => solution,
+        Err(err) => {
+            eprintln!("Failed to solve: {}", err);
+            process::exit(1);
+        }
+    };
+    let best_moves: Vec<String> = solution
+        .best_moves()
+        .iter()
+        .map(|game_move| game_move.to_string())
+        .collect();
+    println!();
+    println!("Outcome: {}", describe(solution.outcome));
+    println!("Best moves: {}", best_moves.join(", "));
+    println!("Expanded: {}", solver.expanded);
+    println!("Time: {}ms", start.elapsed().as_millis());
+}
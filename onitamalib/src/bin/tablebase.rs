@@ -0,0 +1,65 @@
+use std::env;
+use std::path::Path;
+use std::process;
+
+use instant::Instant;
+
+use onitamalib::tablebase::{Material, Tablebase};
+use onitamalib::Card;
+
+const USAGE: &str = "Usage: tablebase <cards> <red pawns> <blue pawns> <file>
+Solves every position of a deal with up to the given pawns for each side, and saves the table.
+The cards are the five cards of the deal, for example:
+    tablebase Tiger,Crab,Frog,Eel,Ox 1 1 tiger-crab-frog-eel-ox.tb";
+
+fn exit_usage(message: String) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (cards, red, blue, path) = match args.as_slice() {
+        [cards, red, blue, path] => (cards, red, blue, Path::new(path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let cards: Vec<Card> = match cards.split(',').map(|card| card.parse()).collect() {
+        Ok(cards) => cards,
+        Err(err) => exit_usage(format!("Invalid cards: {}", err)),
+    };
+    let cards: [Card; 5] = match cards.as_slice() {
+        [a, b, c, d, e] => [*a, *b, *c, *d, *e],
+        _ => exit_usage(format!("Expected five cards, got {}", cards.len())),
+    };
+    let pawns = |count: &String| match count.parse::<u8>() {
+        Ok(count) if count <= 4 => count,
+        _ => exit_usage(format!("Invalid number of pawns: {}", count)),
+    };
+    let material = Material {
+        red: pawns(red),
+        blue: pawns(blue),
+    };
+    let start = Instant::now();
+    let table = match Tablebase::generate(cards, material) {
+        Ok(table) => table,
+        Err(err) => {
+            eprintln!("Failed to generate table: {}", err);
+            process::exit(1);
+        }
+    };
+    let elapsed = start.elapsed();
+    if let Err(err) = table.save(path) {
+        eprintln!("Failed to save table: {}", err);
+        process::exit(1);
+    }
+    let stats = table.stats();
+    println!("Positions: {}", stats.positions);
+    println!("Wins: {}", stats.wins);
+    println!("Losses: {}", stats.losses);
+    println!("Draws: {}", stats.draws);
+    println!("Longest win: {} plies", stats.longest);
+    println!("Time: {}ms", elapsed.as_millis());
+}
@@ -0,0 +1,134 @@
+// Squares are numbered y * 5 + x, so bit 0 is the top-left corner and bit 24 the bottom-right
+use std::cmp;
+
+use crate::models::{Card, Player, Point};
+
+pub type Bitboard = u32;
+
+pub const CARD_COUNT: usize = 43;
+// Each king starts on its own temple arch, and wins by reaching the opponent's
+pub const RED_TEMPLE: u32 = 22;
+pub const BLUE_TEMPLE: u32 = 2;
+
+// For each card, player and source square, the squares the card can reach from there
+static MOVE_MASKS: [[[Bitboard; 25]; 2]; CARD_COUNT] = build_move_masks();
+
+const fn build_move_masks() -> [[[Bitboard; 25]; 2]; CARD_COUNT] {
+    let mut masks = [[[0; 25]; 2]; CARD_COUNT];
+    let mut card_idx = 0;
+    while card_idx < CARD_COUNT {
+        let offsets = Card::from_index(card_idx as u32).offsets();
+        let mut square = 0;
+        while square < 25 {
+            let (x, y) = ((square % 5) as i8, (square / 5) as i8);
+            let mut offset_idx = 0;
+            while offset_idx < offsets.len() {
+                let Point { x: dx, y: dy } = offsets[offset_idx];
+                // Red moves up the board, so Blue sees every card rotated
+                let red = (x + dx, y + dy);
+                let blue = (x - dx, y - dy);
+                if red.0 >= 0 && red.0 < 5 && red.1 >= 0 && red.1 < 5 {
+                    masks[card_idx][0][square] |= 1 << (red.1 * 5 + red.0);
+                }
+                if blue.0 >= 0 && blue.0 < 5 && blue.1 >= 0 && blue.1 < 5 {
+                    masks[card_idx][1][square] |= 1 << (blue.1 * 5 + blue.0);
+                }
+                offset_idx += 1;
+            }
+            square += 1;
+        }
+        card_idx += 1;
+    }
+    masks
+}
+
+// Each square together with the squares around it
+static KING_ZONES: [Bitboard; 25] = build_king_zones();
+
+const fn build_king_zones() -> [Bitboard; 25] {
+    let mut zones = [0; 25];
+    let mut square = 0;
+    while square < 25 {
+        let (x, y) = ((square % 5) as i8, (square / 5) as i8);
+        let mut dy = -1;
+        while dy <= 1 {
+            let mut dx = -1;
+            while dx <= 1 {
+                let (zx, zy) = (x + dx, y + dy);
+                if zx >= 0 && zx < 5 && zy >= 0 && zy < 5 {
+                    zones[square] |= 1 << (zy * 5 + zx);
+                }
+                dx += 1;
+            }
+            dy += 1;
+        }
+        square += 1;
+    }
+    zones
+}
+
+pub fn king_zone(square: u32) -> Bitboard {
+    KING_ZONES[square as usize]
+}
+
+pub fn move_mask(card: Card, player: Player, square: u32) -> Bitboard {
+    MOVE_MASKS[card.index() as usize][player_index(player)][square as usize]
+}
+
+pub fn player_index(player: Player) -> usize {
+    match player {
+        Player::Red => 0,
+        Player::Blue => 1,
+    }
+}
+
+pub fn temple_square(player: Player) -> u32 {
+    match player {
+        Player::Red => RED_TEMPLE,
+        Player::Blue => BLUE_TEMPLE,
+    }
+}
+
+pub fn goal_square(player: Player) -> u32 {
+    temple_square(player.invert())
+}
+
+pub fn square_of(point: Point) -> u32 {
+    (point.y * 5 + point.x) as u32
+}
+
+pub fn point_of(square: u32) -> Point {
+    Point {
+        x: (square % 5) as i8,
+        y: (square / 5) as i8,
+    }
+}
+
+// Number of king steps between two squares
+pub fn distance(a: u32, b: u32) -> u32 {
+    let (a, b) = (point_of(a), point_of(b));
+    cmp::max((a.x - b.x).abs(), (a.y - b.y).abs()) as u32
+}
+
+pub fn bit_of(point: Point) -> Bitboard {
+    1 << square_of(point)
+}
+
+/// Iterates over the squares set in a bitboard, lowest square first
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
@@ -1,151 +1,122 @@
+use std::convert::TryFrom;
+use std::fmt;
+
 use crate::CardSet;
 use enum_iterator::IntoEnumIterator;
 use rand::prelude::*;
 
-use crate::models::{Board, Card, GameSquare, GameState, Move, Player, Point};
+use crate::bitboard::{
+    bit_of, goal_square, move_mask, point_of, square_of, squares, Bitboard, BLUE_TEMPLE, RED_TEMPLE,
+};
+use crate::models::{
+    Board, BoardRepr, Card, GameEnding, GameSquare, GameState, Move, Player, Point,
+};
 
 impl Board {
     pub fn try_move(self: &Board, game_move: Move) -> Result<GameState, String> {
-        let (blue_king, blue_pawns, blue_hand, red_king, red_pawns, red_hand, spare_card, turn) =
-            match self {
-                Board {
-                    blue_king,
-                    blue_pawns,
-                    blue_hand,
-                    red_king,
-                    red_pawns,
-                    red_hand,
-                    spare_card,
-                    turn,
-                } => (
-                    blue_king, blue_pawns, blue_hand, red_king, red_pawns, red_hand, spare_card,
-                    turn,
-                ),
-            };
-        let (player_king, opponent_king) = match turn {
-            Player::Red => (red_king, blue_king),
-            Player::Blue => (blue_king, red_king),
-        };
-        let player_pieces = self.player_pieces();
         let (card, src, dst) = match game_move {
             Move::Move { card, src, dst } => (card, src, dst),
             Move::Discard { card } => {
+                if !self.player_hand().contains(&card) {
+                    return Err("Card not in hand".to_string());
+                }
                 if self.can_move() {
-                    return Err(format!("Valid moves exist"));
+                    return Err("Valid moves exist".to_string());
                 }
-                let player_hand: [Card; 2] = self.player_hand().map(|c| match c == card {
-                    true => *spare_card,
-                    false => c,
-                });
-                let (red_hand, blue_hand) = match turn {
-                    Player::Red => (player_hand, *blue_hand),
-                    Player::Blue => (*red_hand, player_hand),
-                };
                 return Ok(GameState::Playing {
-                    board: Board {
-                        blue_king: *blue_king,
-                        blue_pawns: *blue_pawns,
-                        blue_hand,
-                        red_king: *red_king,
-                        red_pawns: *red_pawns,
-                        red_hand,
-                        spare_card: card,
-                        turn: turn.invert(),
-                    },
+                    board: self.play_card(card),
                 });
             }
         };
         if !self.player_hand().contains(&card) {
             return Err("Card not in hand".to_string());
         }
-        if !player_pieces.contains(&Some(src)) {
+        let player_pieces = self.pieces(self.turn);
+        if src.out_of_bounds() || player_pieces & bit_of(src) == 0 {
             return Err("No piece at src".to_string());
         }
-        if player_pieces.contains(&Some(dst)) {
-            return Err("Destination occupied by your piece".to_string());
-        }
         if dst.out_of_bounds() {
             return Err("Destination is out of bounds".to_string());
         }
-        let raw_delta = dst - src;
-        let delta = match turn {
-            Player::Red => raw_delta,
-            Player::Blue => -raw_delta,
-        };
-        let moves = card.moves();
-        if !moves.contains(&delta) {
+        if player_pieces & bit_of(dst) != 0 {
+            return Err("Destination occupied by your piece".to_string());
+        }
+        if move_mask(card, self.turn, square_of(src)) & bit_of(dst) == 0 {
+            let raw_delta = dst - src;
+            let delta = match self.turn {
+                Player::Red => raw_delta,
+                Player::Blue => -raw_delta,
+            };
             log::info!("Attempted {:?} with card {:?}", &delta, &card);
             return Err("Move not valid for card".to_string());
         }
-        let goal_square = match turn {
-            Player::Red => Point { x: 2, y: 0 },
-            Player::Blue => Point { x: 2, y: 4 },
-        };
-        let moving_king = *player_king == src;
-        let player_pawns = self.player_pawns().map(|pawn| match pawn {
-            None => None,
-            Some(pawn) if pawn == src => Some(dst),
-            Some(pawn) => Some(pawn),
-        });
-        let opponent_pawns = self.opponent_pawns().map(|pawn| match pawn {
-            None => None,
-            Some(pawn) if pawn == dst => None,
-            Some(pawn) => Some(pawn),
-        });
-        let player_hand: [Card; 2] = self.player_hand().map(|c| match c == card {
-            true => *spare_card,
-            false => c,
-        });
-        let player_king = match moving_king {
-            true => dst,
-            false => *player_king,
+        Ok(self.apply_move(card, square_of(src), square_of(dst)))
+    }
+    // Plays a move that is already known to be legal
+    pub(crate) fn apply_move(&self, card: Card, src: u32, dst: u32) -> GameState {
+        let (src_bit, dst_bit) = (1 << src, 1 << dst);
+        let turn = self.turn;
+        let moving_king = self.king(turn) & src_bit != 0;
+        let mut board = self.play_card(card);
+        let (player_pieces, player_king, opponent_pieces) = match turn {
+            Player::Red => (
+                &mut board.red_pieces,
+                &mut board.red_king,
+                &mut board.blue_pieces,
+            ),
+            Player::Blue => (
+                &mut board.blue_pieces,
+                &mut board.blue_king,
+                &mut board.red_pieces,
+            ),
         };
-        let board = match self.turn {
-            Player::Red => Board {
-                blue_king: *blue_king,
-                blue_pawns: opponent_pawns,
-                blue_hand: *blue_hand,
-                red_king: player_king,
-                red_pawns: player_pawns,
-                red_hand: player_hand,
-                spare_card: card,
-                turn: Player::Blue,
-            },
-            Player::Blue => Board {
-                blue_king: player_king,
-                blue_pawns: player_pawns,
-                blue_hand: player_hand,
-                red_king: *red_king,
-                red_pawns: opponent_pawns,
-                red_hand: *red_hand,
-                spare_card: card,
-                turn: Player::Red,
-            },
+        *player_pieces ^= src_bit | dst_bit;
+        if moving_king {
+            *player_king = dst_bit;
+        }
+        // A captured king keeps its square, but is no longer one of the opponent's pieces
+        *opponent_pieces &= !dst_bit;
+        let captured_king = self.king(turn.invert()) & dst_bit != 0;
+        let ending = match captured_king {
+            true => GameEnding::KingCaptured,
+            false => GameEnding::TempleReached,
         };
-        if dst == *opponent_king {
-            return Ok(GameState::Finished {
-                winner: *turn,
+        if captured_king || (moving_king && dst == goal_square(turn)) {
+            return GameState::Finished {
+                winner: Some(turn),
                 board,
-            });
+                ending: Some(ending),
+            };
         }
-        if moving_king && dst == goal_square {
-            return Ok(GameState::Finished {
-                winner: *turn,
-                board,
-            });
+        GameState::Playing { board }
+    }
+    // Swaps the played card with the spare card and passes the turn
+    fn play_card(&self, card: Card) -> Board {
+        let mut board = *self;
+        let player_hand = match self.turn {
+            Player::Red => &mut board.red_hand,
+            Player::Blue => &mut board.blue_hand,
+        };
+        for hand_card in player_hand.iter_mut() {
+            if *hand_card == card {
+                *hand_card = self.spare_card;
+            }
         }
-        return Ok(GameState::Playing { board });
+        board.spare_card = card;
+        board.turn = self.turn.invert();
+        board
     }
-    fn new_from_cards(cards: Vec<Card>) -> Board {
+    pub(crate) fn new_from_cards(cards: Vec<Card>) -> Board {
         let mut cards = cards.into_iter();
-        let pawn_xs: [i8; 4] = [0, 1, 3, 4];
+        // Pieces start on the back row, kings in the middle
+        let blue_pieces: Bitboard = 0b11111;
+        let red_pieces: Bitboard = 0b11111 << 20;
         Board {
-            blue_king: Point { x: 2, y: 0 },
-            blue_pawns: pawn_xs.map(|x| Some(Point { x, y: 0 })),
-
+            blue_pieces,
+            blue_king: 1 << BLUE_TEMPLE,
             blue_hand: [cards.next().unwrap(), cards.next().unwrap()],
-            red_king: Point { x: 2, y: 4 },
-            red_pawns: pawn_xs.map(|x| Some(Point { x, y: 4 })),
+            red_pieces,
+            red_king: 1 << RED_TEMPLE,
             red_hand: [cards.next().unwrap(), cards.next().unwrap()],
             spare_card: cards.next().unwrap(),
             turn: Player::Red,
@@ -165,43 +136,40 @@ impl Board {
                 for set in card_sets {
                     cards.append(&mut set.cards());
                 }
-                let mut rng = thread_rng();
-                cards.shuffle(&mut rng);
-                Board::new_from_cards(cards)
+                Board::new_from_card_list(&cards)
             }
         }
     }
+    // Deals five of the given cards at random, there must be at least five
+    pub fn new_from_card_list(cards: &[Card]) -> Board {
+        let mut cards = cards.to_vec();
+        let mut rng = thread_rng();
+        cards.shuffle(&mut rng);
+        Board::new_from_cards(cards)
+    }
     pub fn to_grid(&self) -> [[GameSquare; 5]; 5] {
         let mut grid = [[GameSquare::Empty; 5]; 5];
-        for Point { x, y } in self.blue_pawns.iter().filter_map(|p| *p) {
-            grid[y as usize][x as usize] = GameSquare::BluePawn;
+        for square in squares(self.blue_pieces) {
+            let Point { x, y } = point_of(square);
+            grid[y as usize][x as usize] = match self.blue_king & (1 << square) {
+                0 => GameSquare::BluePawn,
+                _ => GameSquare::BlueKing,
+            };
         }
-        for Point { x, y } in self.red_pawns.iter().filter_map(|p| *p) {
-            grid[y as usize][x as usize] = GameSquare::RedPawn;
+        for square in squares(self.red_pieces) {
+            let Point { x, y } = point_of(square);
+            grid[y as usize][x as usize] = match self.red_king & (1 << square) {
+                0 => GameSquare::RedPawn,
+                _ => GameSquare::RedKing,
+            };
         }
-        let Point { x, y } = self.red_king;
-        grid[y as usize][x as usize] = GameSquare::RedKing;
-        let Point { x, y } = self.blue_king;
-        grid[y as usize][x as usize] = GameSquare::BlueKing;
-        return grid;
+        grid
     }
     pub fn can_move(&self) -> bool {
-        let player_pieces = self.player_pieces();
-        for src in player_pieces.iter().filter_map(|src| *src) {
-            for card in self.player_hand() {
-                for raw_delta in card.moves() {
-                    let delta = match self.turn {
-                        Player::Red => raw_delta,
-                        Player::Blue => -raw_delta,
-                    };
-                    let dst = delta + src;
-                    if dst.in_bounds() && !player_pieces.contains(&Some(dst)) {
-                        return true;
-                    }
-                }
-            }
-        }
-        return false;
+        let player_pieces = self.pieces(self.turn);
+        self.player_hand().iter().any(|card| {
+            squares(player_pieces).any(|src| move_mask(*card, self.turn, src) & !player_pieces != 0)
+        })
     }
 }
 
@@ -216,6 +184,11 @@ impl GameState {
             board: Board::new_from_card_sets(card_sets),
         }
     }
+    pub fn new_from_card_list(cards: &[Card]) -> GameState {
+        GameState::Playing {
+            board: Board::new_from_card_list(cards),
+        }
+    }
 }
 
 impl Board {
@@ -234,62 +207,166 @@ impl Board {
 }
 
 impl Board {
-    pub fn player_pawns(&self) -> &[Option<Point>; 4] {
-        match self.turn {
-            Player::Red => &self.red_pawns,
-            Player::Blue => &self.blue_pawns,
+    pub fn pieces(&self, player: Player) -> Bitboard {
+        match player {
+            Player::Red => self.red_pieces,
+            Player::Blue => self.blue_pieces,
         }
     }
-    pub fn opponent_pawns(&self) -> &[Option<Point>; 4] {
-        match self.turn.invert() {
-            Player::Red => &self.red_pawns,
-            Player::Blue => &self.blue_pawns,
+    pub fn king(&self, player: Player) -> Bitboard {
+        match player {
+            Player::Red => self.red_king,
+            Player::Blue => self.blue_king,
         }
     }
+    pub fn pawns(&self, player: Player) -> Bitboard {
+        self.pieces(player) & !self.king(player)
+    }
+    pub fn pawn_count(&self, player: Player) -> usize {
+        self.pawns(player).count_ones() as usize
+    }
 }
 
 impl Board {
-    pub fn player_king(&self) -> &Point {
-        match self.turn {
-            Player::Red => &self.red_king,
-            Player::Blue => &self.blue_king,
-        }
+    pub fn red_king(&self) -> Point {
+        point_of(self.red_king.trailing_zeros())
     }
-    pub fn opponent_king(&self) -> &Point {
-        match self.turn.invert() {
-            Player::Red => &self.red_king,
-            Player::Blue => &self.blue_king,
-        }
+    pub fn blue_king(&self) -> Point {
+        point_of(self.blue_king.trailing_zeros())
+    }
+    pub fn red_pawns(&self) -> [Option<Point>; 4] {
+        pawn_points(self.pawns(Player::Red))
+    }
+    pub fn blue_pawns(&self) -> [Option<Point>; 4] {
+        pawn_points(self.pawns(Player::Blue))
+    }
+}
+
+fn pawn_points(pawns: Bitboard) -> [Option<Point>; 4] {
+    let mut points = [None; 4];
+    for (slot, square) in points.iter_mut().zip(squares(pawns)) {
+        *slot = Some(point_of(square));
+    }
+    points
+}
+
+impl Board {
+    pub fn player_pawns(&self) -> [Option<Point>; 4] {
+        pawn_points(self.pawns(self.turn))
+    }
+    pub fn opponent_pawns(&self) -> [Option<Point>; 4] {
+        pawn_points(self.pawns(self.turn.invert()))
+    }
+}
+
+impl Board {
+    pub fn player_king(&self) -> Point {
+        point_of(self.king(self.turn).trailing_zeros())
+    }
+    pub fn opponent_king(&self) -> Point {
+        point_of(self.king(self.turn.invert()).trailing_zeros())
     }
 }
 
 impl Board {
     pub fn player_pieces(&self) -> [Option<Point>; 5] {
         let mut pieces: [Option<Point>; 5] = [None; 5];
-        pieces[1..].copy_from_slice(&*self.player_pawns());
-        pieces[0] = Some(*self.player_king());
-        return pieces;
+        pieces[1..].copy_from_slice(&self.player_pawns());
+        pieces[0] = Some(self.player_king());
+        pieces
     }
     pub fn opponent_pieces(&self) -> [Option<Point>; 5] {
         let mut pieces: [Option<Point>; 5] = [None; 5];
-        pieces[1..].copy_from_slice(&*self.opponent_pawns());
-        pieces[0] = Some(*self.opponent_king());
-        return pieces;
+        pieces[1..].copy_from_slice(&self.opponent_pawns());
+        pieces[0] = Some(self.opponent_king());
+        pieces
     }
 }
 
 impl Board {
     pub fn red_pieces(&self) -> [Option<Point>; 5] {
         let mut pieces: [Option<Point>; 5] = [None; 5];
-        pieces[..4].copy_from_slice(self.red_pawns.as_ref());
-        pieces[4] = Some(self.red_king);
-        return pieces;
+        pieces[..4].copy_from_slice(&self.red_pawns());
+        pieces[4] = Some(self.red_king());
+        pieces
     }
     pub fn blue_pieces(&self) -> [Option<Point>; 5] {
         let mut pieces: [Option<Point>; 5] = [None; 5];
-        pieces[..4].copy_from_slice(self.blue_pawns.as_ref());
-        pieces[4] = Some(self.blue_king);
-        return pieces;
+        pieces[..4].copy_from_slice(&self.blue_pawns());
+        pieces[4] = Some(self.blue_king());
+        pieces
+    }
+}
+
+impl From<Board> for BoardRepr {
+    fn from(board: Board) -> Self {
+        BoardRepr {
+            blue_king: board.blue_king(),
+            blue_pawns: board.blue_pawns(),
+            blue_hand: board.blue_hand,
+            red_king: board.red_king(),
+            red_pawns: board.red_pawns(),
+            red_hand: board.red_hand,
+            spare_card: board.spare_card,
+            turn: board.turn,
+        }
+    }
+}
+
+impl TryFrom<BoardRepr> for Board {
+    type Error = String;
+    fn try_from(repr: BoardRepr) -> Result<Self, Self::Error> {
+        let to_bitboard = |points: &[Option<Point>]| {
+            points
+                .iter()
+                .filter_map(|point| *point)
+                .try_fold(0, |acc, point| match point.in_bounds() {
+                    true => Ok(acc | bit_of(point)),
+                    false => Err(format!("Piece out of bounds: {:?}", point)),
+                })
+        };
+        let red_king = to_bitboard(&[Some(repr.red_king)])?;
+        let blue_king = to_bitboard(&[Some(repr.blue_king)])?;
+        let mut red_pieces = to_bitboard(&repr.red_pawns)?;
+        let mut blue_pieces = to_bitboard(&repr.blue_pawns)?;
+        // A king sharing its square with an opponent was captured on the last move
+        match repr.turn {
+            Player::Red if red_king & (blue_pieces | blue_king) != 0 => {
+                blue_pieces |= blue_king;
+            }
+            Player::Blue if blue_king & (red_pieces | red_king) != 0 => {
+                red_pieces |= red_king;
+            }
+            _ => {
+                red_pieces |= red_king;
+                blue_pieces |= blue_king;
+            }
+        };
+        Ok(Board {
+            blue_pieces,
+            blue_king,
+            blue_hand: repr.blue_hand,
+            red_pieces,
+            red_king,
+            red_hand: repr.red_hand,
+            spare_card: repr.spare_card,
+            turn: repr.turn,
+        })
+    }
+}
+
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Board")
+            .field("blue_king", &self.blue_king())
+            .field("blue_pawns", &self.blue_pawns())
+            .field("blue_hand", &self.blue_hand)
+            .field("red_king", &self.red_king())
+            .field("red_pawns", &self.red_pawns())
+            .field("red_hand", &self.red_hand)
+            .field("spare_card", &self.spare_card)
+            .field("turn", &self.turn)
+            .finish()
     }
 }
 
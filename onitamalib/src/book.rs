@@ -0,0 +1,286 @@
+// Opening book: moves played from early positions in self-play, with how those games ended
+//
+// Positions are keyed by hash. The board is symmetric left to right, so a position and its mirror
+// image, with each card swapped for its mirror image, share an entry. Moves are kept in whichever
+// orientation has the lower hash, and mirrored back when the other orientation is looked up.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use enum_iterator::IntoEnumIterator;
+use rand::prelude::*;
+
+use crate::bitboard::{point_of, square_of, squares, Bitboard, CARD_COUNT};
+use crate::{Board, Card, Move, Player, Point};
+
+const MAGIC: &[u8; 4] = b"ONBK";
+const VERSION: u8 = 1;
+const DISCARD: u8 = u8::MAX;
+
+/// How games went after a book move, for the player who made it
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BookResults {
+    pub wins: u16,
+    pub draws: u16,
+    pub losses: u16,
+}
+
+impl BookResults {
+    pub fn games(&self) -> u32 {
+        self.wins as u32 + self.draws as u32 + self.losses as u32
+    }
+    // Chance of the move being played, a draw counting as half a win.
+    // Moves that have lost more often than they've won are never played
+    fn weight(&self) -> u32 {
+        if self.losses > self.wins {
+            return 0;
+        }
+        2 * self.wins as u32 + self.draws as u32
+    }
+}
+
+#[derive(Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<(Move, BookResults)>>,
+}
+
+// The card moving the same way with left and right swapped, if there is one
+fn mirror_card(card: Card) -> Option<Card> {
+    let mut mirrored: Vec<Point> = card
+        .offsets()
+        .iter()
+        .map(|offset| Point {
+            x: -offset.x,
+            y: offset.y,
+        })
+        .collect();
+    mirrored.sort_by_key(|offset| (offset.x, offset.y));
+    Card::into_enum_iter().find(|other| {
+        let mut offsets = other.moves();
+        offsets.sort_by_key(|offset| (offset.x, offset.y));
+        offsets == mirrored
+    })
+}
+
+fn mirror_point(point: Point) -> Point {
+    Point {
+        x: 4 - point.x,
+        y: point.y,
+    }
+}
+
+fn mirror_squares(set: Bitboard) -> Bitboard {
+    squares(set).fold(0, |mirrored, square| {
+        mirrored | 1 << square_of(mirror_point(point_of(square)))
+    })
+}
+
+fn mirror_board(board: &Board) -> Option<Board> {
+    let hand = |hand: [Card; 2]| Some([mirror_card(hand[0])?, mirror_card(hand[1])?]);
+    Some(Board {
+        blue_pieces: mirror_squares(board.blue_pieces),
+        blue_king: mirror_squares(board.blue_king),
+        blue_hand: hand(board.blue_hand)?,
+        red_pieces: mirror_squares(board.red_pieces),
+        red_king: mirror_squares(board.red_king),
+        red_hand: hand(board.red_hand)?,
+        spare_card: mirror_card(board.spare_card)?,
+        turn: board.turn,
+    })
+}
+
+fn mirror_move(game_move: Move) -> Move {
+    match game_move {
+        Move::Move { card, src, dst } => Move::Move {
+            card: mirror_card(card).expect("Mirrored a card without a mirror image"),
+            src: mirror_point(src),
+            dst: mirror_point(dst),
+        },
+        Move::Discard { card } => Move::Discard {
+            card: mirror_card(card).expect("Mirrored a card without a mirror image"),
+        },
+    }
+}
+
+// The key for the position, and whether its moves are kept mirrored
+fn book_key(board: &Board) -> (u64, bool) {
+    let hash = board.zobrist_hash();
+    match mirror_board(board).map(|mirrored| mirrored.zobrist_hash()) {
+        Some(mirrored) if mirrored < hash => (mirrored, true),
+        _ => (hash, false),
+    }
+}
+
+impl OpeningBook {
+    pub fn new() -> OpeningBook {
+        OpeningBook::default()
+    }
+    // Number of positions in the book
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    // Adds a move played from the position, and the winner of the game it was played in
+    pub fn record(&mut self, board: &Board, game_move: Move, winner: Option<Player>) {
+        let (key, mirrored) = book_key(board);
+        let game_move = match mirrored {
+            true => mirror_move(game_move),
+            false => game_move,
+        };
+        let moves = self.entries.entry(key).or_default();
+        let idx = match moves.iter().position(|(known, _)| *known == game_move) {
+            Some(idx) => idx,
+            None => {
+                moves.push((game_move, BookResults::default()));
+                moves.len() - 1
+            }
+        };
+        let results = &mut moves[idx].1;
+        let count = match winner {
+            Some(winner) if winner == board.turn => &mut results.wins,
+            Some(_) => &mut results.losses,
+            None => &mut results.draws,
+        };
+        *count = count.saturating_add(1);
+    }
+    // The book moves from the position, with how the games after them went
+    pub fn moves(&self, board: &Board) -> Vec<(Move, BookResults)> {
+        let (key, mirrored) = book_key(board);
+        let moves = match self.entries.get(&key) {
+            Some(moves) => moves,
+            None => {
+                return vec![];
+            }
+        };
+        moves
+            .iter()
+            .map(|(game_move, results)| match mirrored {
+                true => (mirror_move(*game_move), *results),
+                false => (*game_move, *results),
+            })
+            // Guards against another position sharing the hash
+            .filter(|(game_move, _)| board.try_move(*game_move).is_ok())
+            .collect()
+    }
+    // A book move, chosen at random weighted by how well it has done, if any has done well enough
+    pub fn choose<R: Rng>(&self, board: &Board, rng: &mut R) -> Option<Move> {
+        let moves = self.moves(board);
+        let total: u32 = moves.iter().map(|(_, results)| results.weight()).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.gen_range(0..total);
+        for (game_move, results) in moves {
+            if pick < results.weight() {
+                return Some(game_move);
+            }
+            pick -= results.weight();
+        }
+        None
+    }
+}
+
+/// Files
+///
+impl OpeningBook {
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), String> {
+        let mut data = MAGIC.to_vec();
+        data.push(VERSION);
+        data.extend((self.entries.len() as u32).to_le_bytes());
+        // Sorted so the same book always makes the same file
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let moves = &self.entries[key];
+            data.extend(key.to_le_bytes());
+            data.push(moves.len() as u8);
+            for (game_move, results) in moves {
+                let (card, src, dst) = match game_move {
+                    Move::Move { card, src, dst } => {
+                        (*card, square_of(*src) as u8, square_of(*dst) as u8)
+                    }
+                    Move::Discard { card } => (*card, DISCARD, DISCARD),
+                };
+                data.extend([card.index() as u8, src, dst]);
+                for count in [results.wins, results.draws, results.losses] {
+                    data.extend(count.to_le_bytes());
+                }
+            }
+        }
+        out.write_all(&data).map_err(|err| err.to_string())
+    }
+    pub fn read_from<R: Read>(input: &mut R) -> Result<OpeningBook, String> {
+        let mut data = vec![];
+        input
+            .read_to_end(&mut data)
+            .map_err(|err| err.to_string())?;
+        let mut data = data.as_slice();
+        let mut take = |count: usize| -> Result<&[u8], String> {
+            if data.len() < count {
+                return Err("Opening book is truncated".to_string());
+            }
+            let (taken, rest) = data.split_at(count);
+            data = rest;
+            Ok(taken)
+        };
+        if take(4)? != MAGIC {
+            return Err("Not an opening book".to_string());
+        }
+        let version = take(1)?[0];
+        if version != VERSION {
+            return Err(format!("Unsupported opening book version {}", version));
+        }
+        let mut count = [0u8; 4];
+        count.copy_from_slice(take(4)?);
+        let mut book = OpeningBook::new();
+        for _ in 0..u32::from_le_bytes(count) {
+            let mut key = [0u8; 8];
+            key.copy_from_slice(take(8)?);
+            let moves = take(1)?[0];
+            let mut entry = Vec::with_capacity(moves as usize);
+            for _ in 0..moves {
+                let fields = take(9)?;
+                if fields[0] as usize >= CARD_COUNT {
+                    return Err(format!("Unknown card {}", fields[0]));
+                }
+                let card = Card::from_index(fields[0] as u32);
+                let game_move = match (fields[1], fields[2]) {
+                    (DISCARD, DISCARD) => Move::Discard { card },
+                    (src, dst) if src < 25 && dst < 25 => Move::Move {
+                        card,
+                        src: point_of(src as u32),
+                        dst: point_of(dst as u32),
+                    },
+                    _ => {
+                        return Err("Invalid square in opening book".to_string());
+                    }
+                };
+                let count = |idx: usize| u16::from_le_bytes([fields[idx], fields[idx + 1]]);
+                let results = BookResults {
+                    wins: count(3),
+                    draws: count(5),
+                    losses: count(7),
+                };
+                entry.push((game_move, results));
+            }
+            book.entries.insert(u64::from_le_bytes(key), entry);
+        }
+        if !data.is_empty() {
+            return Err("Opening book is longer than expected".to_string());
+        }
+        Ok(book)
+    }
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut out = BufWriter::new(file);
+        self.write_to(&mut out)?;
+        out.flush().map_err(|err| err.to_string())
+    }
+    pub fn load(path: &Path) -> Result<OpeningBook, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        OpeningBook::read_from(&mut BufReader::new(file))
+    }
+}
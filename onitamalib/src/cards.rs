@@ -2,232 +2,232 @@ use crate::models::{Card, CardDirection, CardSet, Point};
 
 impl Card {
     pub fn moves(&self) -> Vec<Point> {
+        self.offsets().to_vec()
+    }
+    pub const fn offsets(&self) -> &'static [Point] {
         match self {
-            Card::Tiger => vec![
-                Point { x: 0, y: -2 }, 
-                Point { x: 0, y: 1 }
-            ],
-            Card::Dragon => vec![
+            Card::Tiger => &[Point { x: 0, y: -2 }, Point { x: 0, y: 1 }],
+            Card::Dragon => &[
                 Point { x: -2, y: -1 },
                 Point { x: -1, y: 1 },
                 Point { x: 2, y: -1 },
                 Point { x: 1, y: 1 },
             ],
-            Card::Frog => vec![
+            Card::Frog => &[
                 Point { x: -2, y: 0 },
                 Point { x: -1, y: -1 },
                 Point { x: 1, y: 1 },
             ],
-            Card::Rabbit => vec![
+            Card::Rabbit => &[
                 Point { x: 1, y: -1 },
                 Point { x: 2, y: 0 },
                 Point { x: -1, y: 1 },
             ],
-            Card::Crab => vec![
+            Card::Crab => &[
                 Point { x: 0, y: -1 },
                 Point { x: -2, y: 0 },
                 Point { x: 2, y: 0 },
             ],
-            Card::Elephant => vec![
+            Card::Elephant => &[
                 Point { x: 1, y: 0 },
                 Point { x: -1, y: -1 },
                 Point { x: 1, y: -1 },
                 Point { x: -1, y: 0 },
             ],
-            Card::Goose => vec![
+            Card::Goose => &[
                 Point { x: -1, y: 0 },
                 Point { x: -1, y: -1 },
                 Point { x: 1, y: 0 },
                 Point { x: 1, y: 1 },
             ],
-            Card::Rooster => vec![
+            Card::Rooster => &[
                 Point { x: 1, y: 0 },
                 Point { x: 1, y: -1 },
                 Point { x: -1, y: 0 },
                 Point { x: -1, y: 1 },
             ],
-            Card::Monkey => vec![
+            Card::Monkey => &[
                 Point { x: -1, y: -1 },
                 Point { x: 1, y: -1 },
                 Point { x: -1, y: 1 },
                 Point { x: 1, y: 1 },
             ],
-            Card::Mantis => vec![
+            Card::Mantis => &[
                 Point { x: -1, y: -1 },
                 Point { x: 1, y: -1 },
                 Point { x: 0, y: 1 },
             ],
-            Card::Horse => vec![
+            Card::Horse => &[
                 Point { x: 0, y: -1 },
                 Point { x: -1, y: 0 },
                 Point { x: 0, y: 1 },
             ],
-            Card::Ox => vec![
+            Card::Ox => &[
                 Point { x: 0, y: -1 },
                 Point { x: 1, y: 0 },
                 Point { x: 0, y: 1 },
             ],
-            Card::Crane => vec![
+            Card::Crane => &[
                 Point { x: 0, y: -1 },
                 Point { x: 1, y: 1 },
                 Point { x: -1, y: 1 },
             ],
-            Card::Boar => vec![
+            Card::Boar => &[
                 Point { x: 0, y: -1 },
                 Point { x: 1, y: 0 },
                 Point { x: -1, y: 0 },
             ],
-            Card::Eel => vec![
+            Card::Eel => &[
                 Point { x: 1, y: 0 },
                 Point { x: -1, y: -1 },
                 Point { x: -1, y: 1 },
             ],
-            Card::Cobra => vec![
+            Card::Cobra => &[
                 Point { x: -1, y: 0 },
                 Point { x: 1, y: -1 },
                 Point { x: 1, y: 1 },
             ],
 
             // Sensei's Path
-            Card::Fox => vec![
+            Card::Fox => &[
                 Point { x: 1, y: -1 },
                 Point { x: 1, y: 0 },
                 Point { x: 1, y: 1 },
             ],
-            Card::Dog => vec![
+            Card::Dog => &[
                 Point { x: -1, y: -1 },
                 Point { x: -1, y: 0 },
                 Point { x: -1, y: 1 },
             ],
-            Card::Giraffe => vec![
+            Card::Giraffe => &[
                 Point { x: -2, y: -1 },
                 Point { x: 2, y: -1 },
                 Point { x: 0, y: 1 },
             ],
-            Card::Panda => vec![
+            Card::Panda => &[
                 Point { x: -1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: 1, y: -1 },
             ],
-            Card::Bear => vec![
+            Card::Bear => &[
                 Point { x: 1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: -1, y: -1 },
             ],
-            Card::Kirin => vec![
+            Card::Kirin => &[
                 Point { x: 0, y: 2 },
                 Point { x: 1, y: -2 },
                 Point { x: -1, y: -2 },
             ],
-            Card::SeaSnake => vec![
+            Card::SeaSnake => &[
                 Point { x: -1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: 2, y: 0 },
             ],
-            Card::Viper => vec![
+            Card::Viper => &[
                 Point { x: 1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: -2, y: 0 },
             ],
-            Card::Phoenix => vec![
+            Card::Phoenix => &[
                 Point { x: -2, y: 0 },
                 Point { x: -1, y: -1 },
                 Point { x: 1, y: -1 },
                 Point { x: 2, y: 0 },
             ],
-            Card::Mouse => vec![
+            Card::Mouse => &[
                 Point { x: -1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: 1, y: 0 },
             ],
-            Card::Rat => vec![
+            Card::Rat => &[
                 Point { x: 1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: -1, y: 0 },
             ],
-            Card::Turtle => vec![
+            Card::Turtle => &[
                 Point { x: -2, y: 0 },
                 Point { x: -1, y: 1 },
                 Point { x: 1, y: 1 },
                 Point { x: 2, y: 0 },
             ],
-            Card::Tanuki => vec![
+            Card::Tanuki => &[
                 Point { x: -1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: 2, y: -1 },
             ],
-            Card::Iguana => vec![
+            Card::Iguana => &[
                 Point { x: 1, y: 1 },
                 Point { x: 0, y: -1 },
                 Point { x: -2, y: -1 },
             ],
-            Card::Sable => vec![
+            Card::Sable => &[
                 Point { x: -2, y: 0 },
                 Point { x: -1, y: 1 },
                 Point { x: 1, y: -1 },
             ],
-            Card::Otter => vec![
+            Card::Otter => &[
                 Point { x: 2, y: 0 },
                 Point { x: 1, y: 1 },
                 Point { x: -1, y: -1 },
             ],
             // Promotional Cards
-            Card::Goat => vec![
+            Card::Goat => &[
                 Point { x: -1, y: 0 },
                 Point { x: 0, y: 1 },
                 Point { x: 1, y: -1 },
             ],
-            Card::Sheep => vec![
+            Card::Sheep => &[
                 Point { x: 1, y: 0 },
                 Point { x: 0, y: 1 },
                 Point { x: -1, y: -1 },
             ],
-            Card::Lobster => vec![
+            Card::Lobster => &[
                 Point { x: -1, y: -1 },
                 Point { x: -1, y: 2 },
                 Point { x: 1, y: -1 },
                 Point { x: 1, y: 2 },
             ],
-            Card::Steer => vec![
+            Card::Steer => &[
                 Point { x: 1, y: 0 },
                 Point { x: -1, y: 1 },
                 Point { x: 1, y: 1 },
                 Point { x: -1, y: 0 },
             ],
-            Card::Hornet => vec![
+            Card::Hornet => &[
                 Point { x: -2, y: 2 },
                 Point { x: 0, y: -1 },
                 Point { x: 1, y: 0 },
             ],
-            Card::Centipede => vec![
+            Card::Centipede => &[
                 Point { x: -1, y: 0 },
                 Point { x: 0, y: -1 },
                 Point { x: 2, y: 2 },
             ],
-            Card::Cat => vec![
+            Card::Cat => &[
                 Point { x: -1, y: 0 },
                 Point { x: 0, y: -1 },
                 Point { x: 0, y: 2 },
                 Point { x: 2, y: 0 },
             ],
-            Card::Serow => vec![
+            Card::Serow => &[
                 Point { x: -2, y: 0 },
                 Point { x: 0, y: -1 },
                 Point { x: 0, y: 2 },
                 Point { x: 1, y: 0 },
             ],
-            Card::Nessie => vec![
+            Card::Nessie => &[
                 Point { x: -2, y: -1 },
                 Point { x: 2, y: 0 },
                 Point { x: -1, y: 1 },
                 Point { x: 1, y: 1 },
             ],
-            Card::Butterfly => vec![
+            Card::Butterfly => &[
                 Point { x: -2, y: -1 },
                 Point { x: 2, y: -1 },
                 Point { x: 0, y: 1 },
             ],
-            Card::Moth => vec![
+            Card::Moth => &[
                 Point { x: -2, y: 1 },
                 Point { x: 2, y: 1 },
                 Point { x: 0, y: -1 },
@@ -284,7 +284,7 @@ impl Card {
             Card::Moth => CardDirection::Balanced,
         }
     }
-    pub fn index(&self) -> u32 {
+    pub const fn index(&self) -> u32 {
         match self {
             Card::Tiger => 0,
             Card::Dragon => 1,
@@ -337,6 +337,12 @@ impl Card {
 
 impl From<u32> for Card {
     fn from(idx: u32) -> Self {
+        Card::from_index(idx)
+    }
+}
+
+impl Card {
+    pub const fn from_index(idx: u32) -> Card {
         match idx {
             0 => Card::Tiger,
             1 => Card::Dragon,
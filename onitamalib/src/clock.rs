@@ -0,0 +1,145 @@
+// Time controls for online games, the server keeps the clock and runs out the flag
+//
+// Times are in milliseconds, and the clock only tracks time spent on finished turns,
+// whoever keeps it measures how long the current turn has been going.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Player;
+
+// Longest time control that can be asked for, in seconds
+const MAX_SECONDS: u64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TimeControl {
+    // A fixed amount of time for the whole game
+    SuddenDeath { total: u64 },
+    // Time for the whole game, with an increment added after every move
+    Fischer { initial: u64, increment: u64 },
+    // A fixed amount of time for each move, unused time is lost
+    PerMove { per_move: u64 },
+}
+
+impl TimeControl {
+    fn initial(&self) -> u64 {
+        match *self {
+            TimeControl::SuddenDeath { total } => total,
+            TimeControl::Fischer { initial, .. } => initial,
+            TimeControl::PerMove { per_move } => per_move,
+        }
+    }
+    // Checks a time control sent by a client can be played
+    pub fn validate(&self) -> Result<(), String> {
+        let longest = match *self {
+            TimeControl::Fischer { initial, increment } => initial.max(increment),
+            _ => self.initial(),
+        };
+        if longest > MAX_SECONDS * 1000 {
+            return Err("Time controls can be at most a day".to_string());
+        }
+        if self.initial() == 0 {
+            return Err("Time controls need some time to play".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Written in seconds, as "sudden:300", "fischer:300:5" or "move:30"
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TimeControl::SuddenDeath { total } => write!(f, "sudden:{}", total / 1000),
+            TimeControl::Fischer { initial, increment } => {
+                write!(f, "fischer:{}:{}", initial / 1000, increment / 1000)
+            }
+            TimeControl::PerMove { per_move } => write!(f, "move:{}", per_move / 1000),
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+    fn from_str(text: &str) -> Result<TimeControl, String> {
+        let mut parts = text.split(':');
+        let kind = parts.next().unwrap_or_default();
+        let seconds: Vec<u64> = parts
+            .map(|part| match part.parse::<u64>() {
+                Ok(seconds) if seconds <= MAX_SECONDS => Ok(seconds),
+                Ok(_) => Err("Time controls can be at most a day".to_string()),
+                Err(_) => Err(format!("Invalid number of seconds: {}", part)),
+            })
+            .collect::<Result<_, _>>()?;
+        let control = match (kind, seconds.as_slice()) {
+            ("sudden", &[total]) => TimeControl::SuddenDeath {
+                total: total * 1000,
+            },
+            ("fischer", &[initial, increment]) => TimeControl::Fischer {
+                initial: initial * 1000,
+                increment: increment * 1000,
+            },
+            ("move", &[per_move]) => TimeControl::PerMove {
+                per_move: per_move * 1000,
+            },
+            _ => {
+                return Err(format!("Invalid time control: {}", text));
+            }
+        };
+        control.validate()?;
+        Ok(control)
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Clock {
+    pub control: TimeControl,
+    // Time left for each player at the start of their turn
+    pub red: u64,
+    pub blue: u64,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Clock {
+        let initial = control.initial();
+        Clock {
+            control,
+            red: initial,
+            blue: initial,
+        }
+    }
+    pub fn remaining(&self, player: Player) -> u64 {
+        match player {
+            Player::Red => self.red,
+            Player::Blue => self.blue,
+        }
+    }
+    // The clock as it stands part way through the player's turn
+    pub fn after(&self, player: Player, elapsed: u64) -> Clock {
+        let mut clock = *self;
+        *clock.remaining_mut(player) = self.remaining(player).saturating_sub(elapsed);
+        clock
+    }
+    // Charges the player for a turn that took elapsed, returning false if their flag fell first
+    pub fn finish_turn(&mut self, player: Player, elapsed: u64) -> bool {
+        let remaining = self.remaining(player);
+        if elapsed >= remaining {
+            *self.remaining_mut(player) = 0;
+            return false;
+        }
+        *self.remaining_mut(player) = match self.control {
+            TimeControl::SuddenDeath { .. } => remaining - elapsed,
+            TimeControl::Fischer { increment, .. } => remaining - elapsed + increment,
+            TimeControl::PerMove { per_move } => per_move,
+        };
+        true
+    }
+    fn remaining_mut(&mut self, player: Player) -> &mut u64 {
+        match player {
+            Player::Red => &mut self.red,
+            Player::Blue => &mut self.blue,
+        }
+    }
+}
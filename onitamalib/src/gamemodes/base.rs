@@ -1,4 +1,6 @@
-use crate::models::{CardSet, GameState, Move, Player};
+use crate::history::PositionHistory;
+use crate::models::{CardSet, GameEnding, GameMeta, GameState, Move, Player};
+use crate::record::{GameRecord, GameResult, Participant};
 use enum_iterator::IntoEnumIterator;
 use std::collections::HashSet;
 
@@ -7,14 +9,26 @@ pub struct Game {
     state: GameState,
     card_sets: Vec<CardSet>,
     last_move: Option<Move>,
+    record: GameRecord,
+    history: PositionHistory,
+}
+
+// Starts a record from the state, a game joined part way through is recorded from there
+fn record_from(state: &GameState) -> GameRecord {
+    match state {
+        GameState::Playing { board } | GameState::Finished { board, .. } => GameRecord::new(*board),
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
+        let state = GameState::new();
         let game = Game {
-            state: GameState::new(),
+            state,
             card_sets: vec![],
             last_move: None,
+            record: record_from(&state),
+            history: PositionHistory::new(&state, None),
         };
         return game;
     }
@@ -34,10 +48,13 @@ impl Game {
         if cards_enabled < 5 {
             return Game::new();
         }
+        let state = GameState::new_from_card_sets(&card_sets);
         let game = Game {
-            state: GameState::new_from_card_sets(&card_sets),
+            state,
             card_sets,
             last_move: None,
+            record: record_from(&state),
+            history: PositionHistory::new(&state, None),
         };
         return game;
     }
@@ -47,6 +64,8 @@ impl Game {
     pub fn reset(&mut self) {
         self.state = GameState::new_from_card_sets(&self.card_sets);
         self.last_move = None;
+        self.record = record_from(&self.state);
+        self.history = PositionHistory::new(&self.state, None);
     }
 }
 
@@ -58,8 +77,9 @@ impl Game {
                 return Err("Game Already Finished".to_string());
             }
         };
-        self.state = board.try_move(game_move)?;
+        self.state = self.history.record(board.try_move(game_move)?);
         self.last_move = Some(game_move);
+        self.record.push(game_move, &self.state);
         Ok(())
     }
     pub fn get_last_move(&self) -> Option<Move> {
@@ -68,6 +88,78 @@ impl Game {
     pub fn set_state(&mut self, state: GameState) {
         self.last_move = None;
         self.state = state;
+        self.record = record_from(&state);
+        self.history = PositionHistory::new(&state, None);
+    }
+    // Undoes moves until the game is back at the state, keeping the moves before it in the record.
+    // A state this game never reached starts the record over from it instead
+    pub fn take_back_to(&mut self, state: GameState) {
+        let mut states = vec![GameState::Playing {
+            board: self.record.start,
+        }];
+        for recorded in self.record.moves.iter() {
+            let next = match states[states.len() - 1] {
+                GameState::Playing { board } => board.try_move(recorded.game_move),
+                GameState::Finished { .. } => Err("Game Already Finished".to_string()),
+            };
+            match next {
+                Ok(next) => states.push(next),
+                Err(_) => break,
+            };
+        }
+        let replayed = states.len() == self.record.moves.len() + 1;
+        let kept = match states.iter().rposition(|seen| *seen == state) {
+            Some(kept) if replayed && kept < self.record.moves.len() => kept,
+            _ => {
+                self.set_state(state);
+                return;
+            }
+        };
+        while self.record.moves.len() > kept {
+            self.record.moves.pop();
+            self.history.forget(&self.state);
+            self.state = states[self.record.moves.len()];
+        }
+        self.last_move = self.record.moves.last().map(|recorded| recorded.game_move);
+        self.record.result = GameResult::from(&state);
+        self.record.ending = match state {
+            GameState::Finished { ending, .. } => ending,
+            GameState::Playing { .. } => None,
+        };
+    }
+    // Ends the game as a draw when told to by the server,
+    // which may know of repetitions from before this game was joined
+    pub fn declare_draw(&mut self, ending: GameEnding) {
+        if let GameState::Playing { board } = self.state {
+            self.state = GameState::Finished {
+                board,
+                winner: None,
+                ending: Some(ending),
+            };
+            self.record.declare(GameResult::Drawn, ending);
+        }
+    }
+    // Ends the game when the server says so, such as when a player runs out of time
+    pub fn declare_winner(&mut self, winner: Player, ending: GameEnding) {
+        if let GameState::Playing { board } = self.state {
+            self.state = GameState::Finished {
+                board,
+                winner: Some(winner),
+                ending: Some(ending),
+            };
+            self.record.declare(GameResult::Won(winner), ending);
+        }
+    }
+    pub fn get_record(&self) -> &GameRecord {
+        &self.record
+    }
+    // The record as text, with who played each side filled in
+    pub fn export_record(&self, red: Participant, blue: Participant, meta: &GameMeta) -> String {
+        let mut record = self.record.clone();
+        record.red = red;
+        record.blue = blue;
+        record.meta = meta.clone();
+        record.to_string()
     }
     pub fn get_state(&self) -> GameState {
         return self.state;
@@ -86,7 +178,7 @@ impl Game {
     }
     pub fn get_winner(&self) -> Option<Player> {
         match self.state {
-            GameState::Finished { winner, .. } => Some(winner),
+            GameState::Finished { winner, .. } => winner,
             GameState::Playing { .. } => None,
         }
     }
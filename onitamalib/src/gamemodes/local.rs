@@ -3,6 +3,7 @@ use wasm_bindgen::prelude::*;
 
 use crate::gamemodes::base::Game;
 use crate::models::Move;
+use crate::record::Participant;
 use crate::{CardSet, GameEvent, GameMeta, GameView};
 
 #[wasm_bindgen]
@@ -59,18 +60,18 @@ impl LocalGame {
     fn try_move(&mut self, game_move: Move) -> Result<(), String> {
         self.game.try_move(game_move)?;
         self.send_current_view();
-        match self.game.get_winner() {
-            Some(winner) => {
-                let winner = format!("{:?}", winner);
-                self.send_event(GameEvent::End {
-                    training: false,
-                    against: "local".to_string(),
-                    winner,
-                    meta: self.meta.clone(),
-                });
-            }
-            None => {}
-        };
+        if self.game.is_finished() {
+            let winner = match self.game.get_winner() {
+                Some(winner) => format!("{:?}", winner),
+                None => "draw".to_string(),
+            };
+            self.send_event(GameEvent::End {
+                training: false,
+                against: "local".to_string(),
+                winner,
+                meta: self.meta.clone(),
+            });
+        }
         Ok(())
     }
     fn send_current_view(&self) {
@@ -138,4 +139,12 @@ impl LocalGame {
         self.game.reset();
         self.send_current_view();
     }
+    #[wasm_bindgen(js_name = exportRecord)]
+    pub fn export_record(&self) -> String {
+        let local = || Participant {
+            name: "Local".to_string(),
+            agent: None,
+        };
+        self.game.export_record(local(), local(), &self.meta)
+    }
 }
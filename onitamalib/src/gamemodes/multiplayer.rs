@@ -4,9 +4,11 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::MessageEvent;
 
+use crate::clock::Clock;
 use crate::gamemodes::base::Game;
-use crate::messages::GameMessage;
-use crate::models::{Move, Player};
+use crate::messages::{ChatContent, ChatMessage, GameMessage, Offer, RoomSettings};
+use crate::models::{GameEnding, Move, Player};
+use crate::record::Participant;
 use crate::{GameEvent, GameMeta, GameView};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
@@ -17,6 +19,7 @@ pub enum ConnectionState {
     Finished,
     RematchRequested,
     OpponentRematchRequested,
+    OpponentReconnecting,
     OpponentDisconnected,
     Disconnected,
     Errored,
@@ -27,11 +30,22 @@ pub enum ConnectionState {
 pub struct MultiplayerView {
     connection: ConnectionState,
     player: Player,
+    spectating: bool,
+    watchers: u32,
     error: Option<String>,
     room_id: Option<String>,
     #[serde(flatten)]
     game: GameView,
     last_move: Option<Move>,
+    clock: Option<Clock>,
+    token: Option<String>,
+    settings: Option<RoomSettings>,
+    chat: Vec<ChatMessage>,
+    offer_sent: Option<Offer>,
+    offer_received: Option<Offer>,
+    // When the clock was last received, in milliseconds since the epoch,
+    // the player to move has used the time since then
+    clock_synced: f64,
 }
 
 #[wasm_bindgen]
@@ -45,8 +59,17 @@ pub struct MultiplayerGame {
     conn_state: ConnectionState,
     resume_state: ConnectionState,
     player: Player,
+    spectating: bool,
+    watchers: u32,
     room_id: Option<String>,
     error: Option<String>,
+    clock: Option<Clock>,
+    clock_synced: f64,
+    token: Option<String>,
+    settings: Option<RoomSettings>,
+    chat: Vec<ChatMessage>,
+    offer_sent: Option<Offer>,
+    offer_received: Option<Offer>,
 }
 
 impl MultiplayerGame {
@@ -57,11 +80,24 @@ impl MultiplayerGame {
             connection: self.conn_state,
             room_id: self.room_id.clone(),
             player: self.player,
+            spectating: self.spectating,
+            watchers: self.watchers,
             error: self.error.clone(),
             last_move: self.game.get_last_move(),
+            clock: self.clock,
+            clock_synced: self.clock_synced,
+            token: self.token.clone(),
+            settings: self.settings.clone(),
+            chat: self.chat.clone(),
+            offer_sent: self.offer_sent,
+            offer_received: self.offer_received,
         };
         self.send_view(view);
     }
+    fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+        self.clock_synced = js_sys::Date::now();
+    }
     fn send_view(&self, view: MultiplayerView) {
         let view = JsValue::from_serde(&view).unwrap();
         let this = JsValue::null();
@@ -98,13 +134,14 @@ impl MultiplayerGame {
             Ok(meta) => meta,
             Err(_) => GameMeta::blank(),
         };
-        // TODO: Implement choosing DLCs for multiplayer
         let game = Game::new();
         let game = MultiplayerGame {
             room_id: None,
             game,
             meta,
             player: Player::Red, // Start Red, changes once playing
+            spectating: false,
+            watchers: 0,
             on_send_msg,
             on_send_view,
             on_send_error,
@@ -112,6 +149,13 @@ impl MultiplayerGame {
             conn_state: ConnectionState::Connecting,
             resume_state: ConnectionState::Connecting,
             error: None,
+            clock: None,
+            clock_synced: 0.0,
+            token: None,
+            settings: None,
+            chat: vec![],
+            offer_sent: None,
+            offer_received: None,
         };
         game.send_current_view();
         return game;
@@ -122,6 +166,10 @@ impl MultiplayerGame {
 impl MultiplayerGame {
     #[wasm_bindgen(js_name = move)]
     pub fn play_move(&mut self, game_move: &JsValue) {
+        if self.spectating {
+            self.send_error("Spectators can't move".to_string());
+            return;
+        }
         if !self.is_player_turn() {
             self.send_error("Not your turn".to_string());
             return;
@@ -136,7 +184,10 @@ impl MultiplayerGame {
         match self.try_move(game_move) {
             Ok(()) => {
                 log::info!("Successfully played move");
-                let msg = GameMessage::Move { game_move };
+                let msg = GameMessage::Move {
+                    game_move,
+                    clock: None,
+                };
                 self.send_msg(msg);
             }
             Err(err) => {
@@ -145,12 +196,79 @@ impl MultiplayerGame {
         };
     }
     pub fn reset(&mut self) {
+        if self.spectating {
+            return;
+        }
         log::info!("Forgeiting");
         self.conn_state = ConnectionState::RematchRequested;
         let msg = GameMessage::RequestRematch;
         self.send_msg(msg);
         self.send_current_view();
     }
+    #[wasm_bindgen(js_name = sendChat)]
+    pub fn send_chat(&mut self, content: &JsValue) {
+        if self.spectating {
+            self.send_error("Spectators can't chat".to_string());
+            return;
+        }
+        let content = match content.into_serde::<ChatContent>() {
+            Ok(content) => content.cleaned(),
+            Err(err) => Err(err.to_string()),
+        };
+        match content {
+            Ok(content) => self.send_msg(GameMessage::SendChat { content }),
+            Err(err) => self.send_error(err),
+        };
+    }
+    pub fn resign(&mut self) {
+        if self.spectating || self.game.is_finished() {
+            return;
+        }
+        self.send_msg(GameMessage::Resign);
+    }
+    #[wasm_bindgen(js_name = offerDraw)]
+    pub fn offer_draw(&mut self) {
+        self.make_offer(Offer::Draw);
+    }
+    #[wasm_bindgen(js_name = requestTakeback)]
+    pub fn request_takeback(&mut self) {
+        self.make_offer(Offer::Takeback);
+    }
+    #[wasm_bindgen(js_name = acceptOffer)]
+    pub fn accept_offer(&mut self) {
+        if let Some(offer) = self.offer_received.take() {
+            self.send_msg(GameMessage::Accept { offer });
+            self.send_current_view();
+        }
+    }
+    #[wasm_bindgen(js_name = declineOffer)]
+    pub fn decline_offer(&mut self) {
+        if let Some(offer) = self.offer_received.take() {
+            self.send_msg(GameMessage::Decline { offer });
+            self.send_current_view();
+        }
+    }
+    #[wasm_bindgen(js_name = exportRecord)]
+    pub fn export_record(&self) -> String {
+        let participant = |name: &str| Participant {
+            name: name.to_string(),
+            agent: None,
+        };
+        let (red, blue) = match (self.spectating, self.player) {
+            (true, _) => (participant("Red"), participant("Blue")),
+            (false, Player::Red) => (participant("You"), participant("Opponent")),
+            (false, Player::Blue) => (participant("Opponent"), participant("You")),
+        };
+        self.game.export_record(red, blue, &self.meta)
+    }
+    fn make_offer(&mut self, offer: Offer) {
+        if self.spectating || self.game.is_finished() || self.offer_sent.is_some() {
+            return;
+        }
+        self.offer_sent = Some(offer);
+        self.send_msg(GameMessage::Offer { offer });
+        self.send_current_view();
+    }
     fn is_player_turn(&self) -> bool {
         match self.game.get_turn() {
             None => false,
@@ -204,26 +322,43 @@ impl MultiplayerGame {
     }
     fn try_move(&mut self, game_move: Move) -> Result<(), String> {
         self.game.try_move(game_move)?;
-        if self.game.is_finished() {
-            self.conn_state = ConnectionState::Finished;
-            let winner = match self.game.get_winner() {
-                Some(player) => match player == self.player {
-                    true => "player",
-                    false => "opponent",
-                },
-                None => "unknown",
-            }
-            .to_string();
-            self.send_event(GameEvent::End {
-                training: false,
-                against: "remote".to_string(),
-                winner,
-                meta: self.meta.clone(),
-            })
-        }
+        self.check_finished();
         self.send_current_view();
         Ok(())
     }
+    fn check_finished(&mut self) {
+        if !self.game.is_finished() {
+            return;
+        }
+        self.offer_sent = None;
+        self.offer_received = None;
+        match self.conn_state {
+            // Finished while a player is away, shown once they're back
+            ConnectionState::OpponentReconnecting | ConnectionState::OpponentDisconnected => {
+                self.resume_state = ConnectionState::Finished;
+            }
+            _ => {
+                self.conn_state = ConnectionState::Finished;
+            }
+        };
+        if self.spectating {
+            return;
+        }
+        let winner = match self.game.get_winner() {
+            Some(player) => match player == self.player {
+                true => "player",
+                false => "opponent",
+            },
+            None => "draw",
+        }
+        .to_string();
+        self.send_event(GameEvent::End {
+            training: false,
+            against: "remote".to_string(),
+            winner,
+            meta: self.meta.clone(),
+        })
+    }
     fn handle_game_message(&mut self, msg: GameMessage) {
         log::info!("Message: {:?}", &msg);
         match (self.conn_state, msg) {
@@ -236,11 +371,18 @@ impl MultiplayerGame {
                     room_id,
                     player,
                     waiting,
+                    clock,
+                    token,
+                    settings,
                 },
             ) => {
                 log::info!("Initializing");
+                self.set_clock(clock);
+                self.token = token;
+                self.settings = settings;
                 self.room_id = Some(room_id);
                 self.player = player;
+                self.spectating = false;
                 self.send_event(GameEvent::Start {
                     training: false,
                     against: "online".to_string(),
@@ -255,32 +397,155 @@ impl MultiplayerGame {
                 };
                 self.game.set_state(state);
             }
+            (
+                ConnectionState::Connecting
+                | ConnectionState::Running
+                | ConnectionState::Finished
+                | ConnectionState::OpponentRematchRequested,
+                GameMessage::Spectate {
+                    state,
+                    room_id,
+                    clock,
+                },
+            ) => {
+                log::info!("Spectating");
+                self.set_clock(clock);
+                self.room_id = Some(room_id);
+                self.player = Player::Red;
+                self.spectating = true;
+                self.conn_state = match state.finished() {
+                    true => ConnectionState::Finished,
+                    false => ConnectionState::Running,
+                };
+                self.game.set_state(state);
+            }
+            (_, GameMessage::Watchers { count }) => {
+                self.watchers = count;
+            }
             (ConnectionState::Waiting, GameMessage::Joined) => {
                 log::info!("Player joined");
-                self.conn_state = ConnectionState::Running;
+                // A player can rejoin a finished game to ask for a rematch
+                self.conn_state = match self.game.is_finished() {
+                    true => ConnectionState::Finished,
+                    false => ConnectionState::Running,
+                };
             }
-            (ConnectionState::OpponentDisconnected, GameMessage::Joined) => {
+            (
+                ConnectionState::OpponentReconnecting | ConnectionState::OpponentDisconnected,
+                GameMessage::Joined,
+            ) => {
                 log::info!("Player re-joined");
                 self.conn_state = self.resume_state;
             }
-            (ConnectionState::Running, GameMessage::Move { game_move }) => {
+            // Spectators keep seeing moves from the player who is still there
+            (
+                ConnectionState::OpponentReconnecting | ConnectionState::OpponentDisconnected,
+                GameMessage::Move { game_move, clock },
+            ) if self.spectating && self.resume_state == ConnectionState::Running => {
+                match self.try_move(game_move) {
+                    Ok(()) => {
+                        self.set_clock(clock);
+                    }
+                    Err(err) => {
+                        log::error!("Player played illegal move: {}", err);
+                    }
+                }
+            }
+            (ConnectionState::Running, GameMessage::Move { game_move, clock }) => {
                 log::info!("Received move");
-                if self.is_player_turn() {
+                if !self.spectating && self.is_player_turn() {
                     log::error!("Opponent attempted to play during our turn");
                     self.send_error("Opponent played out of turn".to_string());
                     return;
                 }
+                // Moving lets any offer lapse
+                self.offer_sent = None;
+                self.offer_received = None;
                 match self.try_move(game_move) {
-                    Ok(()) => {}
+                    Ok(()) => {
+                        self.set_clock(clock);
+                    }
                     Err(err) => {
                         log::error!("Opponent played illegal move: {}", err);
                         self.send_error("Opponent played illegal move".to_string());
                     }
                 }
             }
+            (_, GameMessage::Clock { clock }) => {
+                self.set_clock(Some(clock));
+            }
+            (_, GameMessage::Chat { message }) => {
+                self.chat.push(message);
+            }
+            (_, GameMessage::ChatHistory { messages }) => {
+                self.chat = messages;
+            }
+            (_, GameMessage::ChatRejected { message }) => {
+                self.send_error(message);
+            }
+            (_, GameMessage::Timeout { player }) if !self.game.is_finished() => {
+                log::info!("{:?} ran out of time", player);
+                if let Some(clock) = self.clock.as_mut() {
+                    clock.finish_turn(player, u64::MAX);
+                }
+                self.game
+                    .declare_winner(player.invert(), GameEnding::Timeout);
+                self.check_finished();
+            }
+            (ConnectionState::Running, GameMessage::Drawn) => {
+                log::info!("Server declared a draw");
+                self.game.declare_draw(GameEnding::Repetition);
+                self.check_finished();
+            }
+            (_, GameMessage::Resigned { player }) if !self.game.is_finished() => {
+                log::info!("{:?} resigned", player);
+                self.game
+                    .declare_winner(player.invert(), GameEnding::Resignation);
+                self.check_finished();
+            }
+            (ConnectionState::Running, GameMessage::Offer { offer }) if !self.spectating => {
+                self.offer_received = Some(offer);
+            }
+            (_, GameMessage::Accept { offer: Offer::Draw }) if !self.game.is_finished() => {
+                log::info!("Draw agreed");
+                self.game.declare_draw(GameEnding::DrawAgreed);
+                self.check_finished();
+            }
+            (_, GameMessage::Decline { offer }) => {
+                self.offer_sent = None;
+                let message = match offer {
+                    Offer::Draw => "Opponent declined the draw",
+                    Offer::Takeback => "Opponent declined the takeback",
+                };
+                self.send_error(message.to_string());
+            }
+            (ConnectionState::Running, GameMessage::TakenBack { state, clock }) => {
+                log::info!("Moves taken back");
+                self.offer_sent = None;
+                self.offer_received = None;
+                self.game.take_back_to(state);
+                self.set_clock(clock);
+            }
+            // Already seen the repetition ourselves
+            (ConnectionState::Finished, GameMessage::Drawn) => {}
             (ConnectionState::Finished, GameMessage::RequestRematch) => {
                 self.conn_state = ConnectionState::OpponentRematchRequested;
             }
+            // Spectators aren't asked about rematches, the rematch starts with a new snapshot
+            (ConnectionState::OpponentRematchRequested, GameMessage::RequestRematch)
+                if self.spectating => {}
+            (
+                ConnectionState::OpponentReconnecting | ConnectionState::OpponentDisconnected,
+                GameMessage::Reconnecting,
+            ) => {}
+            (_, GameMessage::Reconnecting) => {
+                self.resume_state = self.conn_state;
+                self.conn_state = ConnectionState::OpponentReconnecting;
+            }
+            // Gave up waiting for them to reconnect
+            (ConnectionState::OpponentReconnecting, GameMessage::Disconnected) => {
+                self.conn_state = ConnectionState::OpponentDisconnected;
+            }
             (_, GameMessage::Disconnected) => {
                 self.resume_state = self.conn_state;
                 self.conn_state = ConnectionState::OpponentDisconnected;
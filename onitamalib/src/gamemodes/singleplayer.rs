@@ -5,6 +5,7 @@ use wasm_bindgen::prelude::*;
 
 use crate::gamemodes::base::Game;
 use crate::models::Move;
+use crate::record::Participant;
 use crate::{AiAgent, CardSet, GameEvent, GameMeta, GameView, MoveRequest, Player};
 
 struct PreviousState {
@@ -114,6 +115,7 @@ impl SinglePlayerGame {
         let msg = MoveRequest {
             state,
             agent: self.agent,
+            book: false,
         };
         let msg = JsValue::from_serde(&msg).unwrap();
         let this = JsValue::null();
@@ -149,22 +151,20 @@ impl SinglePlayerGame {
         self.game.try_move(game_move)?;
         self.last_move = Some(game_move);
         self.send_current_view();
-        match self.game.get_winner() {
-            None => {}
-            Some(winner) => {
-                let winner = match winner == self.player {
-                    true => "player".to_string(),
-                    false => "ai".to_string(),
-                };
-                let against = format!("{:?}", self.agent);
-                self.send_event(GameEvent::End {
-                    training: self.training_mode,
-                    against,
-                    winner,
-                    meta: self.meta.clone(),
-                })
-            }
-        };
+        if self.game.is_finished() {
+            let winner = match self.game.get_winner() {
+                Some(winner) if winner == self.player => "player".to_string(),
+                Some(_) => "ai".to_string(),
+                None => "draw".to_string(),
+            };
+            let against = format!("{:?}", self.agent);
+            self.send_event(GameEvent::End {
+                training: self.training_mode,
+                against,
+                winner,
+                meta: self.meta.clone(),
+            })
+        }
         Ok(())
     }
     fn send_current_view(&self) {
@@ -271,4 +271,21 @@ impl SinglePlayerGame {
         self.agent_move();
         self.rank_moves();
     }
+
+    #[wasm_bindgen(js_name = exportRecord)]
+    pub fn export_record(&self) -> String {
+        let player = Participant {
+            name: "Player".to_string(),
+            agent: None,
+        };
+        let agent = Participant {
+            name: format!("{:?}", self.agent),
+            agent: Some(self.agent),
+        };
+        let (red, blue) = match self.player {
+            Player::Red => (player, agent),
+            Player::Blue => (agent, player),
+        };
+        self.game.export_record(red, blue, &self.meta)
+    }
 }
@@ -1,5 +1,6 @@
-use crate::{CardSet, CardSetDescription};
+use crate::{CardSet, CardSetDescription, Emote};
 use enum_iterator::IntoEnumIterator;
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(js_name = listCardSets)]
@@ -11,3 +12,20 @@ pub fn list_card_sets() -> JsValue {
         .collect();
     serde_wasm_bindgen::to_value(&card_sets).unwrap()
 }
+
+#[derive(Serialize)]
+struct EmoteDescription {
+    id: Emote,
+    text: &'static str,
+}
+
+#[wasm_bindgen(js_name = listEmotes)]
+pub fn list_emotes() -> JsValue {
+    let emotes: Vec<EmoteDescription> = Emote::into_enum_iter()
+        .map(|emote| EmoteDescription {
+            id: emote,
+            text: emote.text(),
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&emotes).unwrap()
+}
@@ -0,0 +1,89 @@
+// Draw detection by repeated positions and an optional limit on the number of moves
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GameEnding, GameState};
+
+// A position occurring this many times is a draw
+pub const REPETITION_LIMIT: u8 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionHistory {
+    // Times each position has occurred, keyed by a hash of the pieces, cards and side to move
+    seen: HashMap<u64, u8>,
+    moves: u32,
+    move_limit: Option<u32>,
+}
+
+impl PositionHistory {
+    pub fn new(state: &GameState, move_limit: Option<u32>) -> PositionHistory {
+        let mut history = PositionHistory {
+            seen: HashMap::new(),
+            moves: 0,
+            move_limit,
+        };
+        history.visit(state);
+        history
+    }
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+    pub fn move_limit(&self) -> Option<u32> {
+        self.move_limit
+    }
+    // Records the state a move led to, turning it into a draw when its position
+    // has now occurred three times or the move limit has been reached
+    pub fn record(&mut self, state: GameState) -> GameState {
+        self.moves += 1;
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => {
+                return state;
+            }
+        };
+        let occurrences = self.visit(&state);
+        let limit_reached = self.move_limit.is_some_and(|limit| self.moves >= limit);
+        let ending = match occurrences >= REPETITION_LIMIT {
+            true => GameEnding::Repetition,
+            false => GameEnding::MoveLimit,
+        };
+        if occurrences >= REPETITION_LIMIT || limit_reached {
+            return GameState::Finished {
+                board,
+                winner: None,
+                ending: Some(ending),
+            };
+        }
+        state
+    }
+    // Undoes recording the state, for when the move that led to it is taken back
+    pub fn forget(&mut self, state: &GameState) {
+        self.moves = self.moves.saturating_sub(1);
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => {
+                return;
+            }
+        };
+        let hash = board.zobrist_hash();
+        if let Some(occurrences) = self.seen.get_mut(&hash) {
+            *occurrences -= 1;
+            if *occurrences == 0 {
+                self.seen.remove(&hash);
+            }
+        }
+    }
+    fn visit(&mut self, state: &GameState) -> u8 {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => {
+                return 0;
+            }
+        };
+        let occurrences = self.seen.entry(board.zobrist_hash()).or_insert(0);
+        *occurrences += 1;
+        *occurrences
+    }
+}
@@ -2,9 +2,19 @@ extern crate console_error_panic_hook;
 pub use messages::*;
 pub use models::*;
 
+pub mod bitboard;
 mod board;
+pub mod book;
 mod cards;
+pub mod clock;
+pub mod history;
 mod models;
+pub mod notation;
+pub mod perft;
+pub mod record;
+pub mod solver;
+pub mod tablebase;
+mod zobrist;
 
 mod agents;
 pub use agents::*;
@@ -1,21 +1,284 @@
+use enum_iterator::IntoEnumIterator;
 use serde::{Deserialize, Serialize};
 
-use crate::models::Player;
+use crate::clock::{Clock, TimeControl};
+use crate::models::{Card, CardSet, Player};
 use crate::{GameState, Move};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum GameMessage {
     Joined,
     RequestRematch,
+    // The opponent dropped, their seat is held while they reconnect
+    Reconnecting,
+    // The opponent left and didn't come back in time, so their seat is free
     Disconnected,
+    // Sent by the server when a move draws the game
+    Drawn,
     Initialize {
         state: GameState,
         room_id: String,
         player: Player,
         waiting: bool,
+        // Only for rooms with a time control
+        #[serde(default)]
+        clock: Option<Clock>,
+        // Session token for rejoining the room in the same seat
+        #[serde(default)]
+        token: Option<String>,
+        // How the room was set up, only for online rooms
+        #[serde(default)]
+        settings: Option<RoomSettings>,
     },
+    // Sent to spectators instead of Initialize, they then receive every move
+    Spectate {
+        state: GameState,
+        room_id: String,
+        #[serde(default)]
+        clock: Option<Clock>,
+    },
+    // Number of spectators, sent to everyone in the room whenever it changes
+    Watchers {
+        count: u32,
+    },
+    // The server adds the clock after the move when the room has a time control
     Move {
         game_move: Move,
+        #[serde(default)]
+        clock: Option<Clock>,
+    },
+    // The clock after the player's own move, sent back to them by the server
+    Clock {
+        clock: Clock,
+    },
+    // The player ran out of time and lost
+    Timeout {
+        player: Player,
+    },
+    // Sent by a player giving up, the server tells everyone who resigned
+    Resign,
+    Resigned {
+        player: Player,
+    },
+    // Offers are passed to the opponent, who accepts or declines them
+    Offer {
+        offer: Offer,
+    },
+    // Sent to everyone once the offer has been applied
+    Accept {
+        offer: Offer,
+    },
+    // Sent back to the player who made the offer
+    Decline {
+        offer: Offer,
+    },
+    // Sent to everyone after a takeback, with the position play continues from
+    TakenBack {
+        state: GameState,
+        #[serde(default)]
+        clock: Option<Clock>,
+    },
+    // Sent by a player, the server relays it to everyone in the room as a Chat
+    SendChat {
+        content: ChatContent,
+    },
+    Chat {
+        message: ChatMessage,
+    },
+    // The room's chat so far, sent on joining
+    ChatHistory {
+        messages: Vec<ChatMessage>,
+    },
+    // The server refused to send a chat message, unlike Error the game carries on
+    ChatRejected {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+// Something a player can ask of their opponent during a game
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Offer {
+    Draw,
+    // Undoes moves back to before the asking player's last move
+    Takeback,
+}
+
+// Longest chat message, in characters
+pub const MAX_CHAT_LENGTH: usize = 200;
+
+// Preset messages, for players who shouldn't have free text
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, IntoEnumIterator)]
+pub enum Emote {
+    Hello,
+    GoodLuck,
+    NiceMove,
+    Oops,
+    Thanks,
+    GoodGame,
+}
+
+impl Emote {
+    pub fn text(&self) -> &'static str {
+        match self {
+            Emote::Hello => "Hello!",
+            Emote::GoodLuck => "Good luck!",
+            Emote::NiceMove => "Nice move!",
+            Emote::Oops => "Oops!",
+            Emote::Thanks => "Thanks!",
+            Emote::GoodGame => "Good game!",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum ChatContent {
+    Text { text: String },
+    Emote { emote: Emote },
+}
+
+impl ChatContent {
+    // The content as it should be sent, with text trimmed and checked against the length limit,
+    // control characters such as line breaks are only allowed at the ends where they're trimmed
+    pub fn cleaned(&self) -> Result<ChatContent, String> {
+        match self {
+            ChatContent::Text { text } => {
+                let text = text.trim();
+                if text.is_empty() {
+                    return Err("Chat messages can't be empty".to_string());
+                }
+                if text.chars().count() > MAX_CHAT_LENGTH {
+                    return Err(format!(
+                        "Chat messages can be at most {} characters",
+                        MAX_CHAT_LENGTH
+                    ));
+                }
+                if text.chars().any(char::is_control) {
+                    return Err("Chat messages can't contain control characters".to_string());
+                }
+                Ok(ChatContent::Text {
+                    text: text.to_string(),
+                })
+            }
+            ChatContent::Emote { emote } => Ok(ChatContent::Emote { emote: *emote }),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ChatMessage {
+    pub from: Player,
+    pub content: ChatContent,
+}
+
+// How an online room is set up, players are only matched with others wanting the same
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomSettings {
+    // Card sets dealt from, every card when empty
+    pub card_sets: Vec<CardSet>,
+    // Exact cards dealt from instead of whole sets, when not empty
+    #[serde(default)]
+    pub cards: Vec<Card>,
+    pub time_control: Option<TimeControl>,
+}
+
+// Fewest cards a game can be dealt from, two for each hand and the spare
+const MIN_CARDS: usize = 5;
+
+impl RoomSettings {
+    // Cards and card sets in a fixed order without repeats, with every card written as none,
+    // so settings that deal from the same cards compare equal
+    pub fn normalized(&self) -> RoomSettings {
+        let mut card_sets: Vec<CardSet> = CardSet::into_enum_iter()
+            .filter(|card_set| self.card_sets.contains(card_set))
+            .collect();
+        let mut cards: Vec<Card> = Card::into_enum_iter()
+            .filter(|card| self.cards.contains(card))
+            .collect();
+        if !cards.is_empty() || card_sets.len() == CardSet::into_enum_iter().count() {
+            card_sets = vec![];
+        }
+        if cards.len() == Card::into_enum_iter().count() {
+            cards = vec![];
+        }
+        RoomSettings {
+            card_sets,
+            cards,
+            time_control: self.time_control,
+        }
+    }
+    // Checks settings sent by a client can be played
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(time_control) = self.time_control {
+            time_control.validate()?;
+        }
+        let distinct = Card::into_enum_iter()
+            .filter(|card| self.cards.contains(card))
+            .count();
+        if !self.cards.is_empty() && distinct < MIN_CARDS {
+            return Err(format!("At least {} different cards are needed", MIN_CARDS));
+        }
+        Ok(())
+    }
+    pub fn new_game(&self) -> GameState {
+        match self.cards.is_empty() {
+            true => GameState::new_from_card_sets(&self.card_sets),
+            false => GameState::new_from_card_list(&self.cards),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicRoom {
+    pub room_id: String,
+    pub settings: RoomSettings,
+    // Seconds since the unix epoch
+    pub created: u64,
+}
+
+// Sent as JSON over the lobby socket
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum LobbyMessage {
+    // Joins the matchmaking queue, replacing any earlier seek
+    Seek {
+        settings: RoomSettings,
+    },
+    CancelSeek,
+    // Public rooms waiting for an opponent, sent on joining the lobby and whenever they change
+    Rooms {
+        rooms: Vec<PublicRoom>,
+    },
+    // In the queue, waiting for someone with the same settings
+    Seeking,
+    // Paired with an opponent, both players join the room as usual
+    #[serde(rename_all = "camelCase")]
+    Matched {
+        room_id: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+// Sent as JSON over a bot's socket, the protocol is described in BOTS.md
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum BotMessage {
+    // Connected and available to be challenged
+    Registered {
+        name: String,
+    },
+    // A player wants a game, the bot accepts by joining the room like any other player
+    #[serde(rename_all = "camelCase")]
+    Challenge {
+        room_id: String,
+        settings: RoomSettings,
     },
     Error {
         message: String,
@@ -1,11 +1,12 @@
 use std::fmt;
 use std::ops::{Add, Neg, Sub};
 
+use crate::bitboard::Bitboard;
 use crate::AiAgent;
 use enum_iterator::IntoEnumIterator;
 use serde::{Deserialize, Serialize};
 
-#[derive(Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Copy, Clone, Hash, Debug, Serialize, Deserialize)]
 pub enum Player {
     Red,
     Blue,
@@ -86,7 +87,7 @@ pub enum CardDirection {
     Balanced,
 }
 
-#[derive(Eq, PartialEq, Copy, Clone, IntoEnumIterator, Debug, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Copy, Clone, Hash, IntoEnumIterator, Debug, Serialize, Deserialize)]
 pub enum Card {
     // Base game
     Tiger,
@@ -181,8 +182,22 @@ impl From<CardSet> for CardSetDescription {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+// Pieces are stored as bitboards, but serialized in the same layout as `BoardRepr`
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "BoardRepr", into = "BoardRepr")]
 pub struct Board {
+    pub(crate) blue_pieces: Bitboard,
+    pub(crate) blue_king: Bitboard,
+    pub blue_hand: [Card; 2],
+    pub(crate) red_pieces: Bitboard,
+    pub(crate) red_king: Bitboard,
+    pub red_hand: [Card; 2],
+    pub spare_card: Card,
+    pub turn: Player,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BoardRepr {
     pub blue_king: Point,
     pub blue_pawns: [Option<Point>; 4],
     pub blue_hand: [Card; 2],
@@ -200,11 +215,32 @@ pub enum Move {
     Discard { card: Card },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
 #[serde(tag = "status")]
 pub enum GameState {
-    Playing { board: Board },
-    Finished { board: Board, winner: Player },
+    Playing {
+        board: Board,
+    },
+    // The winner is None when the game was drawn
+    Finished {
+        board: Board,
+        winner: Option<Player>,
+        // Missing for games that finished before endings were recorded
+        #[serde(default)]
+        ending: Option<GameEnding>,
+    },
+}
+
+// How a finished game came to an end
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, IntoEnumIterator)]
+pub enum GameEnding {
+    KingCaptured,
+    TempleReached,
+    Repetition,
+    MoveLimit,
+    Timeout,
+    Resignation,
+    DrawAgreed,
 }
 
 #[derive(Copy, Clone, Serialize, Deserialize, Debug)]
@@ -231,7 +267,8 @@ pub enum GameView {
         can_move: bool,
     },
     Finished {
-        winner: Player,
+        winner: Option<Player>,
+        ending: Option<GameEnding>,
         grid: [[GameSquare; 5]; 5],
         #[serde(rename = "redCards")]
         red_cards: Vec<CardDescription>,
@@ -281,8 +318,13 @@ impl From<&GameState> for GameView {
                 turn: board.turn,
                 can_move: board.can_move(),
             },
-            GameState::Finished { winner, board } => Self::Finished {
+            GameState::Finished {
+                winner,
+                board,
+                ending,
+            } => Self::Finished {
                 winner: *winner,
+                ending: *ending,
                 grid: board.to_grid(),
                 red_cards: board.red_hand.iter().map(to_card).collect(),
                 blue_cards: board.blue_hand.iter().map(to_card).collect(),
@@ -294,7 +336,7 @@ impl From<&GameState> for GameView {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct GameMeta {
     #[serde(default)]
     pub build: String,
@@ -336,4 +378,7 @@ pub enum GameEvent {
 pub struct MoveRequest {
     pub state: GameState,
     pub agent: AiAgent,
+    // Play from the opening book while the position is in it, when one is loaded
+    #[serde(default)]
+    pub book: bool,
 }
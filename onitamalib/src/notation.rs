@@ -0,0 +1,248 @@
+// Compact text notation for boards and moves
+//
+// Squares are named from Red's side of the board: files a-e run left to right and ranks 1-5
+// run from Red's back row to Blue's, so the red king starts on c1 and the blue king on c5.
+//
+// A board is written as the piece layout, Red's hand, Blue's hand, the spare card and the side to move:
+//     ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r
+// The layout lists ranks from 5 down to 1, with K/P for Red's king and pawns, k/p for Blue's,
+// and digits for runs of empty squares.
+//
+// A move is written as the card and its source and destination, `Tiger c1-c3`,
+// or as `discard Tiger` when no piece can move.
+use std::fmt;
+use std::str::FromStr;
+
+use enum_iterator::IntoEnumIterator;
+
+use crate::bitboard::{bit_of, player_index, Bitboard};
+use crate::{Board, Card, CardSet, Move, Player, Point};
+
+pub fn square_name(point: Point) -> String {
+    let file = (b'a' + point.x as u8) as char;
+    let rank = 5 - point.y;
+    format!("{}{}", file, rank)
+}
+
+pub fn parse_square(name: &str) -> Result<Point, String> {
+    let mut chars = name.chars();
+    let (file, rank) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(file), Some(rank), None) => (file, rank),
+        _ => {
+            return Err(format!("Invalid square: {}", name));
+        }
+    };
+    let point = match (file, rank.to_digit(10)) {
+        ('a'..='e', Some(rank @ 1..=5)) => Point {
+            x: (file as u8 - b'a') as i8,
+            y: 5 - rank as i8,
+        },
+        _ => {
+            return Err(format!("Invalid square: {}", name));
+        }
+    };
+    Ok(point)
+}
+
+impl FromStr for Card {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Card::into_enum_iter()
+            .find(|card| card.to_string().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown card: {}", name))
+    }
+}
+
+impl FromStr for CardSet {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        CardSet::into_enum_iter()
+            .find(|card_set| format!("{:?}", card_set).eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown card set: {}", name))
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Move { card, src, dst } => {
+                write!(f, "{} {}-{}", card, square_name(*src), square_name(*dst))
+            }
+            Move::Discard { card } => write!(f, "discard {}", card),
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = notation.split_whitespace().collect();
+        match parts.as_slice() {
+            [discard, card] if discard.eq_ignore_ascii_case("discard") => Ok(Move::Discard {
+                card: card.parse()?,
+            }),
+            [card, squares] => {
+                let (src, dst) = squares
+                    .split_once('-')
+                    .ok_or_else(|| format!("Invalid move squares: {}", squares))?;
+                Ok(Move::Move {
+                    card: card.parse()?,
+                    src: parse_square(src)?,
+                    dst: parse_square(dst)?,
+                })
+            }
+            _ => Err(format!("Invalid move: {}", notation)),
+        }
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ranks: Vec<String> = vec![];
+        for y in 0..5 {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for x in 0..5 {
+                let bit = bit_of(Point { x, y });
+                let piece = match (self.red_pieces & bit != 0, self.blue_pieces & bit != 0) {
+                    (true, _) if self.red_king & bit != 0 => Some('K'),
+                    (true, _) => Some('P'),
+                    (_, true) if self.blue_king & bit != 0 => Some('k'),
+                    (_, true) => Some('p'),
+                    (false, false) => None,
+                };
+                match piece {
+                    Some(piece) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank.push(piece);
+                    }
+                    None => {
+                        empty += 1;
+                    }
+                };
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            ranks.push(rank);
+        }
+        let turn = match self.turn {
+            Player::Red => 'r',
+            Player::Blue => 'b',
+        };
+        write!(
+            f,
+            "{} {},{} {},{} {} {}",
+            ranks.join("/"),
+            self.red_hand[0],
+            self.red_hand[1],
+            self.blue_hand[0],
+            self.blue_hand[1],
+            self.spare_card,
+            turn
+        )
+    }
+}
+
+fn parse_hand(hand: &str) -> Result<[Card; 2], String> {
+    match hand.split(',').collect::<Vec<&str>>().as_slice() {
+        [first, second] => Ok([first.parse()?, second.parse()?]),
+        _ => Err(format!("A hand must have two cards: {}", hand)),
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = notation.split_whitespace().collect();
+        let (layout, red_hand, blue_hand, spare_card, turn) = match parts.as_slice() {
+            [layout, red_hand, blue_hand, spare_card, turn] => {
+                (*layout, *red_hand, *blue_hand, *spare_card, *turn)
+            }
+            _ => {
+                return Err(format!("Expected five fields: {}", notation));
+            }
+        };
+        // Indexed by player
+        let mut kings: [Bitboard; 2] = [0; 2];
+        let mut pawns: [Bitboard; 2] = [0; 2];
+        let ranks: Vec<&str> = layout.split('/').collect();
+        if ranks.len() != 5 {
+            return Err(format!("Expected five ranks: {}", layout));
+        }
+        for (y, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            for piece in rank.chars() {
+                if let Some(empty) = piece.to_digit(10) {
+                    if empty == 0 || x + empty as i8 > 5 {
+                        return Err(format!("Rank must have five squares: {}", rank));
+                    }
+                    x += empty as i8;
+                    continue;
+                }
+                if x >= 5 {
+                    return Err(format!("Rank is too long: {}", rank));
+                }
+                let bit = bit_of(Point { x, y: y as i8 });
+                match piece {
+                    'K' => kings[player_index(Player::Red)] |= bit,
+                    'P' => pawns[player_index(Player::Red)] |= bit,
+                    'k' => kings[player_index(Player::Blue)] |= bit,
+                    'p' => pawns[player_index(Player::Blue)] |= bit,
+                    _ => {
+                        return Err(format!("Unknown piece: {}", piece));
+                    }
+                };
+                x += 1;
+            }
+            if x != 5 {
+                return Err(format!("Rank must have five squares: {}", rank));
+            }
+        }
+        for player in [Player::Red, Player::Blue] {
+            let idx = player_index(player);
+            if kings[idx].count_ones() != 1 {
+                return Err(format!("{:?} must have exactly one king", player));
+            }
+            if pawns[idx].count_ones() > 4 {
+                return Err(format!("{:?} has more than four pawns", player));
+            }
+        }
+        let red_hand = parse_hand(red_hand)?;
+        let blue_hand = parse_hand(blue_hand)?;
+        let spare_card: Card = spare_card.parse()?;
+        let cards = [
+            red_hand[0],
+            red_hand[1],
+            blue_hand[0],
+            blue_hand[1],
+            spare_card,
+        ];
+        for (idx, card) in cards.iter().enumerate() {
+            if cards[..idx].contains(card) {
+                return Err(format!("Card used twice: {}", card));
+            }
+        }
+        let turn = match turn {
+            "r" => Player::Red,
+            "b" => Player::Blue,
+            _ => {
+                return Err(format!("Side to move must be r or b: {}", turn));
+            }
+        };
+        let (red, blue) = (player_index(Player::Red), player_index(Player::Blue));
+        Ok(Board {
+            blue_pieces: kings[blue] | pawns[blue],
+            blue_king: kings[blue],
+            blue_hand,
+            red_pieces: kings[red] | pawns[red],
+            red_king: kings[red],
+            red_hand,
+            spare_card,
+            turn,
+        })
+    }
+}
@@ -0,0 +1,42 @@
+// Counts move sequences to a fixed depth, for checking the move generator against known counts
+//
+// Every legal move is played through `Board::try_move`, so the checks there are exercised too.
+// A move that ends the game is counted as a leaf only when it is the last move of the sequence,
+// like checkmate in chess perft.
+use crate::{Board, GameState, Move};
+
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|game_move| count(board, game_move, depth))
+        .sum()
+}
+
+// Leaf count below each root move
+pub fn divide(board: &Board, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return vec![];
+    }
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|game_move| (game_move, count(board, game_move, depth)))
+        .collect()
+}
+
+// Leaves below a move, with the move itself being the first of depth moves
+fn count(board: &Board, game_move: Move, depth: u32) -> u64 {
+    let state = match board.try_move(game_move) {
+        Ok(state) => state,
+        Err(err) => panic!("Generated illegal move {} on {}: {}", game_move, board, err),
+    };
+    match state {
+        GameState::Playing { board } => perft(&board, depth - 1),
+        GameState::Finished { .. } if depth == 1 => 1,
+        GameState::Finished { .. } => 0,
+    }
+}
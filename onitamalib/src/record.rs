@@ -0,0 +1,367 @@
+// A full game history with a PGN-like text format
+//
+//     [Red "Player"]
+//     [Blue "Alphabeta"]
+//     [BlueAgent "Alphabeta"]
+//     [Build "abc123"]
+//     [Uid "..."]
+//     [Started "1650000000000"]
+//     [Position "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r"]
+//     [MoveLimit "200"]
+//     [Result "1-0"]
+//     [Ending "TempleReached"]
+//
+//     1. Tiger c1-c3 {1500} Frog c5-b4 {3200}
+//     2. ...
+//     1-0
+//
+// Moves use the notation from `notation`, each followed by the milliseconds since the game started.
+// Results are 1-0 when Red won, 0-1 when Blue won, 1/2-1/2 for a draw and * while the game is still going.
+// The MoveLimit tag is only written when the game had one, so draws by the limit replay correctly.
+// The Ending tag says how a finished game ended, which is how a resignation, loss on time or
+// agreed draw, with a result the final position doesn't show, replays.
+use std::fmt;
+use std::str::FromStr;
+
+use enum_iterator::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+
+use crate::history::PositionHistory;
+use crate::{AiAgent, Board, GameEnding, GameMeta, GameState, Move, Player};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Participant {
+    pub name: String,
+    pub agent: Option<AiAgent>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedMove {
+    pub game_move: Move,
+    // Milliseconds since the game started
+    pub elapsed: u64,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GameResult {
+    Unfinished,
+    Won(Player),
+    Drawn,
+}
+
+// Endings decided off the board, which the record has to be told about
+fn declared_ending_fits(ending: GameEnding, result: GameResult) -> bool {
+    matches!(
+        (ending, result),
+        (GameEnding::Resignation, GameResult::Won(_))
+            | (GameEnding::Timeout, GameResult::Won(_))
+            | (GameEnding::DrawAgreed, GameResult::Drawn)
+    )
+}
+
+impl From<&GameState> for GameResult {
+    fn from(state: &GameState) -> Self {
+        match state {
+            GameState::Playing { .. } => GameResult::Unfinished,
+            GameState::Finished {
+                winner: Some(winner),
+                ..
+            } => GameResult::Won(*winner),
+            GameState::Finished { winner: None, .. } => GameResult::Drawn,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GameRecord {
+    pub start: Board,
+    pub moves: Vec<RecordedMove>,
+    pub result: GameResult,
+    pub ending: Option<GameEnding>,
+    // Moves after which the game was drawn, if it had a limit
+    pub move_limit: Option<u32>,
+    pub red: Participant,
+    pub blue: Participant,
+    pub meta: GameMeta,
+    // Milliseconds since the unix epoch
+    pub started: u64,
+}
+
+// Milliseconds since the unix epoch
+pub fn now_millis() -> u64 {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "web")] {
+            js_sys::Date::now() as u64
+        } else {
+            use std::time::{SystemTime, UNIX_EPOCH};
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as u64)
+        }
+    }
+}
+
+impl GameRecord {
+    pub fn new(start: Board) -> GameRecord {
+        GameRecord {
+            start,
+            moves: vec![],
+            result: GameResult::Unfinished,
+            ending: None,
+            move_limit: None,
+            red: Participant::default(),
+            blue: Participant::default(),
+            meta: GameMeta::blank(),
+            started: now_millis(),
+        }
+    }
+    // Records a move that has already been played, along with the state it led to
+    pub fn push(&mut self, game_move: Move, state: &GameState) {
+        self.moves.push(RecordedMove {
+            game_move,
+            elapsed: now_millis().saturating_sub(self.started),
+        });
+        self.result = GameResult::from(state);
+        self.ending = match state {
+            GameState::Finished { ending, .. } => *ending,
+            GameState::Playing { .. } => None,
+        };
+    }
+    // Ends the game without a move, such as when a player resigns
+    pub fn declare(&mut self, result: GameResult, ending: GameEnding) {
+        self.result = result;
+        self.ending = Some(ending);
+    }
+    // Plays every move from the start, failing on the first illegal one
+    pub fn replay(&self) -> Result<GameState, String> {
+        let mut state = GameState::Playing { board: self.start };
+        let mut history = PositionHistory::new(&state, self.move_limit);
+        for (idx, recorded) in self.moves.iter().enumerate() {
+            state = match state {
+                GameState::Playing { board } => {
+                    history.record(board.try_move(recorded.game_move).map_err(|err| {
+                        format!("Move {} ({}): {}", idx + 1, recorded.game_move, err)
+                    })?)
+                }
+                GameState::Finished { .. } => {
+                    return Err(format!("Move {} played after the game finished", idx + 1));
+                }
+            };
+        }
+        if GameResult::from(&state) == self.result {
+            return Ok(state);
+        }
+        match (state, self.ending) {
+            (GameState::Playing { board }, Some(ending))
+                if declared_ending_fits(ending, self.result) =>
+            {
+                let winner = match self.result {
+                    GameResult::Won(winner) => Some(winner),
+                    _ => None,
+                };
+                Ok(GameState::Finished {
+                    board,
+                    winner,
+                    ending: Some(ending),
+                })
+            }
+            _ => Err("Result does not match the final position".to_string()),
+        }
+    }
+}
+
+fn result_notation(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Won(Player::Red) => "1-0",
+        GameResult::Won(Player::Blue) => "0-1",
+        GameResult::Drawn => "1/2-1/2",
+        GameResult::Unfinished => "*",
+    }
+}
+
+fn parse_result(notation: &str) -> Option<GameResult> {
+    match notation {
+        "1-0" => Some(GameResult::Won(Player::Red)),
+        "0-1" => Some(GameResult::Won(Player::Blue)),
+        "1/2-1/2" => Some(GameResult::Drawn),
+        "*" => Some(GameResult::Unfinished),
+        _ => None,
+    }
+}
+
+fn parse_agent(name: &str) -> Result<AiAgent, String> {
+    AiAgent::into_enum_iter()
+        .find(|agent| format!("{:?}", agent) == name)
+        .ok_or_else(|| format!("Unknown agent: {}", name))
+}
+
+fn parse_ending(name: &str) -> Result<GameEnding, String> {
+    GameEnding::into_enum_iter()
+        .find(|ending| format!("{:?}", ending) == name)
+        .ok_or_else(|| format!("Unknown ending: {}", name))
+}
+
+fn write_tag(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(f, "[{} \"{}\"]", name, value)
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tag(f, "Red", &self.red.name)?;
+        write_tag(f, "Blue", &self.blue.name)?;
+        if let Some(agent) = self.red.agent {
+            write_tag(f, "RedAgent", &format!("{:?}", agent))?;
+        }
+        if let Some(agent) = self.blue.agent {
+            write_tag(f, "BlueAgent", &format!("{:?}", agent))?;
+        }
+        write_tag(f, "Build", &self.meta.build)?;
+        write_tag(f, "Uid", &self.meta.uid)?;
+        write_tag(f, "Started", &self.started.to_string())?;
+        write_tag(f, "Position", &self.start.to_string())?;
+        if let Some(limit) = self.move_limit {
+            write_tag(f, "MoveLimit", &limit.to_string())?;
+        }
+        write_tag(f, "Result", result_notation(self.result))?;
+        if let Some(ending) = self.ending {
+            write_tag(f, "Ending", &format!("{:?}", ending))?;
+        }
+        writeln!(f)?;
+        for (idx, pair) in self.moves.chunks(2).enumerate() {
+            write!(f, "{}.", idx + 1)?;
+            for recorded in pair {
+                write!(f, " {} {{{}}}", recorded.game_move, recorded.elapsed)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "{}", result_notation(self.result))
+    }
+}
+
+// Splits a tag line like `[Name "value"]` into its name and unescaped value
+fn parse_tag(line: &str) -> Result<(&str, String), String> {
+    let invalid = || format!("Invalid tag: {}", line);
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, quoted) = inner.split_once(' ').ok_or_else(invalid)?;
+    let quoted = quoted
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next().ok_or_else(invalid)?),
+            '"' => {
+                return Err(invalid());
+            }
+            c => value.push(c),
+        };
+    }
+    Ok((name, value))
+}
+
+impl FromStr for GameRecord {
+    type Err = String;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut start: Option<Board> = None;
+        let mut tagged_result: Option<GameResult> = None;
+        let mut ending: Option<GameEnding> = None;
+        let mut move_limit: Option<u32> = None;
+        let (mut red, mut blue) = (Participant::default(), Participant::default());
+        let mut meta = GameMeta::blank();
+        let mut started = 0;
+        let mut lines = text.lines().map(str::trim).peekable();
+        while let Some(line) = lines.next_if(|line| line.is_empty() || line.starts_with('[')) {
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = parse_tag(line)?;
+            match name {
+                "Red" => red.name = value,
+                "Blue" => blue.name = value,
+                "RedAgent" => red.agent = Some(parse_agent(&value)?),
+                "BlueAgent" => blue.agent = Some(parse_agent(&value)?),
+                "Build" => meta.build = value,
+                "Uid" => meta.uid = value,
+                "Started" => {
+                    started = value
+                        .parse()
+                        .map_err(|_| format!("Invalid start time: {}", value))?;
+                }
+                "Position" => start = Some(value.parse()?),
+                "MoveLimit" => {
+                    move_limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid move limit: {}", value))?,
+                    );
+                }
+                "Result" => {
+                    tagged_result = Some(
+                        parse_result(&value).ok_or_else(|| format!("Invalid result: {}", value))?,
+                    );
+                }
+                "Ending" => ending = Some(parse_ending(&value)?),
+                // Unknown tags are ignored, like in PGN
+                _ => {}
+            };
+        }
+        let mut record = GameRecord {
+            start: start.ok_or("Missing Position tag")?,
+            moves: vec![],
+            result: GameResult::Unfinished,
+            ending,
+            move_limit,
+            red,
+            blue,
+            meta,
+            started,
+        };
+        let movetext: Vec<&str> = lines.flat_map(str::split_whitespace).collect();
+        let mut tokens = movetext.into_iter().peekable();
+        let mut final_result: Option<GameResult> = None;
+        while let Some(token) = tokens.next() {
+            if final_result.is_some() {
+                return Err(format!("Unexpected text after the result: {}", token));
+            }
+            if let Some(result) = parse_result(token) {
+                final_result = Some(result);
+                continue;
+            }
+            // Move numbers are only there for people reading the record
+            if token.ends_with('.') && token[..token.len() - 1].parse::<u32>().is_ok() {
+                continue;
+            }
+            let second = tokens
+                .next()
+                .ok_or_else(|| format!("Incomplete move: {}", token))?;
+            let game_move: Move = format!("{} {}", token, second).parse()?;
+            let elapsed = match tokens.next_if(|token| token.starts_with('{')) {
+                Some(comment) => comment
+                    .strip_prefix('{')
+                    .and_then(|comment| comment.strip_suffix('}'))
+                    .and_then(|elapsed| elapsed.parse().ok())
+                    .ok_or_else(|| format!("Invalid move time: {}", comment))?,
+                None => 0,
+            };
+            record.moves.push(RecordedMove { game_move, elapsed });
+        }
+        record.result = match (tagged_result, final_result) {
+            (Some(tagged), Some(result)) if tagged != result => {
+                return Err("Result tag does not match the result after the moves".to_string());
+            }
+            (Some(result), _) | (None, Some(result)) => result,
+            (None, None) => GameResult::Unfinished,
+        };
+        record.replay()?;
+        Ok(record)
+    }
+}
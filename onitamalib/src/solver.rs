@@ -0,0 +1,439 @@
+// Game-theoretic solver, finding how a position ends with perfect play
+//
+// Each question "can this player force a win" is answered by proof-number search, which grows the
+// game tree towards whichever line is cheapest to settle. A position is a draw when neither player
+// can force a win. Returning to a position already on the line counts as a draw, as in the tablebases.
+//
+// Settled positions are kept by hash and saved to a checkpoint file as the search goes, so a solve
+// that is stopped picks up where it left off. Positions only settled because of a repetition depend
+// on how they were reached, so they are never kept.
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use instant::{Duration, Instant};
+
+use crate::bitboard::player_index;
+use crate::tablebase::Tablebases;
+use crate::{Board, GameState, Move, Player};
+
+const MAGIC: &[u8; 4] = b"ONSV";
+const VERSION: u8 = 1;
+const INFINITY: u32 = u32::MAX;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    Win(Player),
+    Draw,
+}
+
+impl Outcome {
+    // Higher is better for the player
+    fn value(&self, player: Player) -> i8 {
+        match self {
+            Outcome::Win(winner) if *winner == player => 1,
+            Outcome::Win(_) => -1,
+            Outcome::Draw => 0,
+        }
+    }
+}
+
+/// The outcome of a position, and of each move from it
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub outcome: Outcome,
+    pub moves: Vec<(Move, Outcome)>,
+}
+
+impl Solution {
+    // The moves keeping the outcome with perfect play
+    pub fn best_moves(&self) -> Vec<Move> {
+        self.moves
+            .iter()
+            .filter(|(_, outcome)| *outcome == self.outcome)
+            .map(|(game_move, _)| *game_move)
+            .collect()
+    }
+}
+
+struct Node {
+    board: Board,
+    hash: u64,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    proof: u32,
+    disproof: u32,
+    // Disproved only because of a repetition on the line leading here
+    repeated: bool,
+}
+
+impl Node {
+    fn solved(&self) -> bool {
+        self.proof == 0 || self.disproof == 0
+    }
+}
+
+fn add(a: u32, b: u32) -> u32 {
+    match (a, b) {
+        (INFINITY, _) | (_, INFINITY) => INFINITY,
+        (a, b) => a.saturating_add(b).min(INFINITY - 1),
+    }
+}
+
+pub struct Solver {
+    // Whether each player can force a win from positions settled so far, by hash
+    solved: [HashMap<u64, bool>; 2],
+    tablebases: Tablebases,
+    // Search trees are started afresh from the solved positions once they reach this size
+    max_nodes: usize,
+    checkpoint: Option<(PathBuf, Duration)>,
+    last_checkpoint: Instant,
+    // Nodes expanded, across every search
+    pub expanded: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            solved: [HashMap::new(), HashMap::new()],
+            tablebases: Tablebases::default(),
+            max_nodes: 10_000_000,
+            checkpoint: None,
+            last_checkpoint: Instant::now(),
+            expanded: 0,
+        }
+    }
+    pub fn with_tablebases(mut self, tablebases: Tablebases) -> Solver {
+        self.tablebases = tablebases;
+        self
+    }
+    pub fn max_nodes(mut self, max_nodes: usize) -> Solver {
+        self.max_nodes = max_nodes.max(2);
+        self
+    }
+    // Saves settled positions to the file every interval, resuming from it if it exists
+    pub fn checkpoint_to(mut self, path: PathBuf, interval: Duration) -> Result<Solver, String> {
+        if path.exists() {
+            let file = File::open(&path).map_err(|err| err.to_string())?;
+            self.read_checkpoint(&mut BufReader::new(file))?;
+            log::info!("Resumed with {} solved positions", self.solved_count());
+        }
+        self.checkpoint = Some((path, interval));
+        self.last_checkpoint = Instant::now();
+        Ok(self)
+    }
+    pub fn solved_count(&self) -> usize {
+        self.solved.iter().map(HashMap::len).sum()
+    }
+    // Solves the position and every move from it, reporting each move as it is solved
+    pub fn solve<F>(&mut self, board: &Board, mut report: F) -> Result<Solution, String>
+    where
+        F: FnMut(Move, Outcome),
+    {
+        let history = [board.zobrist_hash()];
+        let mut moves = vec![];
+        for game_move in board.legal_moves() {
+            let outcome = match board.try_move(game_move)? {
+                GameState::Finished {
+                    winner: Some(winner),
+                    ..
+                } => Outcome::Win(winner),
+                GameState::Finished { winner: None, .. } => Outcome::Draw,
+                GameState::Playing { board } => self.outcome(&board, &history)?,
+            };
+            report(game_move, outcome);
+            moves.push((game_move, outcome));
+        }
+        let outcome = moves
+            .iter()
+            .map(|(_, outcome)| *outcome)
+            .max_by_key(|outcome| outcome.value(board.turn))
+            .ok_or_else(|| "No legal moves".to_string())?;
+        self.save_checkpoint()?;
+        Ok(Solution { outcome, moves })
+    }
+    // The outcome with perfect play, the history holding positions before this one that count as repeats
+    pub fn outcome(&mut self, board: &Board, history: &[u64]) -> Result<Outcome, String> {
+        for player in [board.turn, board.turn.invert()] {
+            if self.proves_win(board, history, player)? {
+                return Ok(Outcome::Win(player));
+            }
+        }
+        Ok(Outcome::Draw)
+    }
+    // Whether the attacker can force a win from the position
+    pub fn proves_win(
+        &mut self,
+        board: &Board,
+        history: &[u64],
+        attacker: Player,
+    ) -> Result<bool, String> {
+        let mut solved_at_restart = self.solved_count();
+        let mut nodes = vec![self.leaf(*board, None, history, &[], attacker)];
+        // Slots of nodes below settled positions, reused for new nodes
+        let mut free = vec![];
+        while !nodes[0].solved() {
+            if nodes.len() - free.len() >= self.max_nodes {
+                if self.solved_count() == solved_at_restart {
+                    self.save_checkpoint()?;
+                    return Err(
+                        "Search tree is full without progress, allow more nodes".to_string()
+                    );
+                }
+                log::info!(
+                    "Restarting search with {} solved positions",
+                    self.solved_count()
+                );
+                solved_at_restart = self.solved_count();
+                nodes = vec![self.leaf(*board, None, history, &[], attacker)];
+                free.clear();
+                continue;
+            }
+            let idx = most_proving(&nodes, attacker);
+            self.expand(&mut nodes, &mut free, idx, history, attacker);
+            self.update(&mut nodes, &mut free, idx, attacker);
+            self.expanded += 1;
+            self.checkpoint_if_due()?;
+        }
+        Ok(nodes[0].proof == 0)
+    }
+    // A new node, settled straight away when its outcome is already known
+    fn leaf(
+        &self,
+        board: Board,
+        parent: Option<usize>,
+        history: &[u64],
+        nodes: &[Node],
+        attacker: Player,
+    ) -> Node {
+        let hash = board.zobrist_hash();
+        let mut node = Node {
+            board,
+            hash,
+            parent,
+            children: vec![],
+            proof: 1,
+            disproof: 1,
+            repeated: history.contains(&hash),
+        };
+        let mut line = parent;
+        while let Some(idx) = line {
+            if nodes[idx].hash == hash {
+                node.repeated = true;
+            }
+            line = nodes[idx].parent;
+        }
+        let known = match self.solved[player_index(attacker)].get(&hash) {
+            _ if node.repeated => Some(false),
+            Some(proven) => Some(*proven),
+            None => self
+                .tablebases
+                .probe(&board)
+                .map(|value| value.winner(board.turn) == Some(attacker)),
+        };
+        let known = known.or_else(|| match has_winning_move(&board) {
+            true => Some(board.turn == attacker),
+            false => None,
+        });
+        match known {
+            Some(true) => {
+                node.proof = 0;
+                node.disproof = INFINITY;
+            }
+            Some(false) => {
+                node.proof = INFINITY;
+                node.disproof = 0;
+            }
+            None => {
+                let moves = board.legal_moves().len() as u32;
+                match board.turn == attacker {
+                    true => node.disproof = moves,
+                    false => node.proof = moves,
+                };
+            }
+        };
+        node
+    }
+    fn expand(
+        &self,
+        nodes: &mut Vec<Node>,
+        free: &mut Vec<usize>,
+        idx: usize,
+        history: &[u64],
+        attacker: Player,
+    ) {
+        let board = nodes[idx].board;
+        for game_move in board.legal_moves() {
+            // Nodes with a winning move are settled before being expanded, so every move continues the game
+            let next = match board.try_move(game_move) {
+                Ok(GameState::Playing { board }) => board,
+                _ => panic!("Expanded a position with a winning move"),
+            };
+            let child = self.leaf(next, Some(idx), history, nodes, attacker);
+            let child = match free.pop() {
+                Some(slot) => {
+                    nodes[slot] = child;
+                    slot
+                }
+                None => {
+                    nodes.push(child);
+                    nodes.len() - 1
+                }
+            };
+            nodes[idx].children.push(child);
+        }
+    }
+    // Recomputes the numbers of the node and its ancestors, keeping any that become settled
+    fn update(&mut self, nodes: &mut [Node], free: &mut Vec<usize>, idx: usize, attacker: Player) {
+        let mut current = Some(idx);
+        while let Some(idx) = current {
+            let children = &nodes[idx].children;
+            let attacking = nodes[idx].board.turn == attacker;
+            let proofs = children.iter().map(|child| nodes[*child].proof);
+            let disproofs = children.iter().map(|child| nodes[*child].disproof);
+            let (proof, disproof) = match attacking {
+                true => (proofs.min(), disproofs.reduce(add)),
+                false => (proofs.reduce(add), disproofs.min()),
+            };
+            let (proof, disproof) = (proof.unwrap_or(INFINITY), disproof.unwrap_or(0));
+            let mut disproved = children
+                .iter()
+                .map(|child| &nodes[*child])
+                .filter(|child| child.disproof == 0);
+            // A disproof holds everywhere unless it relied on a repetition the defender couldn't avoid
+            let repeated = disproof == 0
+                && match attacking {
+                    true => disproved.any(|child| child.repeated),
+                    false => disproved.all(|child| child.repeated),
+                };
+            let node = &mut nodes[idx];
+            let unchanged = (node.proof, node.disproof) == (proof, disproof);
+            node.proof = proof;
+            node.disproof = disproof;
+            node.repeated = repeated;
+            if !node.solved() {
+                if unchanged {
+                    break;
+                }
+                current = node.parent;
+                continue;
+            }
+            if !repeated {
+                self.solved[player_index(attacker)].insert(node.hash, proof == 0);
+            }
+            current = node.parent;
+            // Nothing below a settled position is searched again
+            let mut released = std::mem::take(&mut node.children);
+            while let Some(child) = released.pop() {
+                released.append(&mut nodes[child].children);
+                free.push(child);
+            }
+        }
+    }
+}
+
+// The leaf to expand next, following the cheapest line to settle the root
+fn most_proving(nodes: &[Node], attacker: Player) -> usize {
+    let mut idx = 0;
+    while !nodes[idx].children.is_empty() {
+        let children = nodes[idx].children.iter().copied();
+        let next = match nodes[idx].board.turn == attacker {
+            true => children.min_by_key(|child| nodes[*child].proof),
+            false => children.min_by_key(|child| nodes[*child].disproof),
+        };
+        idx = next.expect("Node has children");
+    }
+    idx
+}
+
+fn has_winning_move(board: &Board) -> bool {
+    board.legal_moves().into_iter().any(|game_move| {
+        let state = board
+            .try_move(game_move)
+            .expect("Solver generated illegal move");
+        matches!(state, GameState::Finished { .. })
+    })
+}
+
+/// Checkpoints
+///
+impl Solver {
+    fn checkpoint_if_due(&mut self) -> Result<(), String> {
+        match &self.checkpoint {
+            Some((_, interval)) if self.last_checkpoint.elapsed() >= *interval => {
+                self.save_checkpoint()
+            }
+            _ => Ok(()),
+        }
+    }
+    pub fn save_checkpoint(&mut self) -> Result<(), String> {
+        let path = match &self.checkpoint {
+            Some((path, _)) => path.clone(),
+            None => {
+                return Ok(());
+            }
+        };
+        // Written beside the checkpoint then renamed over it, so stopping mid-write keeps the old one
+        let partial = path.with_extension("partial");
+        let file = File::create(&partial).map_err(|err| err.to_string())?;
+        let mut out = BufWriter::new(file);
+        self.write_checkpoint(&mut out)?;
+        out.flush().map_err(|err| err.to_string())?;
+        drop(out);
+        fs::rename(&partial, &path).map_err(|err| err.to_string())?;
+        self.last_checkpoint = Instant::now();
+        log::info!(
+            "Saved {} solved positions to {}",
+            self.solved_count(),
+            path.display()
+        );
+        Ok(())
+    }
+    fn write_checkpoint<W: Write>(&self, out: &mut W) -> Result<(), String> {
+        let mut header = MAGIC.to_vec();
+        header.push(VERSION);
+        out.write_all(&header).map_err(|err| err.to_string())?;
+        for attacker in [Player::Red, Player::Blue] {
+            for (hash, proven) in self.solved[player_index(attacker)].iter() {
+                let mut entry = hash.to_le_bytes().to_vec();
+                entry.push(player_index(attacker) as u8 | (*proven as u8) << 1);
+                out.write_all(&entry).map_err(|err| err.to_string())?;
+            }
+        }
+        Ok(())
+    }
+    fn read_checkpoint<R: Read>(&mut self, input: &mut R) -> Result<(), String> {
+        let mut header = [0u8; 5];
+        input
+            .read_exact(&mut header)
+            .map_err(|err| err.to_string())?;
+        if &header[..4] != MAGIC {
+            return Err("Not a solver checkpoint".to_string());
+        }
+        if header[4] != VERSION {
+            return Err(format!("Unsupported checkpoint version {}", header[4]));
+        }
+        let mut data = vec![];
+        input
+            .read_to_end(&mut data)
+            .map_err(|err| err.to_string())?;
+        if data.len() % 9 != 0 {
+            return Err("Checkpoint is truncated".to_string());
+        }
+        for entry in data.chunks(9) {
+            let mut hash = [0u8; 8];
+            hash.copy_from_slice(&entry[..8]);
+            let attacker = (entry[8] & 1) as usize;
+            let proven = entry[8] & 2 != 0;
+            self.solved[attacker].insert(u64::from_le_bytes(hash), proven);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,652 @@
+// Endgame tablebases: every position with few pawns for one deal of five cards, solved exactly
+//
+// Tables are built by retrograde analysis, working back from positions one move from winning,
+// and with less material first so captures lead into positions that are already solved.
+// Each position takes one byte, zero for a draw and otherwise the plies until the game ends
+// with best play, which is odd when the side to move wins and even when it loses.
+// Repeating positions count as draws, and any move limit is ignored.
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::bitboard::{goal_square, move_mask, squares, Bitboard, CARD_COUNT};
+use crate::{Board, Card, GameState, Move, Player};
+
+const MAGIC: &[u8; 4] = b"ONTB";
+const VERSION: u8 = 1;
+// Ways to split five cards into two hands and a spare
+const DEALS: usize = 30;
+const NO_SLOT: u8 = u8::MAX;
+// Longest distance an entry can hold
+const MAX_PLIES: usize = u8::MAX as usize;
+
+// Binomial coefficients, for numbering sets of up to four pawns
+static BINOMIALS: [[usize; 5]; 26] = build_binomials();
+
+const fn build_binomials() -> [[usize; 5]; 26] {
+    let mut binomials = [[0; 5]; 26];
+    let mut n = 0;
+    while n < 26 {
+        binomials[n][0] = 1;
+        let mut k = 1;
+        while k < 5 && k <= n {
+            binomials[n][k] = binomials[n - 1][k - 1] + binomials[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    binomials
+}
+
+// Number of ways to place the pawns
+fn placements(pawns: u8) -> usize {
+    BINOMIALS[25][pawns as usize]
+}
+
+// Position of a set of squares in colexicographic order among sets of the same size
+fn rank(set: Bitboard) -> usize {
+    squares(set)
+        .enumerate()
+        .map(|(idx, square)| BINOMIALS[square as usize][idx + 1])
+        .sum()
+}
+
+fn unrank(mut rank: usize, pawns: u8) -> Bitboard {
+    let mut set = 0;
+    for k in (1..=pawns as usize).rev() {
+        let square = (0..25).rev().find(|square| BINOMIALS[*square][k] <= rank);
+        let square = square.expect("Rank out of range");
+        rank -= BINOMIALS[square][k];
+        set |= 1 << square;
+    }
+    set
+}
+
+/// The most pawns each side can have in the positions a table covers
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Material {
+    pub red: u8,
+    pub blue: u8,
+}
+
+/// How a position ends with best play, counted in plies
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TableValue {
+    // The side to move wins
+    Win(u8),
+    // The side to move loses
+    Loss(u8),
+    Draw,
+}
+
+impl TableValue {
+    fn from_entry(entry: u8) -> TableValue {
+        match entry {
+            0 => TableValue::Draw,
+            plies if plies % 2 == 1 => TableValue::Win(plies),
+            plies => TableValue::Loss(plies),
+        }
+    }
+    // The value of the position before the move leading to this one
+    fn before_move(&self) -> Option<TableValue> {
+        match *self {
+            TableValue::Draw => Some(TableValue::Draw),
+            TableValue::Win(plies) => plies.checked_add(1).map(TableValue::Loss),
+            TableValue::Loss(plies) => plies.checked_add(1).map(TableValue::Win),
+        }
+    }
+    // Higher is better for the side to move, winning sooner and losing later
+    fn preference(&self) -> i32 {
+        match *self {
+            TableValue::Win(plies) => 1000 - plies as i32,
+            TableValue::Draw => 0,
+            TableValue::Loss(plies) => plies as i32 - 1000,
+        }
+    }
+    pub fn winner(&self, turn: Player) -> Option<Player> {
+        match self {
+            TableValue::Win(_) => Some(turn),
+            TableValue::Loss(_) => Some(turn.invert()),
+            TableValue::Draw => None,
+        }
+    }
+    // The score the agents give the position, Red maximizing
+    pub fn score(&self, turn: Player) -> i64 {
+        match self.winner(turn) {
+            Some(Player::Red) => i64::MAX,
+            Some(Player::Blue) => i64::MIN,
+            None => 0,
+        }
+    }
+}
+
+/// Counts of the positions in a table, by how they end
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TableStats {
+    pub positions: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub draws: u64,
+    // Plies in the longest forced win
+    pub longest: u8,
+}
+
+/// Solved positions for one deal of cards, up to a number of pawns each side
+pub struct Tablebase {
+    // Sorted by index, position in this array is a card's slot
+    cards: [Card; 5],
+    material: Material,
+    slots: [u8; CARD_COUNT],
+    // Slots in the red and blue hands for each deal
+    deals: [(u8, u8); DEALS],
+    // The deal with the given slots in the red and blue hands
+    deal_index: [[u8; 32]; 32],
+    // Where the positions with each number of pawns start
+    offsets: Vec<usize>,
+    entries: Vec<u8>,
+}
+
+// Entries in a table of the material, failing for material too large to index.
+// Positions are numbered with u32 while solving, so each count of pawns must fit in one
+fn material_size(material: Material) -> Result<usize, String> {
+    if material.red > 4 || material.blue > 4 {
+        return Err("Each side has at most four pawns".to_string());
+    }
+    let mut size: u64 = 0;
+    for red in 0..=material.red {
+        for blue in 0..=material.blue {
+            let positions =
+                2 * DEALS as u64 * 625 * placements(red) as u64 * placements(blue) as u64;
+            if positions > u32::MAX as u64 {
+                return Err(format!(
+                    "Too many positions with {} red and {} blue pawns for a table",
+                    red, blue
+                ));
+            }
+            size += positions;
+        }
+    }
+    usize::try_from(size).map_err(|_| "Table is too large for this platform".to_string())
+}
+
+impl Tablebase {
+    fn empty(cards: [Card; 5], material: Material) -> Result<Tablebase, String> {
+        Tablebase::with_entries(cards, material, vec![0; material_size(material)?])
+    }
+    fn with_entries(
+        cards: [Card; 5],
+        material: Material,
+        entries: Vec<u8>,
+    ) -> Result<Tablebase, String> {
+        if entries.len() != material_size(material)? {
+            return Err("Wrong number of entries for the material".to_string());
+        }
+        let mut cards = cards;
+        cards.sort_by_key(|card| card.index());
+        if cards.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err("Cards must be different".to_string());
+        }
+        let mut slots = [NO_SLOT; CARD_COUNT];
+        for (slot, card) in cards.iter().enumerate() {
+            slots[card.index() as usize] = slot as u8;
+        }
+        let mut deals = [(0, 0); DEALS];
+        let mut deal_index = [[0; 32]; 32];
+        let pairs = |available: u8| {
+            (0..5)
+                .flat_map(move |a| (a + 1..5).map(move |b| (1 << a) | (1 << b)))
+                .filter(move |pair| pair & available == *pair)
+        };
+        let mut deal = 0;
+        for red in pairs(0b11111) {
+            for blue in pairs(0b11111 & !red) {
+                deals[deal] = (red, blue);
+                deal_index[red as usize][blue as usize] = deal as u8;
+                deal += 1;
+            }
+        }
+        let mut offsets = vec![];
+        let mut size = 0;
+        for red in 0..=material.red {
+            for blue in 0..=material.blue {
+                offsets.push(size);
+                size += table_size(red, blue);
+            }
+        }
+        Ok(Tablebase {
+            cards,
+            material,
+            slots,
+            deals,
+            deal_index,
+            offsets,
+            entries,
+        })
+    }
+    pub fn cards(&self) -> [Card; 5] {
+        self.cards
+    }
+    pub fn material(&self) -> Material {
+        self.material
+    }
+    fn offset(&self, red: u8, blue: u8) -> usize {
+        self.offsets[red as usize * (self.material.blue as usize + 1) + blue as usize]
+    }
+    // Where the position is in the table, if the table covers it
+    fn index(&self, board: &Board) -> Option<usize> {
+        let (red_pawns, blue_pawns) = (board.pawns(Player::Red), board.pawns(Player::Blue));
+        let (red, blue) = (red_pawns.count_ones() as u8, blue_pawns.count_ones() as u8);
+        if red > self.material.red || blue > self.material.blue {
+            return None;
+        }
+        let hand_slots = |hand: &[Card; 2]| {
+            hand.iter()
+                .try_fold(0u8, |slots, card| match self.slots[card.index() as usize] {
+                    NO_SLOT => None,
+                    slot => Some(slots | 1 << slot),
+                })
+        };
+        let red_slots = hand_slots(&board.red_hand)?;
+        let blue_slots = hand_slots(&board.blue_hand)?;
+        if self.slots[board.spare_card.index() as usize] == NO_SLOT {
+            return None;
+        }
+        let deal = self.deal_index[red_slots as usize][blue_slots as usize] as usize;
+        let turn = match board.turn {
+            Player::Red => 0,
+            Player::Blue => 1,
+        };
+        let kings = board.red_king.trailing_zeros() as usize * 25
+            + board.blue_king.trailing_zeros() as usize;
+        let idx = ((turn * DEALS + deal) * 625 + kings) * placements(red) + rank(red_pawns);
+        Some(self.offset(red, blue) + idx * placements(blue) + rank(blue_pawns))
+    }
+    // The position at an index within the positions with the given pawns,
+    // None for positions that can't occur, such as a king on the opposing temple
+    fn board_at(&self, red: u8, blue: u8, idx: usize) -> Option<Board> {
+        let blue_pawns = unrank(idx % placements(blue), blue);
+        let idx = idx / placements(blue);
+        let red_pawns = unrank(idx % placements(red), red);
+        let idx = idx / placements(red);
+        let blue_king: Bitboard = 1 << (idx % 25);
+        let red_king: Bitboard = 1 << (idx / 25 % 25);
+        let idx = idx / 625;
+        let (red_slots, blue_slots) = self.deals[idx % DEALS];
+        let turn = match idx / DEALS {
+            0 => Player::Red,
+            _ => Player::Blue,
+        };
+        let pieces = [red_king, blue_king, red_pawns, blue_pawns];
+        let occupied = pieces.iter().fold(0, |occupied, pieces| occupied | pieces);
+        let overlapping =
+            occupied.count_ones() != pieces.iter().map(|p| p.count_ones()).sum::<u32>();
+        if overlapping
+            || red_king == 1 << goal_square(Player::Red)
+            || blue_king == 1 << goal_square(Player::Blue)
+        {
+            return None;
+        }
+        let cards_in = |slots: u8| {
+            (0..5)
+                .filter(move |slot| slots & (1 << slot) != 0)
+                .map(move |slot| self.cards[slot])
+        };
+        let hand = |slots: u8| {
+            let mut cards = cards_in(slots);
+            [cards.next().unwrap(), cards.next().unwrap()]
+        };
+        let (red_hand, blue_hand) = (hand(red_slots), hand(blue_slots));
+        let spare_card = cards_in(!(red_slots | blue_slots)).next().unwrap();
+        Some(Board {
+            blue_pieces: blue_king | blue_pawns,
+            blue_king,
+            blue_hand,
+            red_pieces: red_king | red_pawns,
+            red_king,
+            red_hand,
+            spare_card,
+            turn,
+        })
+    }
+    pub fn probe(&self, board: &Board) -> Option<TableValue> {
+        self.index(board)
+            .map(|idx| TableValue::from_entry(self.entries[idx]))
+    }
+    // The move with the best outcome, and how the game ends after it
+    pub fn best_move(&self, board: &Board) -> Option<(Move, TableValue)> {
+        self.index(board)?;
+        let mut best: Option<(Move, TableValue)> = None;
+        for game_move in board.legal_moves() {
+            let value = match board.try_move(game_move).expect("Generated illegal move") {
+                GameState::Finished { .. } => TableValue::Win(1),
+                GameState::Playing { board } => self.probe(&board)?.before_move()?,
+            };
+            match best {
+                Some((_, best_value)) if best_value.preference() >= value.preference() => {}
+                _ => {
+                    best = Some((game_move, value));
+                }
+            };
+        }
+        best
+    }
+    // Every position the table covers, with its value
+    pub fn positions(&self) -> impl Iterator<Item = (Board, TableValue)> + '_ {
+        let (red, blue) = (self.material.red, self.material.blue);
+        (0..=red)
+            .flat_map(move |red| (0..=blue).map(move |blue| (red, blue)))
+            .flat_map(move |(red, blue)| {
+                (0..table_size(red, blue)).filter_map(move |idx| {
+                    let board = self.board_at(red, blue, idx)?;
+                    let entry = self.entries[self.offset(red, blue) + idx];
+                    Some((board, TableValue::from_entry(entry)))
+                })
+            })
+    }
+    pub fn stats(&self) -> TableStats {
+        let mut stats = TableStats::default();
+        for (_, value) in self.positions() {
+            stats.positions += 1;
+            match value {
+                TableValue::Win(plies) => {
+                    stats.wins += 1;
+                    stats.longest = stats.longest.max(plies);
+                }
+                TableValue::Loss(_) => {
+                    stats.losses += 1;
+                }
+                TableValue::Draw => {
+                    stats.draws += 1;
+                }
+            };
+        }
+        stats
+    }
+}
+
+// Positions with exactly the given pawns, including ones that can't occur
+fn table_size(red: u8, blue: u8) -> usize {
+    2 * DEALS * 625 * placements(red) * placements(blue)
+}
+
+/// Generation
+///
+impl Tablebase {
+    // Solves every position of the deal with at most the given pawns
+    pub fn generate(cards: [Card; 5], material: Material) -> Result<Tablebase, String> {
+        Tablebase::generate_within(cards, material, MAX_PLIES)
+    }
+    // Fails if any position takes more than the given plies to decide
+    pub(crate) fn generate_within(
+        cards: [Card; 5],
+        material: Material,
+        max_plies: usize,
+    ) -> Result<Tablebase, String> {
+        let mut table = Tablebase::empty(cards, material)?;
+        for red in 0..=material.red {
+            for blue in 0..=material.blue {
+                table.solve(red, blue, max_plies.min(MAX_PLIES))?;
+            }
+        }
+        Ok(table)
+    }
+    // Solves the positions with exactly these pawns, those with fewer must already be solved
+    fn solve(&mut self, red: u8, blue: u8, max_plies: usize) -> Result<(), String> {
+        let too_long = || Err("Too many plies to a forced win for the table".to_string());
+        let offset = self.offset(red, blue);
+        let size = table_size(red, blue);
+        // Moves from each position whose outcome isn't yet known to be a win for the opponent
+        let mut unsolved_moves = vec![0u8; size];
+        // Positions winning in some plies by capturing into an already solved position
+        let mut wins_at: Vec<Vec<u32>> = vec![vec![]; max_plies + 2];
+        // Positions with a capture leading to a win for the opponent in some plies
+        let mut replies_at: Vec<Vec<u32>> = vec![vec![]; max_plies + 1];
+        for (idx, moves) in unsolved_moves.iter_mut().enumerate() {
+            let board = match self.board_at(red, blue, idx) {
+                Some(board) => board,
+                None => continue,
+            };
+            for game_move in board.legal_moves() {
+                let next = match board.try_move(game_move).expect("Generated illegal move") {
+                    GameState::Finished { .. } => {
+                        wins_at[1].push(idx as u32);
+                        break;
+                    }
+                    GameState::Playing { board } => board,
+                };
+                *moves += 1;
+                let next_idx = self.index(&next).expect("Move left the table");
+                if next_idx >= offset {
+                    continue;
+                }
+                match TableValue::from_entry(self.entries[next_idx]) {
+                    TableValue::Loss(plies) => wins_at[plies as usize + 1].push(idx as u32),
+                    TableValue::Win(plies) => replies_at[plies as usize].push(idx as u32),
+                    TableValue::Draw => {}
+                };
+            }
+        }
+        let mut next: Vec<u32> = vec![];
+        for plies in 1..=max_plies {
+            // Positions found to end in this many plies
+            let mut solved = std::mem::take(&mut next);
+            for idx in wins_at[plies].drain(..) {
+                if self.entries[offset + idx as usize] == 0 {
+                    self.entries[offset + idx as usize] = plies as u8;
+                    solved.push(idx);
+                }
+            }
+            for idx in replies_at[plies - 1].drain(..) {
+                if self.entries[offset + idx as usize] == 0 {
+                    unsolved_moves[idx as usize] -= 1;
+                    if unsolved_moves[idx as usize] == 0 {
+                        self.entries[offset + idx as usize] = plies as u8;
+                        solved.push(idx);
+                    }
+                }
+            }
+            for idx in solved {
+                let board = self.board_at(red, blue, idx as usize).unwrap();
+                let loses = plies % 2 == 0;
+                for previous in predecessors(&board) {
+                    let previous = self.index(&previous).expect("Unmove left the table") - offset;
+                    if self.entries[offset + previous] != 0 {
+                        continue;
+                    }
+                    if !loses {
+                        unsolved_moves[previous] -= 1;
+                        if unsolved_moves[previous] != 0 {
+                            continue;
+                        }
+                    }
+                    if plies == max_plies {
+                        return too_long();
+                    }
+                    self.entries[offset + previous] = plies as u8 + 1;
+                    next.push(previous as u32);
+                }
+            }
+        }
+        // Captures into the longest results would decide positions past the limit
+        for idx in wins_at[max_plies + 1].drain(..) {
+            if self.entries[offset + idx as usize] == 0 {
+                return too_long();
+            }
+        }
+        for idx in replies_at[max_plies].drain(..) {
+            if self.entries[offset + idx as usize] == 0 {
+                unsolved_moves[idx as usize] -= 1;
+                if unsolved_moves[idx as usize] == 0 {
+                    return too_long();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Positions where the last player to move could have reached this one without a capture
+fn predecessors(board: &Board) -> Vec<Board> {
+    let player = board.turn;
+    let mover = player.invert();
+    let occupied = board.red_pieces | board.blue_pieces;
+    // The played card became the spare, and the mover took the old spare into their hand
+    let played = board.spare_card;
+    let mover_hand = match mover {
+        Player::Red => board.red_hand,
+        Player::Blue => board.blue_hand,
+    };
+    let mut previous = vec![];
+    for (taken, kept) in [(0, 1), (1, 0)] {
+        let mut before = *board;
+        before.turn = mover;
+        before.spare_card = mover_hand[taken];
+        let hand = [mover_hand[kept], played];
+        match mover {
+            Player::Red => before.red_hand = hand,
+            Player::Blue => before.blue_hand = hand,
+        };
+        // Discarding only happens when no piece can move
+        if !before.can_move() {
+            previous.push(before);
+        }
+        for dst in squares(board.pieces(mover)) {
+            // Moving back uses the card as the other player would
+            for src in squares(move_mask(played, player, dst) & !occupied) {
+                let (dst_bit, src_bit) = (1 << dst, 1 << src);
+                let mut moved = before;
+                let (pieces, king) = match mover {
+                    Player::Red => (&mut moved.red_pieces, &mut moved.red_king),
+                    Player::Blue => (&mut moved.blue_pieces, &mut moved.blue_king),
+                };
+                *pieces ^= dst_bit | src_bit;
+                if *king == dst_bit {
+                    // A king on the opposing temple would have already won
+                    if src == goal_square(mover) {
+                        continue;
+                    }
+                    *king = src_bit;
+                }
+                previous.push(moved);
+            }
+        }
+    }
+    previous
+}
+
+/// Files
+///
+impl Tablebase {
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), String> {
+        let mut header = MAGIC.to_vec();
+        header.push(VERSION);
+        header.extend(self.cards.iter().map(|card| card.index() as u8));
+        header.extend([self.material.red, self.material.blue]);
+        out.write_all(&header).map_err(|err| err.to_string())?;
+        out.write_all(&self.entries).map_err(|err| err.to_string())
+    }
+    pub fn read_from<R: Read>(input: &mut R) -> Result<Tablebase, String> {
+        let mut header = [0u8; 12];
+        input
+            .read_exact(&mut header)
+            .map_err(|err| err.to_string())?;
+        if &header[..4] != MAGIC {
+            return Err("Not a tablebase".to_string());
+        }
+        if header[4] != VERSION {
+            return Err(format!("Unsupported tablebase version {}", header[4]));
+        }
+        let mut cards = [Card::Tiger; 5];
+        for (card, idx) in cards.iter_mut().zip(header[5..10].iter()) {
+            if *idx as usize >= CARD_COUNT {
+                return Err(format!("Unknown card {}", idx));
+            }
+            *card = Card::from_index(*idx as u32);
+        }
+        let material = Material {
+            red: header[10],
+            blue: header[11],
+        };
+        // Checked against the data before anything that size is allocated
+        let size = material_size(material)?;
+        let mut entries = vec![];
+        input
+            .take(size as u64 + 1)
+            .read_to_end(&mut entries)
+            .map_err(|err| err.to_string())?;
+        if entries.len() < size {
+            return Err("Tablebase is shorter than expected".to_string());
+        }
+        if entries.len() > size {
+            return Err("Tablebase is longer than expected".to_string());
+        }
+        Tablebase::with_entries(cards, material, entries)
+    }
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut out = BufWriter::new(file);
+        self.write_to(&mut out)?;
+        out.flush().map_err(|err| err.to_string())
+    }
+    pub fn load(path: &Path) -> Result<Tablebase, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        Tablebase::read_from(&mut BufReader::new(file))
+    }
+}
+
+/// Probing
+///
+// Tables the agents consult, for any deal they cover
+static INSTALLED: RwLock<Vec<Arc<Tablebase>>> = RwLock::new(Vec::new());
+
+// Makes a table available to the agents, replacing any for the same deal
+pub fn install(table: Tablebase) {
+    let mut installed = INSTALLED.write().expect("Tablebases poisoned");
+    installed.retain(|existing| existing.cards != table.cards);
+    installed.push(Arc::new(table));
+}
+
+// Stops the agents using the table for a deal, if one is installed
+pub fn uninstall(cards: [Card; 5]) {
+    let mut cards = cards;
+    cards.sort_by_key(|card| card.index());
+    let mut installed = INSTALLED.write().expect("Tablebases poisoned");
+    installed.retain(|existing| existing.cards != cards);
+}
+
+/// The tables installed when it was taken, cheap to clone and probe from any thread
+#[derive(Clone, Default)]
+pub struct Tablebases {
+    tables: Vec<Arc<Tablebase>>,
+}
+
+pub fn installed() -> Tablebases {
+    let installed = INSTALLED.read().expect("Tablebases poisoned");
+    Tablebases {
+        tables: installed.clone(),
+    }
+}
+
+impl Tablebases {
+    pub fn probe(&self, board: &Board) -> Option<TableValue> {
+        self.tables.iter().find_map(|table| table.probe(board))
+    }
+    pub fn best_move(&self, board: &Board) -> Option<(Move, TableValue)> {
+        self.tables.iter().find_map(|table| table.best_move(board))
+    }
+}
+
+// The best move by the installed tables, scored the way the agents score moves
+pub fn table_move(state: &GameState) -> Option<(Move, i64)> {
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => {
+            return None;
+        }
+    };
+    let (game_move, value) = installed().best_move(board)?;
+    log::info!("Playing tablebase move, {:?}", value);
+    Some((game_move, value.score(board.turn)))
+}
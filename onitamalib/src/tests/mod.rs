@@ -1,3 +1,21 @@
 mod test_alphabeta;
+mod test_analysis;
+mod test_board;
+mod test_book;
+mod test_chat;
+mod test_clock;
+mod test_heuristics;
+mod test_history;
+mod test_mcts;
 mod test_montecarlo;
+mod test_notation;
+#[cfg(feature = "parallel")]
+mod test_parallel;
+mod test_perft;
+mod test_progress;
+mod test_record;
+mod test_settings;
+mod test_solver;
+mod test_tablebase;
+mod test_transposition;
 mod utils;
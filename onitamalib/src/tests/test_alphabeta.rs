@@ -1,23 +1,27 @@
 use indicatif::ProgressBar;
 use instant::Duration;
 
-use crate::{alphabeta, minimax, GameState};
+use crate::agents::heuristics::EvalWeights;
+use crate::alphabeta::{Search, SearchLimits};
+use crate::{alphabeta, minimax, SearchInfo};
 
 use super::utils;
 
 const SHARED_DEPTH: u16 = 3;
+const NODE_COUNT_DEPTH: u16 = 5;
 
 #[test]
 fn alphabeta_equivalent_to_minimax() {
     let test_states = utils::generate_test_states();
+    let weights = EvalWeights::default();
     let bar = ProgressBar::new(100);
     for state in test_states.into_iter() {
         bar.inc(1);
-        let ab_value = match alphabeta::optimal_move(&state, SHARED_DEPTH) {
+        let ab_value = match alphabeta::optimal_move(&state, SHARED_DEPTH, &weights) {
             None => panic!("Alphabeta returned None"),
             Some((_, expected)) => expected,
         };
-        let minimax_value = match minimax::optimal_move(&state, SHARED_DEPTH) {
+        let minimax_value = match minimax::optimal_move(&state, SHARED_DEPTH, &weights) {
             None => panic!("Minimax returned None"),
             Some((_, expected)) => expected,
         };
@@ -35,7 +39,7 @@ fn alphabeta_benchmark() {
     let mut counted = 0u64;
     for state in test_states.into_iter() {
         bar.inc(1);
-        match alphabeta::iterative_deepening_just_depth(&state, duration) {
+        match alphabeta::iterative_deepening_just_depth(&state, duration, &EvalWeights::default()) {
             None => {}
             Some(depth) => {
                 total_depth += depth as u64;
@@ -49,4 +53,60 @@ fn alphabeta_benchmark() {
         "Average Depth: {} out of {} timeouts",
         average_depth, counted
     );
+    let test_states = utils::generate_test_states();
+    let mut ordered_nodes = vec![0u64; NODE_COUNT_DEPTH as usize];
+    let mut unordered_nodes = vec![0u64; NODE_COUNT_DEPTH as usize];
+    for state in test_states.iter() {
+        let counts = alphabeta::nodes_per_depth(state, NODE_COUNT_DEPTH, true);
+        for (total, nodes) in ordered_nodes.iter_mut().zip(counts) {
+            *total += nodes;
+        }
+        let counts = alphabeta::nodes_per_depth(state, NODE_COUNT_DEPTH, false);
+        for (total, nodes) in unordered_nodes.iter_mut().zip(counts) {
+            *total += nodes;
+        }
+    }
+    for (depth, (ordered, unordered)) in ordered_nodes.iter().zip(unordered_nodes).enumerate() {
+        println!(
+            "Depth {}: {} nodes ordered, {} nodes unordered",
+            depth + 1,
+            ordered,
+            unordered
+        );
+    }
+}
+
+#[test]
+fn searches_stop_at_their_limits() {
+    let weights = EvalWeights::default();
+    for state in utils::generate_test_states().into_iter().take(10) {
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let mut search = Search::new(weights);
+        let mut depths = vec![];
+        let result =
+            alphabeta::deepen_within(&state, &limits, &mut search, &mut |info: &SearchInfo| {
+                assert!(!info.pv.is_empty() && info.pv.len() <= info.depth as usize);
+                assert!(info
+                    .to_string()
+                    .starts_with(&format!("depth {}, ", info.depth)));
+                depths.push(info.depth);
+            });
+        let (depth, best) = result.unwrap();
+        // Searches that find a forced result stop early
+        assert!(depth <= 4);
+        assert_eq!(depths, (1..=depth).collect::<Vec<u16>>());
+        let expected = alphabeta::optimal_move(&state, depth, &weights).map(|(_, score)| score);
+        assert_eq!(Some(best.1), expected);
+        let limits = SearchLimits {
+            nodes: Some(1000),
+            ..SearchLimits::default()
+        };
+        let mut search = Search::new(weights);
+        alphabeta::deepen_within(&state, &limits, &mut search, &mut |_: &SearchInfo| {});
+        // The limit is checked every so many nodes, so it's only passed by a few
+        assert!(search.nodes < 1000 + 1024);
+    }
 }
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use crate::book::OpeningBook;
+use crate::{analyse, AiAgent, AnalysisRequest, Board, GameState, Player};
+
+#[test]
+fn analysis_ranks_every_legal_move() {
+    let board: Board = "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r"
+        .parse()
+        .unwrap();
+    let request = AnalysisRequest {
+        state: GameState::Playing { board },
+        agent: AiAgent::Alphabeta,
+        time: 100,
+        book: false,
+    };
+    let analysis = analyse(&request, None).unwrap();
+    let mut legal = board.legal_moves();
+    assert!(legal.contains(&analysis.best_move));
+    let search = analysis.search.unwrap();
+    assert_eq!(search.best_move(), Some(analysis.best_move));
+    assert_eq!(search.score, analysis.score);
+    assert_eq!(analysis.ranked.len(), legal.len());
+    for ranked in analysis.ranked.iter() {
+        legal.retain(|game_move| *game_move != ranked.game_move);
+    }
+    assert!(legal.is_empty());
+    // Red moves, so the highest score comes first
+    let scores: Vec<i64> = analysis.ranked.iter().map(|ranked| ranked.score).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+    let finished = AnalysisRequest {
+        state: GameState::Finished {
+            board,
+            winner: None,
+            ending: None,
+        },
+        ..request
+    };
+    assert!(analyse(&finished, None).is_err());
+}
+
+#[test]
+fn analysis_plays_book_moves_when_asked() {
+    let board: Board = "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r"
+        .parse()
+        .unwrap();
+    let book_move = board.legal_moves()[0];
+    let mut book = OpeningBook::new();
+    book.record(&board, book_move, Some(Player::Red));
+    let book = Arc::new(book);
+    let request = AnalysisRequest {
+        state: GameState::Playing { board },
+        agent: AiAgent::Alphabeta,
+        time: 100,
+        book: true,
+    };
+    // Book moves aren't searched
+    let analysis = analyse(&request, Some(Arc::clone(&book))).unwrap();
+    assert_eq!(analysis.best_move, book_move);
+    assert!(analysis.search.is_none());
+    let unbooked = AnalysisRequest {
+        book: false,
+        ..request
+    };
+    assert!(analyse(&unbooked, Some(book)).unwrap().search.is_some());
+}
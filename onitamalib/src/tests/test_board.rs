@@ -0,0 +1,131 @@
+use crate::{Board, GameState, Move, Player, Point};
+
+use super::utils;
+
+fn all_points() -> Vec<Point> {
+    (0..25)
+        .map(|idx| Point {
+            x: idx % 5,
+            y: idx / 5,
+        })
+        .collect()
+}
+
+// Legal moves worked out directly from the card offsets, without bitboards
+fn naive_legal_moves(board: &Board) -> Vec<Move> {
+    let pieces = board.player_pieces();
+    let mut moves = vec![];
+    for card in board.player_hand() {
+        for src in pieces.iter().filter_map(|p| *p) {
+            for dst in all_points() {
+                let delta = match board.turn {
+                    Player::Red => dst - src,
+                    Player::Blue => src - dst,
+                };
+                if card.moves().contains(&delta) && !pieces.contains(&Some(dst)) {
+                    moves.push(Move::Move {
+                        card: *card,
+                        src,
+                        dst,
+                    });
+                }
+            }
+        }
+    }
+    moves
+}
+
+#[test]
+fn legal_moves_match_card_offsets() {
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let legal_moves = board.legal_moves();
+        let expected = naive_legal_moves(&board);
+        assert_eq!(board.can_move(), !expected.is_empty());
+        if expected.is_empty() {
+            assert!(legal_moves
+                .iter()
+                .all(|game_move| matches!(game_move, Move::Discard { .. })));
+            continue;
+        }
+        assert_eq!(legal_moves.len(), expected.len());
+        for game_move in expected.iter() {
+            assert!(legal_moves.contains(game_move));
+            assert!(board.try_move(*game_move).is_ok());
+        }
+    }
+}
+
+#[test]
+fn try_move_rejects_illegal_moves() {
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let legal_moves = board.legal_moves();
+        for card in board.player_hand() {
+            for src in all_points() {
+                for dst in all_points() {
+                    let game_move = Move::Move {
+                        card: *card,
+                        src,
+                        dst,
+                    };
+                    assert_eq!(
+                        board.try_move(game_move).is_ok(),
+                        legal_moves.contains(&game_move)
+                    );
+                }
+            }
+        }
+        let discard = Move::Discard {
+            card: board.spare_card,
+        };
+        assert!(board.try_move(discard).is_err());
+    }
+}
+
+#[test]
+fn random_legal_move_is_legal() {
+    let mut rng = rand::thread_rng();
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let legal_moves = board.legal_moves();
+        for _ in 0..10 {
+            assert!(legal_moves.contains(&board.random_legal_move(&mut rng)));
+        }
+    }
+}
+
+#[test]
+fn board_serialization_round_trips() {
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let encoded = serde_cbor::to_vec(&board).unwrap();
+        let decoded: Board = serde_cbor::from_slice(&encoded).unwrap();
+        assert_eq!(board, decoded);
+        for game_move in board.legal_moves() {
+            let next = board.try_move(game_move).unwrap();
+            let encoded = serde_cbor::to_vec(&next).unwrap();
+            let decoded: GameState = serde_cbor::from_slice(&encoded).unwrap();
+            let (next, decoded) = match (next, decoded) {
+                (GameState::Playing { board: a }, GameState::Playing { board: b }) => (a, b),
+                (GameState::Finished { board: a, .. }, GameState::Finished { board: b, .. }) => {
+                    (a, b)
+                }
+                _ => panic!("Game status changed in serialization"),
+            };
+            assert_eq!(next, decoded);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use instant::Duration;
+use rand::thread_rng;
+
+use crate::book::OpeningBook;
+use crate::{AgentSession, AiAgent, Board, Card, GameState, Move, Player, Point};
+
+fn opening() -> Board {
+    "ppkpp/5/5/5/PPKPP Frog,Eel Rabbit,Cobra Tiger r"
+        .parse()
+        .unwrap()
+}
+
+// Frog and Eel are the mirror images of Rabbit and Cobra
+fn mirrored_opening() -> Board {
+    "ppkpp/5/5/5/PPKPP Rabbit,Cobra Frog,Eel Tiger r"
+        .parse()
+        .unwrap()
+}
+
+// A move from the opening, and the same move in the mirrored opening
+fn opening_move() -> (Move, Move) {
+    let game_move = Move::Move {
+        card: Card::Frog,
+        src: Point { x: 4, y: 4 },
+        dst: Point { x: 3, y: 3 },
+    };
+    let mirrored_move = Move::Move {
+        card: Card::Rabbit,
+        src: Point { x: 0, y: 4 },
+        dst: Point { x: 1, y: 3 },
+    };
+    (game_move, mirrored_move)
+}
+
+#[test]
+fn mirrored_positions_share_moves() {
+    let board = opening();
+    let (game_move, mirrored_move) = opening_move();
+    assert!(board.try_move(game_move).is_ok());
+    let mut book = OpeningBook::new();
+    book.record(&board, game_move, Some(Player::Red));
+    book.record(&board, game_move, None);
+    assert_eq!(book.len(), 1);
+    let moves = book.moves(&mirrored_opening());
+    assert_eq!(moves.len(), 1);
+    let (played, results) = moves[0];
+    assert_eq!(played, mirrored_move);
+    assert_eq!((results.wins, results.draws, results.losses), (1, 1, 0));
+    assert_eq!(book.moves(&board), vec![(game_move, results)]);
+}
+
+#[test]
+fn books_survive_saving() {
+    let board = opening();
+    let mut book = OpeningBook::new();
+    for (idx, game_move) in board.legal_moves().into_iter().enumerate() {
+        let winner = match idx % 3 {
+            0 => Some(Player::Red),
+            1 => Some(Player::Blue),
+            _ => None,
+        };
+        book.record(&board, game_move, winner);
+    }
+    let mut data = vec![];
+    book.write_to(&mut data).unwrap();
+    let loaded = OpeningBook::read_from(&mut data.as_slice()).unwrap();
+    assert_eq!(loaded.len(), book.len());
+    // Moves are saved in one orientation, both must still read back
+    for board in [board, mirrored_opening()] {
+        assert_eq!(loaded.moves(&board), book.moves(&board));
+        assert!(loaded.choose(&board, &mut thread_rng()).is_some());
+    }
+    // The first move's card and squares follow the header and the position's key and count
+    let first_move = 4 + 1 + 4 + 8 + 1;
+    let mut unknown_card = data.clone();
+    unknown_card[first_move] = u8::MAX;
+    assert!(OpeningBook::read_from(&mut unknown_card.as_slice()).is_err());
+    let mut off_board = data.clone();
+    off_board[first_move + 1] = 25;
+    assert!(OpeningBook::read_from(&mut off_board.as_slice()).is_err());
+    data.pop();
+    assert!(OpeningBook::read_from(&mut data.as_slice()).is_err());
+}
+
+#[test]
+fn losing_moves_are_left_out() {
+    let board = opening();
+    let moves = board.legal_moves();
+    let mut book = OpeningBook::new();
+    book.record(&board, moves[0], Some(Player::Blue));
+    // Nothing has done well enough, so the agent searches instead
+    assert_eq!(book.choose(&board, &mut thread_rng()), None);
+    book.record(&board, moves[1], None);
+    book.record(&board, moves[2], Some(Player::Red));
+    book.record(&board, moves[2], Some(Player::Blue));
+    book.record(&board, moves[2], Some(Player::Blue));
+    for _ in 0..20 {
+        assert_eq!(book.choose(&board, &mut thread_rng()), Some(moves[1]));
+    }
+}
+
+#[test]
+fn agents_play_book_moves() {
+    let (game_move, mirrored_move) = opening_move();
+    let mut book = OpeningBook::new();
+    book.record(&opening(), game_move, Some(Player::Red));
+    let mut session = AgentSession::new(AiAgent::Greedy).with_book(Arc::new(book));
+    // Recorded from the opening, played in its mirror image
+    let state = GameState::Playing {
+        board: mirrored_opening(),
+    };
+    let (played, _) = session
+        .play_move(&state, Duration::from_millis(10))
+        .unwrap();
+    assert_eq!(played, mirrored_move);
+    // Out of the book the agent searches as usual
+    let next = state.try_move(played).unwrap();
+    assert!(session
+        .play_move(&next, Duration::from_millis(10))
+        .is_some());
+}
@@ -0,0 +1,21 @@
+use crate::{ChatContent, Emote, MAX_CHAT_LENGTH};
+
+#[test]
+fn chat_messages_are_trimmed_and_limited() {
+    let text = |text: &str| ChatContent::Text {
+        text: text.to_string(),
+    };
+    assert_eq!(text("  hi there \n").cleaned(), Ok(text("hi there")));
+    assert!(text("").cleaned().is_err());
+    assert!(text("   ").cleaned().is_err());
+    let longest = "文".repeat(MAX_CHAT_LENGTH);
+    assert_eq!(text(&longest).cleaned(), Ok(text(&longest)));
+    assert!(text(&format!("{}!", longest)).cleaned().is_err());
+    assert!(text("line\nbreak").cleaned().is_err());
+    assert!(text("bell\u{7}").cleaned().is_err());
+    assert!(text("\u{1b}[2Jclear").cleaned().is_err());
+    let emote = ChatContent::Emote {
+        emote: Emote::GoodGame,
+    };
+    assert_eq!(emote.cleaned(), Ok(emote.clone()));
+}
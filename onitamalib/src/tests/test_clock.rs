@@ -0,0 +1,59 @@
+use crate::clock::{Clock, TimeControl};
+use crate::Player;
+
+#[test]
+fn time_controls_round_trip() {
+    for text in ["sudden:300", "fischer:180:2", "move:30"] {
+        let control: TimeControl = text.parse().unwrap();
+        assert_eq!(control.to_string(), text);
+    }
+    assert_eq!(
+        "fischer:60:5".parse(),
+        Ok(TimeControl::Fischer {
+            initial: 60_000,
+            increment: 5000,
+        })
+    );
+    for text in [
+        "",
+        "sudden",
+        "sudden:0",
+        "sudden:-1",
+        "fischer:60",
+        "move:1:2",
+        "blitz:60",
+    ] {
+        assert!(text.parse::<TimeControl>().is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn turns_are_charged_by_time_control() {
+    let mut clock = Clock::new(TimeControl::SuddenDeath { total: 10_000 });
+    assert!(clock.finish_turn(Player::Red, 4000));
+    assert_eq!((clock.red, clock.blue), (6000, 10_000));
+
+    let mut clock = Clock::new(TimeControl::Fischer {
+        initial: 10_000,
+        increment: 2000,
+    });
+    assert!(clock.finish_turn(Player::Blue, 1000));
+    assert_eq!(clock.remaining(Player::Blue), 11_000);
+
+    let mut clock = Clock::new(TimeControl::PerMove { per_move: 5000 });
+    assert!(clock.finish_turn(Player::Red, 4999));
+    assert_eq!(clock.remaining(Player::Red), 5000);
+}
+
+#[test]
+fn flag_falls_when_time_runs_out() {
+    let mut clock = Clock::new(TimeControl::Fischer {
+        initial: 3000,
+        increment: 1000,
+    });
+    assert_eq!(clock.after(Player::Red, 1000).red, 2000);
+    assert_eq!(clock.after(Player::Red, 5000).red, 0);
+    // The increment only comes once the move is made in time
+    assert!(!clock.finish_turn(Player::Red, 3000));
+    assert_eq!((clock.red, clock.blue), (0, 3000));
+}
@@ -0,0 +1,62 @@
+use std::convert::TryFrom;
+
+use crate::heuristics::EvalWeights;
+use crate::{Board, BoardRepr, GameState, Point};
+
+use super::utils;
+
+// Swaps the players, rotating the board so each side keeps moving towards the other
+fn swap_sides(board: &Board) -> Board {
+    let repr = BoardRepr::from(*board);
+    let rotate = |point: Point| Point {
+        x: 4 - point.x,
+        y: 4 - point.y,
+    };
+    let rotate_pawns = |pawns: [Option<Point>; 4]| pawns.map(|pawn| pawn.map(rotate));
+    Board::try_from(BoardRepr {
+        blue_king: rotate(repr.red_king),
+        blue_pawns: rotate_pawns(repr.red_pawns),
+        blue_hand: repr.red_hand,
+        red_king: rotate(repr.blue_king),
+        red_pawns: rotate_pawns(repr.blue_pawns),
+        red_hand: repr.blue_hand,
+        spare_card: repr.spare_card,
+        turn: repr.turn.invert(),
+    })
+    .expect("Swapped board is invalid")
+}
+
+#[test]
+fn evaluation_is_symmetric() {
+    let weights = EvalWeights::default();
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let swapped = GameState::Playing {
+            board: swap_sides(&board),
+        };
+        assert_eq!(state.evaluate(&weights), -swapped.evaluate(&weights));
+        assert_eq!(state.basic_value(), -swapped.basic_value());
+    }
+}
+
+#[test]
+fn evaluation_rewards_advanced_king() {
+    let weights = EvalWeights {
+        king_distance: 1,
+        ..EvalWeights::pawns_only()
+    };
+    let state = utils::generate_test_states()[0];
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    };
+    let mut repr = BoardRepr::from(board);
+    repr.red_king = Point { x: 2, y: 2 };
+    let advanced = GameState::Playing {
+        board: Board::try_from(repr).unwrap(),
+    };
+    assert_eq!(state.evaluate(&weights) + 2, advanced.evaluate(&weights));
+}
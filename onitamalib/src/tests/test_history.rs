@@ -0,0 +1,80 @@
+use rand::prelude::*;
+
+use crate::history::PositionHistory;
+use crate::record::{GameRecord, GameResult};
+use crate::{Board, GameEnding, GameState};
+
+fn drawn(state: &GameState) -> bool {
+    matches!(state, GameState::Finished { winner: None, .. })
+}
+
+#[test]
+fn third_repetition_is_drawn() {
+    let state = GameState::new();
+    let mut history = PositionHistory::new(&state, None);
+    assert!(!drawn(&history.record(state)));
+    assert!(drawn(&history.record(state)));
+}
+
+#[test]
+fn forgotten_positions_are_not_repeated() {
+    let state = GameState::new();
+    let mut history = PositionHistory::new(&state, None);
+    history.record(state);
+    // Taking back the move that led to the second occurrence
+    history.forget(&state);
+    assert_eq!(history.moves(), 0);
+    assert!(!drawn(&history.record(state)));
+    let ending = match history.record(state) {
+        GameState::Finished { ending, .. } => ending,
+        GameState::Playing { .. } => None,
+    };
+    assert_eq!(ending, Some(GameEnding::Repetition));
+}
+
+#[test]
+fn repetition_is_keyed_by_cards() {
+    let board = Board::new();
+    let mut history = PositionHistory::new(&GameState::Playing { board }, None);
+    // The same position with the hand in a different order
+    let mut swapped = board;
+    swapped.red_hand.swap(0, 1);
+    assert!(!drawn(
+        &history.record(GameState::Playing { board: swapped })
+    ));
+    // The same pieces with different cards
+    let mut exchanged = board;
+    std::mem::swap(&mut exchanged.red_hand[0], &mut exchanged.spare_card);
+    let exchanged = GameState::Playing { board: exchanged };
+    assert!(!drawn(&history.record(exchanged)));
+    assert!(!drawn(&history.record(exchanged)));
+    assert!(drawn(&history.record(GameState::Playing { board })));
+}
+
+#[test]
+fn move_limit_draws_and_replays() {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut state = GameState::new_rng(&mut rng);
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    };
+    let mut history = PositionHistory::new(&state, Some(4));
+    let mut record = GameRecord::new(board);
+    record.move_limit = Some(4);
+    while let GameState::Playing { board } = state {
+        let game_move = board.random_legal_move(&mut rng);
+        state = history.record(state.try_move(game_move).unwrap());
+        record.push(game_move, &state);
+    }
+    assert_eq!(history.moves(), 4);
+    assert!(drawn(&state));
+    assert_eq!(record.result, GameResult::Drawn);
+    let text = record.to_string();
+    let parsed: GameRecord = text.parse().unwrap();
+    assert_eq!(parsed, record, "{}", text);
+    // Without the limit the same moves leave the game unfinished
+    let mut unlimited = record.clone();
+    unlimited.move_limit = None;
+    assert!(unlimited.replay().is_err());
+}
@@ -0,0 +1,73 @@
+use instant::Duration;
+
+use super::utils;
+use crate::mcts::{MctsConfig, MctsTree, PlayoutPolicy};
+use crate::{GameState, Move};
+
+const SEARCH_DURATION: Duration = Duration::from_millis(50);
+
+fn winning_moves(state: &GameState) -> Vec<Move> {
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    };
+    board
+        .legal_moves()
+        .into_iter()
+        .filter(|game_move| match board.try_move(*game_move) {
+            Ok(GameState::Finished { winner, .. }) => winner == Some(board.turn),
+            _ => false,
+        })
+        .collect()
+}
+
+#[test]
+fn mcts_takes_winning_moves() {
+    let states: Vec<GameState> = utils::generate_test_states()
+        .into_iter()
+        .filter(|state| !winning_moves(state).is_empty())
+        .take(5)
+        .collect();
+    assert!(!states.is_empty());
+    for policy in [PlayoutPolicy::Random, PlayoutPolicy::WinningMoves] {
+        for state in states.iter() {
+            let config = MctsConfig {
+                playout: policy,
+                ..MctsConfig::default()
+            };
+            let mut tree = MctsTree::new(config);
+            let (game_move, _) = tree.play_move(state, SEARCH_DURATION).unwrap();
+            assert!(winning_moves(state).contains(&game_move));
+        }
+    }
+}
+
+#[test]
+fn mcts_reuses_tree() {
+    let state = utils::generate_test_states()[0];
+    let mut tree = MctsTree::default();
+    let (game_move, _) = tree.play_move(&state, SEARCH_DURATION).unwrap();
+    let searched = tree.root_visits();
+    assert!(searched > 0);
+    let state = state.try_move(game_move).unwrap();
+    tree.play_move(&state, Duration::from_millis(0));
+    let reused = tree.root_visits();
+    assert!(reused > 0 && reused < searched);
+}
+
+#[test]
+fn mcts_moves_without_time_to_search() {
+    let state = utils::generate_test_states()[0];
+    let mut tree = MctsTree::default();
+    let (game_move, score) = tree.play_move(&state, Duration::from_millis(0)).unwrap();
+    assert!(state.try_move(game_move).is_ok());
+    assert_eq!(score, 0);
+}
+
+#[test]
+fn mcts_trees_stay_within_their_nodes() {
+    let state = utils::generate_test_states()[0];
+    let mut tree = MctsTree::default().with_max_nodes(100);
+    tree.play_move(&state, SEARCH_DURATION).unwrap();
+    assert_eq!(tree.node_count(), 100);
+}
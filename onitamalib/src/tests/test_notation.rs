@@ -0,0 +1,104 @@
+use crate::notation::{parse_square, square_name};
+use crate::{Board, Card, GameState, Move, Point};
+
+use super::utils;
+
+const START: &str = "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r";
+
+#[test]
+fn squares_are_named_from_red_side() {
+    assert_eq!(square_name(Point { x: 2, y: 4 }), "c1");
+    assert_eq!(square_name(Point { x: 0, y: 0 }), "a5");
+    assert_eq!(parse_square("e1"), Ok(Point { x: 4, y: 4 }));
+    assert!(parse_square("f1").is_err());
+    assert!(parse_square("a6").is_err());
+    assert!(parse_square("a").is_err());
+}
+
+#[test]
+fn starting_position_parses() {
+    let board: Board = START.parse().unwrap();
+    let cards = vec![Card::Frog, Card::Eel, Card::Tiger, Card::Crab, Card::Ox];
+    assert_eq!(board, Board::new_from_cards(cards));
+    assert_eq!(board.to_string(), START);
+    let tiger: Move = "Tiger c1-c3".parse().unwrap();
+    assert_eq!(
+        tiger,
+        Move::Move {
+            card: Card::Tiger,
+            src: Point { x: 2, y: 4 },
+            dst: Point { x: 2, y: 2 },
+        }
+    );
+    assert!(board.try_move(tiger).is_ok());
+}
+
+#[test]
+fn board_notation_round_trips() {
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let notation = board.to_string();
+        let parsed: Board = notation.parse().unwrap();
+        assert_eq!(parsed, board, "{}", notation);
+    }
+}
+
+#[test]
+fn move_notation_round_trips() {
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let mut moves = board.legal_moves();
+        moves.extend(
+            board
+                .player_hand()
+                .iter()
+                .map(|card| Move::Discard { card: *card }),
+        );
+        for game_move in moves {
+            let notation = game_move.to_string();
+            assert_eq!(notation.parse::<Move>(), Ok(game_move), "{}", notation);
+        }
+    }
+    assert_eq!(
+        "discard seasnake".parse::<Move>(),
+        Ok(Move::Discard {
+            card: Card::SeaSnake
+        })
+    );
+}
+
+#[test]
+fn invalid_notation_is_rejected() {
+    let invalid_boards = [
+        "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox",
+        "ppkpp/5/5/5 Tiger,Crab Frog,Eel Ox r",
+        "ppkpp/5/5/5/PPKP Tiger,Crab Frog,Eel Ox r",
+        "ppkpp/6/5/5/PPKPP Tiger,Crab Frog,Eel Ox r",
+        "pppppp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r",
+        "ppppp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r",
+        "ppkpp/5/5/5/PPKPP Tiger,Tiger Frog,Eel Ox r",
+        "ppkpp/5/5/5/PPKPP Tiger Frog,Eel Ox r",
+        "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Unicorn r",
+        "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox x",
+        "ppkpp/5/5/5/PPKPQ Tiger,Crab Frog,Eel Ox r",
+    ];
+    for notation in invalid_boards {
+        assert!(notation.parse::<Board>().is_err(), "{}", notation);
+    }
+    let invalid_moves = [
+        "Tiger",
+        "Tiger c1c3",
+        "Tiger c1-c6",
+        "Unicorn c1-c3",
+        "discard",
+    ];
+    for notation in invalid_moves {
+        assert!(notation.parse::<Move>().is_err(), "{}", notation);
+    }
+}
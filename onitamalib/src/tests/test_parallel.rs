@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use std::thread;
+
+use instant::Duration;
+
+use super::utils;
+use crate::mcts::MctsTree;
+use crate::parallel;
+use crate::transposition::{Bound, SharedTranspositionTable, Table, TableEntry};
+use crate::{GameState, Move};
+
+const SEARCH_DURATION: Duration = Duration::from_millis(50);
+const THREADS: usize = 3;
+
+fn is_legal(state: &GameState, game_move: Move) -> bool {
+    match state {
+        GameState::Playing { board } => board.legal_moves().contains(&game_move),
+        GameState::Finished { .. } => false,
+    }
+}
+
+#[test]
+fn shared_table_is_visible_across_threads() {
+    let table = Arc::new(SharedTranspositionTable::new(8));
+    let handles: Vec<_> = (0..THREADS as u64)
+        .map(|idx| {
+            let mut table = Arc::clone(&table);
+            thread::spawn(move || {
+                table.store(TableEntry {
+                    hash: idx,
+                    depth: 1,
+                    score: idx as i64,
+                    bound: Bound::Exact,
+                    best_move: None,
+                })
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    for idx in 0..THREADS as u64 {
+        assert_eq!(table.probe(idx).unwrap().score, idx as i64);
+    }
+}
+
+#[test]
+fn parallel_agents_play_legal_moves() {
+    for state in utils::generate_test_states().into_iter().take(5) {
+        let weights = Default::default();
+        let (game_move, _) =
+            parallel::alphabeta_threads(&state, SEARCH_DURATION, &weights, THREADS).unwrap();
+        assert!(is_legal(&state, game_move));
+        let (game_move, _) =
+            parallel::pure_montecarlo_threads(&state, SEARCH_DURATION, THREADS).unwrap();
+        assert!(is_legal(&state, game_move));
+        let mut trees: Vec<MctsTree> = (0..THREADS).map(|_| MctsTree::default()).collect();
+        let (game_move, _) = parallel::mcts_threads(&state, SEARCH_DURATION, &mut trees).unwrap();
+        assert!(is_legal(&state, game_move));
+    }
+}
+
+#[test]
+fn parallel_playouts_score_every_move() {
+    let state = utils::generate_test_states()[0];
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    };
+    let moves = board.legal_moves();
+    let scored = parallel::montecarlo_threads(&board, moves.clone(), SEARCH_DURATION, THREADS);
+    let scored_moves: Vec<Move> = scored.iter().map(|(game_move, _)| *game_move).collect();
+    assert_eq!(scored_moves, moves);
+}
@@ -0,0 +1,89 @@
+use crate::perft::{divide, perft};
+use crate::{Board, Move};
+
+// Positions with leaf counts for depths 1 to 4, checked against a separate
+// move generator working from card offsets on a grid
+const POSITIONS: &[(&str, [u64; 4])] = &[
+    (
+        "ppkpp/5/5/5/PPKPP Tiger,Crab Frog,Eel Ox r",
+        [10, 80, 1144, 12988],
+    ),
+    (
+        "ppkpp/5/5/5/PPKPP Dragon,Rabbit Goose,Rooster Monkey b",
+        [8, 80, 1220, 14579],
+    ),
+    (
+        "ppkpp/5/5/5/PPKPP Fox,Panda Kirin,Phoenix Mouse r",
+        [13, 208, 2640, 45054],
+    ),
+    (
+        "ppkpp/5/5/5/PPKPP Goat,Sheep Nessie,Butterfly Moth r",
+        [8, 72, 648, 6156],
+    ),
+    (
+        "1pk2/2P2/1p3/3P1/2K2 Boar,Cobra Crane,Mantis Horse b",
+        [12, 174, 1820, 22330],
+    ),
+    // Red's king has no moves, so must discard
+    (
+        "2k2/5/5/5/K4 Frog,Dog Tiger,Dragon Rabbit r",
+        [2, 6, 12, 56],
+    ),
+    // Red's king can step into the temple
+    ("k4/2K2/5/5/4p Crab,Ox Tiger,Eel Frog r", [6, 16, 76, 383]),
+];
+
+#[test]
+fn perft_counts_match() {
+    for (position, counts) in POSITIONS {
+        let board: Board = position.parse().unwrap();
+        for (depth, expected) in counts.iter().enumerate() {
+            let depth = depth as u32 + 1;
+            assert_eq!(
+                perft(&board, depth),
+                *expected,
+                "{} depth {}",
+                position,
+                depth
+            );
+        }
+    }
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    for (position, counts) in POSITIONS {
+        let board: Board = position.parse().unwrap();
+        let total: u64 = divide(&board, 3).iter().map(|(_, count)| count).sum();
+        assert_eq!(total, counts[2], "{}", position);
+    }
+}
+
+#[test]
+fn perft_covers_discards_and_temple_moves() {
+    let board: Board = "2k2/5/5/5/K4 Frog,Dog Tiger,Dragon Rabbit r"
+        .parse()
+        .unwrap();
+    let moves: Vec<Move> = divide(&board, 1)
+        .into_iter()
+        .map(|(game_move, _)| game_move)
+        .collect();
+    assert_eq!(
+        moves,
+        vec![
+            "discard Frog".parse().unwrap(),
+            "discard Dog".parse().unwrap()
+        ]
+    );
+    // The winning move is a leaf, with nothing counted below it
+    let board: Board = "k4/2K2/5/5/4p Crab,Ox Tiger,Eel Frog r".parse().unwrap();
+    let temple: Move = "Crab c4-c5".parse().unwrap();
+    let count = |depth| {
+        divide(&board, depth)
+            .into_iter()
+            .find(|(game_move, _)| *game_move == temple)
+            .map(|(_, count)| count)
+    };
+    assert_eq!(count(1), Some(1));
+    assert_eq!(count(2), Some(0));
+}
@@ -0,0 +1,80 @@
+use instant::Duration;
+
+use super::utils;
+use crate::montecarlo;
+use crate::{AgentSession, AiAgent, GameState, Move, SearchInfo};
+
+// Long enough for the playout agents to report more than once
+const SEARCH_DURATION: Duration = Duration::from_millis(600);
+
+fn is_legal(state: &GameState, game_move: Move) -> bool {
+    match state {
+        GameState::Playing { board } => board.legal_moves().contains(&game_move),
+        GameState::Finished { .. } => false,
+    }
+}
+
+#[test]
+fn searching_agents_report_progress() {
+    let state = utils::generate_test_states()[0];
+    for agent in [
+        AiAgent::PureMonteCarlo,
+        AiAgent::Minimax,
+        AiAgent::Alphabeta,
+        AiAgent::MonteCarloTreeSearch,
+    ] {
+        let mut reports: Vec<SearchInfo> = vec![];
+        let (game_move, _) = agent
+            .play_move_observed(&state, SEARCH_DURATION, &mut |info: &SearchInfo| {
+                reports.push(info.clone())
+            })
+            .unwrap();
+        assert!(reports.len() > 1, "{:?} reported {:?}", agent, reports);
+        for info in reports.iter() {
+            assert!(is_legal(&state, info.best_move().unwrap()));
+            assert!(info.pv.len() <= info.depth.max(1) as usize);
+        }
+        // Reports come in the order the search made them
+        assert!(reports.windows(2).all(|pair| pair[0].time <= pair[1].time));
+        if let AiAgent::Minimax | AiAgent::Alphabeta = agent {
+            assert_eq!(reports.last().unwrap().best_move(), Some(game_move));
+        }
+    }
+}
+
+#[test]
+fn sessions_report_tree_searches() {
+    let state = utils::generate_test_states()[0];
+    let mut session = AgentSession::new(AiAgent::MonteCarloTreeSearch);
+    let mut reports: Vec<SearchInfo> = vec![];
+    session.play_move_observed(&state, SEARCH_DURATION, &mut |info: &SearchInfo| {
+        reports.push(info.clone())
+    });
+    assert!(!reports.is_empty());
+    // Visits only grow as the tree is searched
+    assert!(reports
+        .windows(2)
+        .all(|pair| pair[0].nodes <= pair[1].nodes));
+}
+
+#[test]
+fn playouts_report_their_best_move() {
+    let board = match utils::generate_test_states()[0] {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    };
+    let moves = board.legal_moves();
+    let mut reports: Vec<SearchInfo> = vec![];
+    let scored = montecarlo::montecarlo_observed(
+        &board,
+        moves.clone(),
+        SEARCH_DURATION,
+        &mut |info: &SearchInfo| reports.push(info.clone()),
+    );
+    assert!(!reports.is_empty());
+    for info in reports.iter() {
+        assert_eq!(info.depth, 1);
+        assert!(moves.contains(&info.best_move().unwrap()));
+    }
+    assert_eq!(scored.len(), moves.len());
+}
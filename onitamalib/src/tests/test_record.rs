@@ -0,0 +1,118 @@
+use rand::prelude::*;
+
+use crate::record::{GameRecord, GameResult, Participant};
+use crate::{AiAgent, GameEnding, GameState, Player};
+
+fn random_game(seed: u64) -> GameRecord {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut state = GameState::new_rng(&mut rng);
+    let mut record = match state {
+        GameState::Playing { board } => GameRecord::new(board),
+        GameState::Finished { .. } => panic!("Game finished before it started"),
+    };
+    while let GameState::Playing { board } = state {
+        let game_move = board.random_legal_move(&mut rng);
+        state = state.try_move(game_move).expect("Generated illegal move");
+        record.push(game_move, &state);
+    }
+    record
+}
+
+#[test]
+fn record_round_trips() {
+    for seed in 0..10 {
+        let mut record = random_game(seed);
+        record.red = Participant {
+            name: "Player".to_string(),
+            agent: None,
+        };
+        record.blue = Participant {
+            name: "Alphabeta".to_string(),
+            agent: Some(AiAgent::Alphabeta),
+        };
+        assert_ne!(record.result, GameResult::Unfinished);
+        let text = record.to_string();
+        let parsed: GameRecord = text.parse().unwrap();
+        assert_eq!(parsed, record, "{}", text);
+        assert_eq!(parsed.replay().unwrap(), record.replay().unwrap());
+    }
+}
+
+#[test]
+fn unfinished_record_round_trips() {
+    let mut record = random_game(0);
+    record.moves.truncate(3);
+    record.result = GameResult::Unfinished;
+    let text = record.to_string();
+    assert!(text.trim_end().ends_with('*'));
+    let parsed: GameRecord = text.parse().unwrap();
+    assert_eq!(parsed, record);
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    let record = random_game(1);
+    let text = record.to_string();
+    // Playing the first move twice is never legal, the card has left the player's hand
+    let first = record.moves[0].game_move.to_string();
+    let doubled = text.replacen(&first, &format!("{} {{0}} {}", first, first), 1);
+    assert!(doubled.parse::<GameRecord>().is_err());
+}
+
+#[test]
+fn mismatched_results_are_rejected() {
+    let mut record = random_game(2);
+    record.result = match record.result {
+        GameResult::Won(Player::Red) => GameResult::Won(Player::Blue),
+        _ => GameResult::Won(Player::Red),
+    };
+    assert!(record.replay().is_err());
+    assert!(record.to_string().parse::<GameRecord>().is_err());
+}
+
+#[test]
+fn tag_values_are_escaped() {
+    let mut record = random_game(3);
+    record.red.name = "A \"quoted\" \\ name".to_string();
+    let parsed: GameRecord = record.to_string().parse().unwrap();
+    assert_eq!(parsed.red.name, record.red.name);
+}
+
+#[test]
+fn resigned_record_round_trips() {
+    let mut record = random_game(4);
+    record.moves.truncate(3);
+    record.declare(GameResult::Won(Player::Blue), GameEnding::Resignation);
+    let text = record.to_string();
+    let parsed: GameRecord = text.parse().unwrap();
+    assert_eq!(parsed, record, "{}", text);
+    match parsed.replay().unwrap() {
+        GameState::Finished { winner, ending, .. } => {
+            assert_eq!(winner, Some(Player::Blue));
+            assert_eq!(ending, Some(GameEnding::Resignation));
+        }
+        GameState::Playing { .. } => panic!("Resigned game still playing"),
+    };
+    // Resigning can't be a draw, and a declared ending can't override the moves
+    record.result = GameResult::Drawn;
+    assert!(record.replay().is_err());
+    let mut finished = random_game(5);
+    finished.declare(GameResult::Drawn, GameEnding::DrawAgreed);
+    assert!(finished.replay().is_err());
+}
+
+#[test]
+fn timed_out_record_round_trips() {
+    let mut record = random_game(6);
+    record.moves.truncate(4);
+    record.declare(GameResult::Won(Player::Red), GameEnding::Timeout);
+    let parsed: GameRecord = record.to_string().parse().unwrap();
+    assert_eq!(parsed, record);
+    match parsed.replay().unwrap() {
+        GameState::Finished { winner, ending, .. } => {
+            assert_eq!(winner, Some(Player::Red));
+            assert_eq!(ending, Some(GameEnding::Timeout));
+        }
+        GameState::Playing { .. } => panic!("Timed out game still playing"),
+    };
+}
@@ -0,0 +1,56 @@
+use crate::{Card, CardSet, GameState, RoomSettings};
+
+fn dealt_cards(state: &GameState) -> Vec<Card> {
+    let board = match state {
+        GameState::Playing { board } => board,
+        GameState::Finished { .. } => panic!("New game is already finished"),
+    };
+    let mut cards = vec![board.spare_card];
+    cards.extend_from_slice(&board.red_hand);
+    cards.extend_from_slice(&board.blue_hand);
+    cards
+}
+
+#[test]
+fn settings_deal_only_chosen_cards() {
+    let base = RoomSettings {
+        card_sets: vec![CardSet::Base],
+        ..RoomSettings::default()
+    };
+    for _ in 0..20 {
+        for card in dealt_cards(&base.new_game()) {
+            assert!(CardSet::Base.cards().contains(&card), "{}", card);
+        }
+    }
+    let chosen = vec![Card::Tiger, Card::Crab, Card::Frog, Card::Eel, Card::Ox];
+    let explicit = RoomSettings {
+        cards: chosen.clone(),
+        ..RoomSettings::default()
+    };
+    for _ in 0..20 {
+        let mut cards = dealt_cards(&explicit.new_game());
+        cards.sort_by_key(|card| chosen.iter().position(|chosen| chosen == card));
+        assert_eq!(cards, chosen);
+    }
+}
+
+#[test]
+fn settings_normalize_and_validate() {
+    let settings = RoomSettings {
+        card_sets: vec![CardSet::SenseiPath, CardSet::Base, CardSet::Base],
+        ..RoomSettings::default()
+    };
+    let expected = vec![CardSet::Base, CardSet::SenseiPath];
+    assert_eq!(settings.normalized().card_sets, expected);
+    let every_set = RoomSettings {
+        card_sets: vec![CardSet::PromotionalPack, CardSet::SenseiPath, CardSet::Base],
+        ..RoomSettings::default()
+    };
+    assert_eq!(every_set.normalized(), RoomSettings::default());
+    let too_few = RoomSettings {
+        cards: vec![Card::Tiger, Card::Crab, Card::Frog, Card::Eel, Card::Eel],
+        ..RoomSettings::default()
+    };
+    assert!(too_few.validate().is_err());
+    assert!(settings.validate().is_ok());
+}
@@ -0,0 +1,40 @@
+use instant::Duration;
+
+use super::utils::CARDS;
+use crate::solver::{Outcome, Solver};
+use crate::tablebase::{Material, Tablebase};
+use crate::Board;
+
+#[test]
+fn solver_agrees_with_tablebase() {
+    let table = Tablebase::generate(CARDS, Material { red: 0, blue: 0 }).unwrap();
+    let mut solver = Solver::new();
+    for (board, value) in table.positions().step_by(97) {
+        let expected = match value.winner(board.turn) {
+            Some(winner) => Outcome::Win(winner),
+            None => Outcome::Draw,
+        };
+        assert_eq!(solver.outcome(&board, &[]), Ok(expected), "{:?}", board);
+    }
+}
+
+#[test]
+fn solver_resumes_from_checkpoint() {
+    let path = std::env::temp_dir().join(format!("onitama-solver-{}.ckpt", std::process::id()));
+    let board: Board = "2k2/5/5/1P3/2K2 Tiger,Crab Frog,Eel Ox r".parse().unwrap();
+    let mut solver = Solver::new()
+        .checkpoint_to(path.clone(), Duration::from_secs(60))
+        .unwrap();
+    let solution = solver.solve(&board, |_, _| {}).unwrap();
+    assert!(solver.expanded > 0);
+    assert!(!solution.best_moves().is_empty());
+    let mut resumed = Solver::new()
+        .checkpoint_to(path.clone(), Duration::from_secs(60))
+        .unwrap();
+    let resolved = resumed.solve(&board, |_, _| {}).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    // Draws reached through repetitions aren't kept, so only those are searched again
+    assert!(resumed.expanded < solver.expanded);
+    assert_eq!(resolved.outcome, solution.outcome);
+    assert_eq!(resolved.best_moves(), solution.best_moves());
+}
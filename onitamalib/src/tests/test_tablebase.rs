@@ -0,0 +1,120 @@
+use instant::Duration;
+
+use super::utils::CARDS;
+use crate::tablebase::{self, Material, TableValue, Tablebase};
+use crate::{AiAgent, Board, Card, GameState};
+
+fn kings_and_pawn() -> Tablebase {
+    Tablebase::generate(CARDS, Material { red: 1, blue: 0 }).unwrap()
+}
+
+#[test]
+fn best_moves_agree_with_table() {
+    let table = kings_and_pawn();
+    let stats = table.stats();
+    assert!(stats.wins > 0 && stats.losses > 0);
+    for (board, value) in table.positions() {
+        let best = table.best_move(&board).map(|(_, value)| value);
+        assert_eq!(best, Some(value), "{:?}", board);
+    }
+}
+
+#[test]
+fn tables_survive_saving() {
+    let table = Tablebase::generate(CARDS, Material { red: 0, blue: 0 }).unwrap();
+    let mut data = vec![];
+    table.write_to(&mut data).unwrap();
+    let loaded = Tablebase::read_from(&mut data.as_slice()).unwrap();
+    assert_eq!(loaded.cards(), table.cards());
+    assert_eq!(loaded.stats(), table.stats());
+    // The header's cards are indices, which must name five different cards
+    let mut unknown = data.clone();
+    unknown[5] = u8::MAX;
+    assert!(Tablebase::read_from(&mut unknown.as_slice()).is_err());
+    data[6] = data[5];
+    assert!(Tablebase::read_from(&mut data.as_slice()).is_err());
+}
+
+#[test]
+fn table_sizes_are_checked() {
+    // Three pawns against two has more positions than a table can number
+    assert!(Tablebase::generate(CARDS, Material { red: 3, blue: 2 }).is_err());
+    let table = Tablebase::generate(CARDS, Material { red: 0, blue: 0 }).unwrap();
+    let mut data = vec![];
+    table.write_to(&mut data).unwrap();
+    // A header claiming four pawns each, the file is rejected rather than allocated for
+    let mut oversized = data.clone();
+    oversized[10..12].copy_from_slice(&[4, 4]);
+    assert!(Tablebase::read_from(&mut oversized.as_slice()).is_err());
+    // A header claiming more material than the entries that follow
+    let mut short = data.clone();
+    short[10] = 1;
+    assert!(Tablebase::read_from(&mut short.as_slice()).is_err());
+    let mut long = data;
+    long.push(0);
+    assert!(Tablebase::read_from(&mut long.as_slice()).is_err());
+}
+
+#[test]
+fn longest_results_fit_the_limit() {
+    let table = kings_and_pawn();
+    let longest = table
+        .positions()
+        .map(|(_, value)| match value {
+            TableValue::Win(plies) | TableValue::Loss(plies) => plies as usize,
+            TableValue::Draw => 0,
+        })
+        .max()
+        .unwrap();
+    let material = Material { red: 1, blue: 0 };
+    let within = Tablebase::generate_within(CARDS, material, longest).unwrap();
+    assert_eq!(within.stats(), table.stats());
+    // One ply short, the longest results must fail rather than be left as draws
+    assert!(Tablebase::generate_within(CARDS, material, longest - 1).is_err());
+}
+
+#[test]
+fn king_next_to_temple_wins() {
+    let table = Tablebase::generate(CARDS, Material { red: 0, blue: 0 }).unwrap();
+    // Tiger moves the king two squares forward onto the temple
+    let board: Board = "k4/5/2K2/5/5 Tiger,Crab Frog,Eel Ox r".parse().unwrap();
+    assert_eq!(table.probe(&board), Some(TableValue::Win(1)));
+    let (game_move, _) = table.best_move(&board).unwrap();
+    let finished = matches!(board.try_move(game_move), Ok(GameState::Finished { .. }));
+    assert!(finished);
+}
+
+#[test]
+fn agents_play_installed_tables() {
+    // A deal no other test plays, so they are left alone while it's installed
+    let cards = [
+        Card::Boar,
+        Card::Cobra,
+        Card::Elephant,
+        Card::Goose,
+        Card::Horse,
+    ];
+    let table = Tablebase::generate(cards, Material { red: 0, blue: 0 }).unwrap();
+    let (board, value) = table
+        .positions()
+        .find(|(_, value)| *value == TableValue::Win(5))
+        .unwrap();
+    tablebase::install(table);
+    let state = GameState::Playing { board };
+    for agent in [
+        AiAgent::Alphabeta,
+        AiAgent::PureMonteCarlo,
+        AiAgent::HybridMonteCarlo,
+        AiAgent::MonteCarloTreeSearch,
+    ] {
+        let (game_move, _) = agent.play_move(&state, Duration::from_millis(50)).unwrap();
+        let next = match board.try_move(game_move).unwrap() {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Won sooner than the table"),
+        };
+        let after = tablebase::installed().probe(&next);
+        assert_eq!(after, Some(TableValue::Loss(4)), "{:?} {:?}", agent, value);
+    }
+    tablebase::uninstall(cards);
+    assert_eq!(tablebase::installed().probe(&board), None);
+}
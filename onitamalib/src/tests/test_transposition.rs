@@ -0,0 +1,46 @@
+use crate::transposition::{Bound, TableEntry, TranspositionTable};
+use crate::GameState;
+
+use super::utils;
+
+#[test]
+fn zobrist_hash_ignores_hand_order() {
+    for state in utils::generate_test_states() {
+        let board = match state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => panic!("Unexpected finished state"),
+        };
+        let mut swapped = board;
+        swapped.red_hand.reverse();
+        swapped.blue_hand.reverse();
+        assert_eq!(board.zobrist_hash(), swapped.zobrist_hash());
+        let mut passed = board;
+        passed.turn = board.turn.invert();
+        assert_ne!(board.zobrist_hash(), passed.zobrist_hash());
+        for game_move in board.legal_moves() {
+            if let GameState::Playing { board: next } = board.try_move(game_move).unwrap() {
+                assert_ne!(board.zobrist_hash(), next.zobrist_hash());
+            }
+        }
+    }
+}
+
+#[test]
+fn transposition_table_prefers_deeper_entries() {
+    let mut table = TranspositionTable::new(4);
+    let entry = |hash, depth| TableEntry {
+        hash,
+        depth,
+        score: depth as i64,
+        bound: Bound::Exact,
+        best_move: None,
+    };
+    table.store(entry(0x10, 5));
+    // Same slot, different position, shallower search
+    table.store(entry(0x20, 2));
+    assert_eq!(table.probe(0x10).map(|e| e.depth), Some(5));
+    assert!(table.probe(0x20).is_none());
+    table.store(entry(0x20, 6));
+    assert_eq!(table.probe(0x20).map(|e| e.depth), Some(6));
+    assert!(table.probe(0x10).is_none());
+}
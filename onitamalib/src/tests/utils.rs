@@ -1,7 +1,10 @@
 use enum_iterator::IntoEnumIterator;
 use rand::prelude::*;
 
-use crate::{Board, Card, GameState, Player, Point};
+use crate::{Board, Card, GameState};
+
+// A deal to build tablebases for, kings alone solve in moments
+pub const CARDS: [Card; 5] = [Card::Tiger, Card::Crab, Card::Frog, Card::Eel, Card::Ox];
 
 const SAMPLES: usize = 100;
 pub fn generate_test_states() -> Vec<GameState> {
@@ -31,18 +34,6 @@ impl Board {
     pub fn new_rng<R: Rng>(rng: &mut R) -> Board {
         let mut cards: Vec<Card> = Card::into_enum_iter().collect();
         cards.shuffle(rng);
-        let mut cards = cards.into_iter();
-        let pawn_xs: [i8; 4] = [0, 1, 3, 4];
-        Board {
-            blue_king: Point { x: 2, y: 0 },
-            blue_pawns: pawn_xs.map(|x| Some(Point { x, y: 0 })),
-
-            blue_hand: [cards.next().unwrap(), cards.next().unwrap()],
-            red_king: Point { x: 2, y: 4 },
-            red_pawns: pawn_xs.map(|x| Some(Point { x, y: 4 })),
-            red_hand: [cards.next().unwrap(), cards.next().unwrap()],
-            spare_card: cards.next().unwrap(),
-            turn: Player::Red,
-        }
+        Board::new_from_cards(cards)
     }
 }
@@ -0,0 +1,93 @@
+// Zobrist keys are generated at compile time from a fixed seed, so hashes are stable across builds
+use crate::bitboard::{player_index, squares, CARD_COUNT};
+use crate::models::{Board, Player};
+
+const SEED: u64 = 0x6f6e_6974_616d_6121;
+
+struct ZobristKeys {
+    pawns: [[u64; 25]; 2],
+    kings: [[u64; 25]; 2],
+    hands: [[u64; CARD_COUNT]; 2],
+    spare: [u64; CARD_COUNT],
+    blue_to_move: u64,
+}
+
+static KEYS: ZobristKeys = build_keys();
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn build_keys() -> ZobristKeys {
+    let mut keys = ZobristKeys {
+        pawns: [[0; 25]; 2],
+        kings: [[0; 25]; 2],
+        hands: [[0; CARD_COUNT]; 2],
+        spare: [0; CARD_COUNT],
+        blue_to_move: 0,
+    };
+    let mut state = SEED;
+    let mut player = 0;
+    while player < 2 {
+        let mut square = 0;
+        while square < 25 {
+            let (next, key) = splitmix64(state);
+            keys.pawns[player][square] = key;
+            let (next, key) = splitmix64(next);
+            keys.kings[player][square] = key;
+            state = next;
+            square += 1;
+        }
+        let mut card = 0;
+        while card < CARD_COUNT {
+            let (next, key) = splitmix64(state);
+            keys.hands[player][card] = key;
+            state = next;
+            card += 1;
+        }
+        player += 1;
+    }
+    let mut card = 0;
+    while card < CARD_COUNT {
+        let (next, key) = splitmix64(state);
+        keys.spare[card] = key;
+        state = next;
+        card += 1;
+    }
+    let (_, key) = splitmix64(state);
+    keys.blue_to_move = key;
+    keys
+}
+
+impl Board {
+    /// Hash of the pieces, both hands, the spare card and the side to move.
+    /// The order of cards within a hand doesn't affect the hash.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for player in [Player::Red, Player::Blue] {
+            let idx = player_index(player);
+            for square in squares(self.pawns(player)) {
+                hash ^= KEYS.pawns[idx][square as usize];
+            }
+            for square in squares(self.pieces(player) & self.king(player)) {
+                hash ^= KEYS.kings[idx][square as usize];
+            }
+            let hand = match player {
+                Player::Red => &self.red_hand,
+                Player::Blue => &self.blue_hand,
+            };
+            for card in hand {
+                hash ^= KEYS.hands[idx][card.index() as usize];
+            }
+        }
+        hash ^= KEYS.spare[self.spare_card.index() as usize];
+        if self.turn == Player::Blue {
+            hash ^= KEYS.blue_to_move;
+        }
+        hash
+    }
+}
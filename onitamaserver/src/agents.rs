@@ -1,23 +1,107 @@
+use std::env;
+use std::fs;
+use std::sync::Arc;
 use std::time::Duration;
 
 use actix::{Actor, Addr, AsyncContext, Handler, StreamHandler, SyncArbiter, SyncContext};
 use actix_web_actors::ws;
 use serde_cbor::ser;
 
-use onitamalib::{AiAgent, GameMessage, GameState, Player};
+use onitamalib::book::OpeningBook;
+use onitamalib::history::PositionHistory;
+use onitamalib::tablebase::{self, Tablebase};
+use onitamalib::{AgentSession, AiAgent, GameMessage, GameState, Player, SearchInfo};
 
 use crate::messages::{AgentRequest, AgentResponse};
 
 pub struct Agent {
     state: GameState,
+    history: PositionHistory,
     id: String,
-    ai: AiAgent,
+    ai: AgentSession,
+    threads: usize,
 }
 
 impl Agent {
-    pub fn new(id: String, ai: AiAgent) -> Agent {
+    pub fn new(id: String, ai: AiAgent, book: Option<Arc<OpeningBook>>) -> Agent {
         let state = GameState::new();
-        Agent { id, state, ai }
+        let ai = match book {
+            Some(book) => AgentSession::new(ai).with_book(book),
+            None => AgentSession::new(ai),
+        };
+        let threads = search_threads();
+        Agent {
+            id,
+            state,
+            history: PositionHistory::new(&state, None),
+            ai,
+            threads,
+        }
+    }
+}
+
+// Threads each agent searches with, set by AGENT_THREADS
+fn search_threads() -> usize {
+    match env::var("AGENT_THREADS").map(|threads| threads.parse()) {
+        Ok(Ok(threads)) if threads > 0 => threads,
+        _ => 1,
+    }
+}
+
+// Installs every tablebase in the TABLEBASE_PATH directory for the agents to consult
+pub fn load_tablebases() {
+    let dir = match env::var("TABLEBASE_PATH") {
+        Ok(dir) => dir,
+        Err(_) => {
+            return;
+        }
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("Failed to read tablebases from {}: {}", dir, err);
+            return;
+        }
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        match Tablebase::load(&path) {
+            Ok(table) => {
+                info!(
+                    "Loaded tablebase {} for {:?}",
+                    path.display(),
+                    table.cards()
+                );
+                tablebase::install(table);
+            }
+            Err(err) => {
+                warn!("Skipping tablebase {}: {}", path.display(), err);
+            }
+        };
+    }
+}
+
+// Loads the opening book at OPENING_BOOK_PATH, for agents asked to play from it
+pub fn load_opening_book() -> Option<Arc<OpeningBook>> {
+    let path = match env::var("OPENING_BOOK_PATH") {
+        Ok(path) => path,
+        Err(_) => {
+            return None;
+        }
+    };
+    match OpeningBook::load(path.as_ref()) {
+        Ok(opening_book) => {
+            info!(
+                "Loaded opening book {} with {} positions",
+                path,
+                opening_book.len()
+            );
+            Some(Arc::new(opening_book))
+        }
+        Err(err) => {
+            error!("Failed to load opening book {}: {}", path, err);
+            None
+        }
     }
 }
 
@@ -33,25 +117,34 @@ const TIMEOUT: Duration = Duration::from_millis(500);
 impl Agent {
     fn play_move(&mut self, state: GameState) -> Result<GameMessage, AgentException> {
         // The state is guaranteed to be Playing
-        let (game_move, expected_score) = match self.ai.play_move(&state, TIMEOUT) {
-            None => {
-                error!("No moves available");
-                return Err(AgentException::AgentError);
-            }
-            Some((best_move, best_score)) => (best_move, best_score),
-        };
+        let id = &self.id;
+        let mut report = |info: &SearchInfo| debug!("Agent {}: {}", id, info);
+        let (game_move, expected_score) =
+            match self
+                .ai
+                .play_move_threads_observed(&state, TIMEOUT, self.threads, &mut report)
+            {
+                None => {
+                    error!("No moves available");
+                    return Err(AgentException::AgentError);
+                }
+                Some((best_move, best_score)) => (best_move, best_score),
+            };
         info!(
             "Expected score: {}, move = {:?}",
             expected_score, &game_move
         );
         self.state = match state.try_move(game_move) {
-            Ok(state) => state,
+            Ok(state) => self.history.record(state),
             Err(err) => {
                 error!("Agent attempted invalid move: {}", err);
                 return Err(AgentException::AgentError);
             }
         };
-        let msg = GameMessage::Move { game_move };
+        let msg = GameMessage::Move {
+            game_move,
+            clock: None,
+        };
         Ok(msg)
     }
     fn handle_game_message(&mut self, msg: GameMessage) -> Result<GameMessage, AgentException> {
@@ -59,11 +152,15 @@ impl Agent {
             (GameState::Finished { .. }, GameMessage::RequestRematch) => {
                 info!("Starting rematch");
                 self.state = GameState::new();
+                self.history = PositionHistory::new(&self.state, None);
                 Ok(GameMessage::Initialize {
                     state: self.state.clone(),
                     room_id: "ai".to_string(),
                     player: Player::Red,
                     waiting: false,
+                    clock: None,
+                    token: None,
+                    settings: None,
                 })
             }
             (_, GameMessage::Joined) => {
@@ -73,11 +170,14 @@ impl Agent {
                     room_id: "ai".to_string(),
                     player: Player::Red,
                     waiting: false,
+                    clock: None,
+                    token: None,
+                    settings: None,
                 })
             }
-            (state, GameMessage::Move { game_move }) => {
+            (state, GameMessage::Move { game_move, .. }) => {
                 let state = match state.try_move(game_move) {
-                    Ok(state) => state,
+                    Ok(state) => self.history.record(state),
                     Err(err) => {
                         warn!("Invalid Move: {}", err);
                         return Err(AgentException::IllegalMove);
@@ -113,12 +213,15 @@ impl Handler<AgentRequest> for Agent {
         match &self.state {
             GameState::Finished { winner, .. } => {
                 let won = match winner {
-                    Player::Red => "won",
-                    Player::Blue => "lost",
+                    Some(Player::Red) => "won",
+                    Some(Player::Blue) => "lost",
+                    None => "drew",
                 };
                 info!(
                     "Game finished, player {} against {:?}: {}",
-                    won, self.ai, self.id
+                    won,
+                    self.ai.agent(),
+                    self.id
                 );
             }
             _ => {}
@@ -133,8 +236,8 @@ pub struct AgentWs {
 }
 
 impl AgentWs {
-    pub fn new(id: String, ai: AiAgent) -> AgentWs {
-        let agent = SyncArbiter::start(1, move || Agent::new(id.clone(), ai));
+    pub fn new(id: String, ai: AiAgent, book: Option<Arc<OpeningBook>>) -> AgentWs {
+        let agent = SyncArbiter::start(1, move || Agent::new(id.clone(), ai, book.clone()));
         AgentWs { agent }
     }
 }
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::env;
+
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, StreamHandler};
+use actix_web_actors::ws;
+use sha1::{Digest, Sha1};
+
+use crate::messages::{JoinedBots, LeftBots, SocketBotMessage};
+use crate::rooms::OnitamaServer;
+
+// Bot accounts and their secrets, set by BOT_ACCOUNTS as "name:secret,name:secret"
+fn bot_accounts() -> HashMap<String, String> {
+    let accounts = env::var("BOT_ACCOUNTS").unwrap_or_default();
+    accounts
+        .split(',')
+        .filter_map(|account| account.split_once(':'))
+        .map(|(name, secret)| (name.trim().to_string(), secret.trim().to_string()))
+        .filter(|(name, secret)| !name.is_empty() && !secret.is_empty())
+        .collect()
+}
+
+// Compares digests of the two, which are always the same length, byte by byte whatever the
+// first difference, so timing gives away neither the secret nor its length
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let (a, b) = (Sha1::digest(a), Sha1::digest(b));
+    a.iter()
+        .zip(b.iter())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+pub fn bot_authorized(name: &str, secret: &str) -> bool {
+    match bot_accounts().get(name) {
+        Some(expected) => constant_time_eq(expected.as_bytes(), secret.as_bytes()),
+        None => false,
+    }
+}
+
+/// Socket
+///
+// A bot waiting for challenges, it plays each game over a room socket of its own
+pub struct BotWs {
+    server: Addr<OnitamaServer>,
+    name: String,
+    id: String,
+}
+
+impl BotWs {
+    pub fn new(server: Addr<OnitamaServer>, name: String, id: String) -> BotWs {
+        BotWs { server, name, id }
+    }
+}
+
+impl Actor for BotWs {
+    type Context = ws::WebsocketContext<Self>;
+    fn started(&mut self, ctx: &mut Self::Context) {
+        info!("Bot {} connected: {}", self.name, self.id);
+        let msg = JoinedBots {
+            name: self.name.clone(),
+            addr: ctx.address(),
+        };
+        self.server.do_send(msg);
+    }
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        info!("Bot {} disconnected: {}", self.name, self.id);
+        let msg = LeftBots {
+            name: self.name.clone(),
+            addr: ctx.address(),
+        };
+        self.server.do_send(msg);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for BotWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                ctx.pong(&msg);
+            }
+            // Kept alive the same way as room sockets
+            Ok(ws::Message::Text(text)) if text == "ping" => {
+                ctx.text("pong");
+            }
+            Ok(ws::Message::Close(reason)) => {
+                info!("Bot connection closed, reason: {:?}", reason);
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => {}
+        };
+    }
+}
+
+impl Handler<SocketBotMessage> for BotWs {
+    type Result = ();
+    fn handle(&mut self, msg: SocketBotMessage, ctx: &mut Self::Context) {
+        let SocketBotMessage(msg) = msg;
+        let data = serde_json::to_string(&msg).expect("Failed to serialize message");
+        ctx.text(data);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, StreamHandler};
+use actix_web_actors::ws;
+use uuid::Uuid;
+
+use onitamalib::{LobbyMessage, PublicRoom, RoomSettings};
+
+use crate::messages::{AddressedLobbyMessage, JoinedLobby, LeftLobby, SocketLobbyMessage};
+use crate::rooms::OnitamaServer;
+
+/// Socket
+///
+pub struct LobbyWs {
+    server: Addr<OnitamaServer>,
+    id: String,
+}
+
+impl LobbyWs {
+    pub fn new(server: Addr<OnitamaServer>, id: String) -> LobbyWs {
+        LobbyWs { server, id }
+    }
+}
+
+impl Actor for LobbyWs {
+    type Context = ws::WebsocketContext<Self>;
+    fn started(&mut self, ctx: &mut Self::Context) {
+        info!("Joined lobby: {}", self.id);
+        self.server.do_send(JoinedLobby(ctx.address()));
+    }
+    // Also reached when the connection drops without closing, so the lobby never keeps a dead socket
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        info!("Left lobby: {}", self.id);
+        self.server.do_send(LeftLobby(ctx.address()));
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LobbyWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        let text = match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                ctx.pong(&msg);
+                return;
+            }
+            Ok(ws::Message::Text(text)) => text,
+            Ok(ws::Message::Close(reason)) => {
+                info!("Lobby connection closed, reason: {:?}", reason);
+                ctx.close(reason);
+                ctx.stop();
+                return;
+            }
+            _ => {
+                return;
+            }
+        };
+        // Kept alive the same way as room sockets
+        if text == "ping" {
+            ctx.text("pong");
+            return;
+        }
+        let msg: LobbyMessage = match serde_json::from_str(&text) {
+            Ok(msg) => msg,
+            Err(err) => {
+                warn!("Error deserializing lobby message: {:?}", err);
+                let message = format!("Invalid message: {}", err);
+                let msg = LobbyMessage::Error { message };
+                ctx.text(serde_json::to_string(&msg).expect("Failed to serialize message"));
+                return;
+            }
+        };
+        let msg = AddressedLobbyMessage {
+            sender: ctx.address(),
+            msg,
+        };
+        self.server.do_send(msg);
+    }
+}
+
+impl Handler<SocketLobbyMessage> for LobbyWs {
+    type Result = ();
+    fn handle(&mut self, msg: SocketLobbyMessage, ctx: &mut Self::Context) {
+        let SocketLobbyMessage(msg) = msg;
+        let data = serde_json::to_string(&msg).expect("Failed to serialize message");
+        ctx.text(data);
+    }
+}
+
+/// Lobby
+///
+// Public rooms waiting for an opponent and players waiting to be matched, kept by the server
+#[derive(Default)]
+pub struct Lobby {
+    sockets: Vec<Addr<LobbyWs>>,
+    rooms: HashMap<Uuid, PublicRoom>,
+    seeking: Vec<(Addr<LobbyWs>, RoomSettings)>,
+}
+
+impl Lobby {
+    pub fn join(&mut self, addr: Addr<LobbyWs>) {
+        addr.do_send(SocketLobbyMessage(self.rooms_message()));
+        self.sockets.push(addr);
+    }
+    pub fn leave(&mut self, addr: &Addr<LobbyWs>) {
+        self.sockets.retain(|sock| sock != addr);
+        self.cancel_seek(addr);
+    }
+    // Lists or unlists a room, letting everyone in the lobby know if that changed anything
+    pub fn update_room(&mut self, key: Uuid, listing: Option<PublicRoom>) {
+        let changed = match listing {
+            Some(listing) => self.rooms.insert(key, listing.clone()) != Some(listing),
+            None => self.rooms.remove(&key).is_some(),
+        };
+        if changed {
+            let msg = self.rooms_message();
+            for sock in self.sockets.iter() {
+                sock.do_send(SocketLobbyMessage(msg.clone()));
+            }
+        }
+    }
+    // Takes the longest waiting player with the same settings out of the queue,
+    // or queues this player if nobody matches
+    pub fn seek(&mut self, addr: Addr<LobbyWs>, settings: RoomSettings) -> Option<Addr<LobbyWs>> {
+        self.cancel_seek(&addr);
+        let matched = self
+            .seeking
+            .iter()
+            .position(|(_, seeking)| seeking == &settings);
+        match matched {
+            Some(idx) => Some(self.seeking.remove(idx).0),
+            None => {
+                self.seeking.push((addr, settings));
+                None
+            }
+        }
+    }
+    pub fn cancel_seek(&mut self, addr: &Addr<LobbyWs>) {
+        self.seeking.retain(|(sock, _)| sock != addr);
+    }
+    fn rooms_message(&self) -> LobbyMessage {
+        let mut rooms: Vec<PublicRoom> = self.rooms.values().cloned().collect();
+        rooms.sort_by_key(|room| room.created);
+        LobbyMessage::Rooms { rooms }
+    }
+}
@@ -16,24 +16,39 @@ use actix_web::{web, App, HttpServer};
 use slog::{o, Drain};
 
 use crate::rooms::OnitamaServer;
-use crate::routes::{create_room, event_receive, join_room, ServerData};
+use crate::routes::{
+    bot_room, connect_bot, create_room, event_receive, join_lobby, join_room, list_bots,
+    watch_room, ServerData,
+};
+use crate::store::open_store;
 
 #[cfg(feature = "agent")]
 mod agents;
+mod bots;
+mod lobby;
 mod messages;
 mod rooms;
 mod routes;
+mod store;
+#[cfg(test)]
+mod tests;
 mod utils;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     pretty_env_logger::init();
-    let server_addr = OnitamaServer::new().start();
+    #[cfg(feature = "agent")]
+    agents::load_tablebases();
+    #[cfg(feature = "agent")]
+    let book = agents::load_opening_book();
+    let server_addr = OnitamaServer::new(open_store()).start();
     let drain = Mutex::new(slog_json::Json::default(std::io::stdout())).fuse();
     let logger = slog::Logger::root(drain, o!());
     let data = ServerData {
         server_addr,
         logger,
+        #[cfg(feature = "agent")]
+        book,
     };
     let data = web::Data::new(data);
     let mut built_path = path::Path::new("./build");
@@ -53,17 +68,28 @@ async fn main() -> std::io::Result<()> {
     HttpServer::new(move || {
         cfg_if::cfg_if! {
             if #[cfg(feature = "agent")] {
-                use crate::routes::ai_room;
+                use crate::routes::{ai_room, analyse_position};
                 let factory =
                     web::scope("/ws")
                         .route("/event", web::post().to(event_receive))
+                        .route("/analysis", web::post().to(analyse_position))
                         .route("/ai/{difficulty}", web::get().to(ai_room))
+                        .route("/lobby", web::get().to(join_lobby))
+                        .route("/bots", web::get().to(list_bots))
+                        .route("/bots/{name}", web::get().to(connect_bot))
+                        .route("/vs/{name}", web::get().to(bot_room))
+                        .route("/watch/{key}", web::get().to(watch_room))
                         .route("/{key}", web::get().to(join_room))
                         .route("/", web::get().to(create_room));
             } else {
                 let factory =
                     web::scope("/ws")
                         .route("/event", web::post().to(event_receive))
+                        .route("/lobby", web::get().to(join_lobby))
+                        .route("/bots", web::get().to(list_bots))
+                        .route("/bots/{name}", web::get().to(connect_bot))
+                        .route("/vs/{name}", web::get().to(bot_room))
+                        .route("/watch/{key}", web::get().to(watch_room))
                         .route("/{key}", web::get().to(join_room))
                         .route("/", web::get().to(create_room));
             }
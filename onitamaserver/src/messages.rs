@@ -1,8 +1,13 @@
 use actix::{Addr, Message};
 use uuid::Uuid;
 
-use onitamalib::{GameMessage, GameState, Player};
+use onitamalib::clock::Clock;
+use onitamalib::{
+    BotMessage, GameMessage, GameState, LobbyMessage, Player, PublicRoom, RoomSettings,
+};
 
+use crate::bots::BotWs;
+use crate::lobby::LobbyWs;
 use crate::rooms::{OnitamaRoom, RoomWs};
 
 #[derive(Message)]
@@ -10,11 +15,24 @@ use crate::rooms::{OnitamaRoom, RoomWs};
 pub struct JoinRoom {
     pub addr: Addr<RoomWs>,
     pub room_key: Uuid,
+    // Join as a read-only spectator even if a seat is free
+    pub spectate: bool,
+    // Session token from an earlier join, to get the same seat back
+    pub token: Option<Uuid>,
+    // Name of the bot account joining, already authenticated
+    pub bot: Option<String>,
 }
 
 #[derive(Message)]
 #[rtype(result = "()")]
-pub struct CreateRoom(pub Addr<RoomWs>);
+pub struct CreateRoom {
+    pub addr: Addr<RoomWs>,
+    pub settings: RoomSettings,
+    // Listed in the lobby while waiting for an opponent
+    pub public: bool,
+    // Name of a connected bot to challenge to the game
+    pub bot: Option<String>,
+}
 
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -29,9 +47,15 @@ pub enum JoinedRoom {
         player: Player,
         state: GameState,
         waiting: bool,
+        clock: Option<Clock>,
+        token: Uuid,
+        settings: RoomSettings,
     },
-    Error {
-        message: String,
+    Spectating {
+        addr: Addr<OnitamaRoom>,
+        room_key: Uuid,
+        state: GameState,
+        clock: Option<Clock>,
     },
 }
 
@@ -50,6 +74,70 @@ pub struct SocketGameMessage(pub GameMessage);
 #[rtype(result = "()")]
 pub struct CloseRoom;
 
+// Sent when the player to move may have run out of time, ignored if moves have been made since
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct FlagFall {
+    pub moves: usize,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct JoinedLobby(pub Addr<LobbyWs>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LeftLobby(pub Addr<LobbyWs>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct AddressedLobbyMessage {
+    pub sender: Addr<LobbyWs>,
+    pub msg: LobbyMessage,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SocketLobbyMessage(pub LobbyMessage);
+
+// Sent by public rooms when they start or stop waiting for an opponent
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RoomListing {
+    pub key: Uuid,
+    pub listing: Option<PublicRoom>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct JoinedBots {
+    pub name: String,
+    pub addr: Addr<BotWs>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct LeftBots {
+    pub name: String,
+    pub addr: Addr<BotWs>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SocketBotMessage(pub BotMessage);
+
+// Names of the bots currently connected
+#[derive(Message)]
+#[rtype(result = "Vec<String>")]
+pub struct ListBots;
+
+// Frees a seat held for a player who dropped, unless they've rejoined since
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct ReleaseSeat {
+    pub player: Player,
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "agent")] {
         use crate::agents::{AgentException, AgentWs};
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, StreamHandler};
 use actix_web_actors::ws;
@@ -8,45 +9,125 @@ use tokio::task::JoinHandle;
 use tokio::time;
 use uuid::Uuid;
 
-use onitamalib::{GameMessage, GameState, Move, Player};
+use onitamalib::clock::Clock;
+use onitamalib::history::PositionHistory;
+use onitamalib::{
+    BotMessage, ChatContent, ChatMessage, GameEnding, GameMessage, GameState, LobbyMessage, Move,
+    Offer, Player, PublicRoom, RoomSettings,
+};
 
+use crate::bots::BotWs;
+use crate::lobby::Lobby;
 use crate::messages::{
-    AddressedGameMessage, CloseRoom, CreateRoom, JoinRoom, JoinedRoom, LeftRoom, SocketGameMessage,
+    AddressedGameMessage, AddressedLobbyMessage, CloseRoom, CreateRoom, FlagFall, JoinRoom,
+    JoinedBots, JoinedLobby, JoinedRoom, LeftBots, LeftLobby, LeftRoom, ListBots, ReleaseSeat,
+    RoomListing, SocketBotMessage, SocketGameMessage, SocketLobbyMessage,
 };
+use crate::store::{now_secs, restore_rooms, SharedStore, StoredRoom};
 
 /// Socket
 ///
+// What the socket asked for when it connected
+#[derive(Clone, Debug)]
+pub enum RoomRequest {
+    Create {
+        settings: RoomSettings,
+        public: bool,
+        bot: Option<String>,
+    },
+    Join {
+        room_key: Uuid,
+        token: Option<Uuid>,
+        // Bot account the socket signed in as
+        bot: Option<String>,
+    },
+    Watch {
+        room_key: Uuid,
+    },
+}
+
 pub struct RoomWs {
     room: Option<Addr<OnitamaRoom>>,
     server: Addr<OnitamaServer>,
     room_key: Option<Uuid>,
+    request: RoomRequest,
     id: String,
+    // Messages are JSON text rather than CBOR, for bots
+    json: bool,
 }
 
 impl RoomWs {
-    pub fn new(server: Addr<OnitamaServer>, room_key: Option<Uuid>, id: String) -> RoomWs {
+    pub fn new(
+        server: Addr<OnitamaServer>,
+        request: RoomRequest,
+        id: String,
+        json: bool,
+    ) -> RoomWs {
         RoomWs {
             room: None,
             server,
-            room_key,
+            room_key: None,
+            request,
             id,
+            json,
         }
     }
+    fn send(&self, msg: &GameMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        match self.json {
+            true => ctx.text(serde_json::to_string(msg).expect("Failed to serialize message")),
+            false => ctx.binary(ser::to_vec(msg).expect("Failed to serialize message")),
+        };
+    }
 }
 
 impl Actor for RoomWs {
     type Context = ws::WebsocketContext<Self>;
     fn started(&mut self, ctx: &mut Self::Context) {
         let addr = ctx.address();
-        match self.room_key {
-            None => {
-                let msg = CreateRoom(addr);
+        match self.request.clone() {
+            RoomRequest::Create {
+                settings,
+                public,
+                bot,
+            } => {
+                let msg = CreateRoom {
+                    addr,
+                    settings,
+                    public,
+                    bot,
+                };
                 self.server.do_send(msg);
             }
-            Some(room_key) => {
-                let msg = JoinRoom { addr, room_key };
+            RoomRequest::Join {
+                room_key,
+                token,
+                bot,
+            } => {
+                let msg = JoinRoom {
+                    addr,
+                    room_key,
+                    spectate: false,
+                    token,
+                    bot,
+                };
                 self.server.do_send(msg);
             }
+            RoomRequest::Watch { room_key } => {
+                let msg = JoinRoom {
+                    addr,
+                    room_key,
+                    spectate: true,
+                    token: None,
+                    bot: None,
+                };
+                self.server.do_send(msg);
+            }
+        }
+    }
+    // Also reached when the connection drops without closing, so the room holds the seat for them
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        if let Some(room) = &self.room {
+            room.do_send(LeftRoom(ctx.address()));
         }
     }
 }
@@ -58,14 +139,18 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for RoomWs {
                 ctx.pong(&msg);
                 return;
             }
-            Ok(ws::Message::Binary(data)) => data,
+            Ok(ws::Message::Binary(data)) if !self.json => {
+                serde_cbor::from_slice(data.as_ref()).map_err(|err| format!("{:?}", err))
+            }
             Ok(ws::Message::Close(reason)) => {
                 info!("Connection closed, reason: {:?}", reason);
-                if let Some(room) = &self.room {
-                    room.do_send(LeftRoom(ctx.address()));
-                }
+                ctx.close(reason);
+                ctx.stop();
                 return;
             }
+            Ok(ws::Message::Text(text)) if self.json && text != "ping" => {
+                serde_json::from_str(&text).map_err(|err| err.to_string())
+            }
             Ok(ws::Message::Text(_)) => {
                 ctx.text("pong");
                 return;
@@ -76,10 +161,14 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for RoomWs {
                 return;
             }
         };
-        let msg: GameMessage = match serde_cbor::from_slice(data.as_ref()) {
+        let msg: GameMessage = match data {
             Ok(msg) => msg,
             Err(err) => {
-                warn!("Error deserializing player message: {:?}", err);
+                warn!("Error deserializing player message: {}", err);
+                if self.json {
+                    let message = format!("Invalid message: {}", err);
+                    self.send(&GameMessage::Error { message }, ctx);
+                }
                 return;
             }
         };
@@ -87,7 +176,13 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for RoomWs {
             Some(room) => room,
             None => {
                 warn!("Message sent too early");
-                ctx.text("Error: Message too early");
+                match self.json {
+                    true => {
+                        let message = "Message too early".to_string();
+                        self.send(&GameMessage::Error { message }, ctx);
+                    }
+                    false => ctx.text("Error: Message too early"),
+                };
                 return;
             }
         };
@@ -103,13 +198,15 @@ impl Handler<JoinedRoom> for RoomWs {
     type Result = ();
     fn handle(&mut self, msg: JoinedRoom, ctx: &mut Self::Context) {
         let msg = match msg {
-            JoinedRoom::Error { message } => GameMessage::Error { message },
             JoinedRoom::Success {
                 addr,
                 room_key,
                 player,
                 state,
                 waiting,
+                clock,
+                token,
+                settings,
             } => {
                 info!("Joined room {} as {:?}: {}", room_key, player, self.id);
                 self.room = Some(addr);
@@ -119,11 +216,28 @@ impl Handler<JoinedRoom> for RoomWs {
                     room_id: room_key.to_string(),
                     player,
                     waiting,
+                    clock,
+                    token: Some(token.to_string()),
+                    settings: Some(settings),
+                }
+            }
+            JoinedRoom::Spectating {
+                addr,
+                room_key,
+                state,
+                clock,
+            } => {
+                info!("Spectating room {}: {}", room_key, self.id);
+                self.room = Some(addr);
+                self.room_key = Some(room_key);
+                GameMessage::Spectate {
+                    state,
+                    room_id: room_key.to_string(),
+                    clock,
                 }
             }
         };
-        let msg = ser::to_vec(&msg).expect("failed to serialize initialize message");
-        ctx.binary(msg);
+        self.send(&msg, ctx);
     }
 }
 
@@ -131,8 +245,7 @@ impl Handler<SocketGameMessage> for RoomWs {
     type Result = ();
     fn handle(&mut self, msg: SocketGameMessage, ctx: &mut Self::Context) {
         let SocketGameMessage(msg) = msg;
-        let data = ser::to_vec(&msg).expect("Failed to serialize message");
-        ctx.binary(data);
+        self.send(&msg, ctx);
     }
 }
 
@@ -140,28 +253,166 @@ impl Handler<SocketGameMessage> for RoomWs {
 ///
 pub struct OnitamaRoom {
     game_state: GameState,
+    history: PositionHistory,
+    moves: Vec<Move>,
+    // The state before each move, for takebacks
+    positions: Vec<GameState>,
+    // An offer waiting for the other player to answer
+    offer: Option<(Player, Offer)>,
     red: Option<Addr<RoomWs>>,
     blue: Option<Addr<RoomWs>>,
+    spectators: Vec<Addr<RoomWs>>,
     key: Uuid,
     requested_rematch: Option<Player>,
     close_room_handle: Option<JoinHandle<()>>,
+    // Time left at the start of the current turn, for rooms with a time control
+    clock: Option<Clock>,
+    // When the clock started running for the current turn,
+    // it starts once both players have joined and keeps running if either leaves
+    turn_started: Option<Instant>,
+    flag_handle: Option<JoinHandle<()>>,
+    // Session tokens handed to each player, letting them reclaim their seat when they rejoin
+    red_token: Uuid,
+    blue_token: Uuid,
+    // Seats held for players who dropped, until their grace period runs out
+    reserved: HashMap<Player, JoinHandle<()>>,
+    // Restored with its tokens, so both seats are held for the players to come back
+    restored: bool,
+    // The bot challenged to the game, the seat left is kept for it
+    invited: Option<String>,
+    // Bot accounts sitting in each seat, only they can reclaim those seats
+    bots: HashMap<Player, String>,
+    chat: Vec<ChatMessage>,
+    // When each player's recent chat messages were sent, for rate limiting
+    chat_sent: HashMap<Player, VecDeque<Instant>>,
+    settings: RoomSettings,
+    public: bool,
+    // Seconds since the unix epoch
+    created: u64,
+    server: Addr<OnitamaServer>,
+    store: SharedStore,
 }
 
 impl OnitamaRoom {
-    pub fn new() -> OnitamaRoom {
+    pub fn new(
+        server: Addr<OnitamaServer>,
+        store: SharedStore,
+        settings: RoomSettings,
+        public: bool,
+    ) -> OnitamaRoom {
+        let game_state = settings.new_game();
         OnitamaRoom {
-            game_state: GameState::new(),
+            game_state,
+            history: PositionHistory::new(&game_state, None),
+            moves: vec![],
+            positions: vec![],
+            offer: None,
             red: None,
             blue: None,
+            spectators: vec![],
             key: Uuid::new_v4(),
             requested_rematch: None,
             close_room_handle: None,
+            clock: settings.time_control.map(Clock::new),
+            turn_started: None,
+            flag_handle: None,
+            red_token: Uuid::new_v4(),
+            blue_token: Uuid::new_v4(),
+            reserved: HashMap::new(),
+            restored: false,
+            invited: None,
+            bots: HashMap::new(),
+            chat: vec![],
+            chat_sent: HashMap::new(),
+            settings,
+            public,
+            created: now_secs(),
+            server,
+            store,
+        }
+    }
+    // A room saved before the server restarted, waiting for its players to rejoin
+    pub fn restore(
+        key: Uuid,
+        stored: StoredRoom,
+        server: Addr<OnitamaServer>,
+        store: SharedStore,
+    ) -> OnitamaRoom {
+        // Rooms saved before tokens existed get new ones, which nobody holds
+        let (red_token, blue_token) = stored
+            .tokens
+            .unwrap_or_else(|| (Uuid::new_v4(), Uuid::new_v4()));
+        OnitamaRoom {
+            game_state: stored.game_state,
+            history: stored.history,
+            moves: stored.moves,
+            positions: stored.positions,
+            offer: None,
+            red: None,
+            blue: None,
+            spectators: vec![],
+            key,
+            requested_rematch: stored.requested_rematch,
+            close_room_handle: None,
+            clock: stored.clock,
+            turn_started: None,
+            flag_handle: None,
+            red_token,
+            blue_token,
+            reserved: HashMap::new(),
+            restored: stored.tokens.is_some(),
+            invited: stored.invited,
+            bots: stored.bots,
+            chat: stored.chat,
+            chat_sent: HashMap::new(),
+            settings: stored.settings,
+            public: stored.public,
+            // Rooms saved without it were created no later than their last save
+            created: match stored.created {
+                0 => stored.updated,
+                created => created,
+            },
+            server,
+            store,
+        }
+    }
+    fn save(&self) {
+        let stored = StoredRoom {
+            game_state: self.game_state,
+            moves: self.moves.clone(),
+            positions: self.positions.clone(),
+            history: self.history.clone(),
+            requested_rematch: self.requested_rematch,
+            settings: self.settings.clone(),
+            public: self.public,
+            clock: self.current_clock(),
+            tokens: Some((self.red_token, self.blue_token)),
+            invited: self.invited.clone(),
+            bots: self.bots.clone(),
+            chat: self.chat.clone(),
+            created: self.created,
+            updated: now_secs(),
+        };
+        if let Err(err) = self.store.save(self.key, &stored) {
+            error!("Failed to save room {}: {}", self.key, err);
+        }
+        // Finished games are kept safe straight away, others wait for the store's next flush
+        if self.game_state.finished() {
+            self.store.flush();
         }
     }
 }
 
 impl Actor for OnitamaRoom {
     type Context = Context<Self>;
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.restored {
+            self.reserve_seat(Player::Red, ctx);
+            self.reserve_seat(Player::Blue, ctx);
+        }
+        // Rooms made by matchmaking close if neither player turns up
+        self.close_if_empty(ctx);
+    }
 }
 
 impl OnitamaRoom {
@@ -187,6 +438,56 @@ impl OnitamaRoom {
         }
         return None;
     }
+    fn player_from_token(&self, token: Uuid) -> Option<Player> {
+        if token == self.red_token {
+            return Some(Player::Red);
+        }
+        if token == self.blue_token {
+            return Some(Player::Blue);
+        }
+        None
+    }
+    fn token(&self, player: Player) -> Uuid {
+        match player {
+            Player::Red => self.red_token,
+            Player::Blue => self.blue_token,
+        }
+    }
+    fn seat(&mut self, player: Player) -> &mut Option<Addr<RoomWs>> {
+        match player {
+            Player::Red => &mut self.red,
+            Player::Blue => &mut self.blue,
+        }
+    }
+    // Free for anyone to take, nobody is in it or coming back to it
+    fn seat_free(&self, player: Player) -> bool {
+        let sock = match player {
+            Player::Red => &self.red,
+            Player::Blue => &self.blue,
+        };
+        sock.is_none() && !self.reserved.contains_key(&player)
+    }
+    // Public rooms are listed while a new game is waiting for an opponent
+    fn listing(&self) -> Option<PublicRoom> {
+        let waiting = self.seat_free(Player::Red) != self.seat_free(Player::Blue);
+        let fresh = self.moves.is_empty() && !self.game_state.finished();
+        if !self.public || !waiting || !fresh {
+            return None;
+        }
+        Some(PublicRoom {
+            room_id: self.key.to_string(),
+            settings: self.settings.clone(),
+            created: self.created,
+        })
+    }
+    fn update_listing(&self) {
+        if self.public {
+            self.server.do_send(RoomListing {
+                key: self.key,
+                listing: self.listing(),
+            });
+        }
+    }
     fn broadcast(&self, msg: GameMessage) {
         if let Some(sock) = &self.blue {
             let msg = SocketGameMessage(msg.clone());
@@ -196,48 +497,149 @@ impl OnitamaRoom {
             let msg = SocketGameMessage(msg.clone());
             sock.do_send(msg);
         }
+        self.send_to_spectators(msg);
+    }
+    fn send_to_spectators(&self, msg: GameMessage) {
+        for sock in self.spectators.iter() {
+            sock.do_send(SocketGameMessage(msg.clone()));
+        }
+    }
+    fn watchers(&self) -> GameMessage {
+        GameMessage::Watchers {
+            count: self.spectators.len() as u32,
+        }
+    }
+    fn cancel_close(&mut self) {
+        if let Some(handle) = self.close_room_handle.take() {
+            info!("Canceled room close: {}", self.key);
+            handle.abort();
+        }
+    }
+    fn add_spectator(&mut self, socket: Addr<RoomWs>, room: Addr<OnitamaRoom>) {
+        self.spectators.push(socket.clone());
+        info!(
+            "Spectator joined room {}, {} watching",
+            self.key,
+            self.spectators.len()
+        );
+        let msg = JoinedRoom::Spectating {
+            addr: room,
+            room_key: self.key,
+            state: self.game_state,
+            clock: self.current_clock(),
+        };
+        socket.do_send(msg);
+        self.send_chat_history(&socket);
+        self.broadcast(self.watchers());
+    }
+    fn send_chat_history(&self, socket: &Addr<RoomWs>) {
+        if !self.chat.is_empty() {
+            let messages = self.chat.clone();
+            socket.do_send(SocketGameMessage(GameMessage::ChatHistory { messages }));
+        }
+    }
+    fn handle_chat(&mut self, content: ChatContent, player: Player) -> Result<(), String> {
+        let content = content.cleaned()?;
+        let sent = self.chat_sent.entry(player).or_default();
+        if !within_chat_limit(sent, Instant::now()) {
+            return Err("Sending messages too quickly, wait a moment".to_string());
+        }
+        let message = ChatMessage {
+            from: player,
+            content,
+        };
+        self.chat.push(message.clone());
+        if self.chat.len() > CHAT_HISTORY {
+            self.chat.remove(0);
+        }
+        self.save();
+        self.broadcast(GameMessage::Chat { message });
+        Ok(())
     }
 }
 
+// Most chat messages a player can send in CHAT_WINDOW
+pub const CHAT_BURST: usize = 5;
+pub const CHAT_WINDOW: Duration = Duration::from_secs(10);
+// Chat messages kept for players who join later
+const CHAT_HISTORY: usize = 100;
+
+// Records a message sent at `now` unless the player has already sent CHAT_BURST in the last CHAT_WINDOW
+pub fn within_chat_limit(sent: &mut VecDeque<Instant>, now: Instant) -> bool {
+    sent.retain(|&at| now.duration_since(at) <= CHAT_WINDOW);
+    if sent.len() >= CHAT_BURST {
+        return false;
+    }
+    sent.push_back(now);
+    true
+}
+
 impl Handler<JoinRoom> for OnitamaRoom {
     type Result = ();
     fn handle(&mut self, msg: JoinRoom, ctx: &mut Self::Context) {
-        let socket = msg.addr;
-        let mut waiting = false;
-        let player: Player = match (&self.red, &self.blue) {
-            (None, None) => {
-                waiting = true;
-                let mut rng = thread_rng();
-                match rng.gen() {
-                    true => Player::Red,
-                    false => Player::Blue,
+        let JoinRoom {
+            addr: socket,
+            spectate,
+            token,
+            bot,
+            ..
+        } = msg;
+        if spectate {
+            self.add_spectator(socket, ctx.address());
+            return;
+        }
+        // A player rejoining with their token gets their seat back, bots' seats need the same bot
+        let reclaimed = token
+            .and_then(|token| self.player_from_token(token))
+            .filter(|player| match self.bots.get(player) {
+                Some(name) => bot.as_ref() == Some(name),
+                None => true,
+            });
+        let player = match reclaimed {
+            Some(player) => player,
+            None => match (self.seat_free(Player::Red), self.seat_free(Player::Blue)) {
+                (true, true) => {
+                    let mut rng = thread_rng();
+                    match rng.gen() {
+                        true => Player::Red,
+                        false => Player::Blue,
+                    }
                 }
-            }
-            (Some(_), None) => Player::Blue,
-            (None, Some(_)) => Player::Red,
-            (Some(_), Some(_)) => {
-                let message = "Room is full".to_string();
-                let msg = JoinedRoom::Error { message };
-                socket.do_send(msg);
-                return;
-            }
-        };
-        match &self.close_room_handle {
-            None => {}
-            Some(handle) => {
-                info!("Canceled room close: {}", self.key);
-                handle.abort();
-                self.close_room_handle = None;
-            }
+                // The seat left in a challenge is only for the bot challenged
+                _ if self.invited.is_some() && bot != self.invited => {
+                    self.add_spectator(socket, ctx.address());
+                    return;
+                }
+                (false, true) => Player::Blue,
+                (true, false) => Player::Red,
+                // Anyone joining a full room watches instead
+                (false, false) => {
+                    self.add_spectator(socket, ctx.address());
+                    return;
+                }
+            },
         };
-        match player {
-            Player::Red => {
-                self.red = Some(socket.clone());
+        self.cancel_close();
+        if let Some(handle) = self.reserved.remove(&player) {
+            info!("{:?} reclaimed their seat: {}", player, self.key);
+            handle.abort();
+        }
+        match bot {
+            Some(name) => {
+                info!("Bot {} is playing {:?}: {}", name, player, self.key);
+                self.bots.insert(player, name);
             }
-            Player::Blue => {
-                self.blue = Some(socket.clone());
+            None => {
+                self.bots.remove(&player);
             }
         };
+        let previous = self.seat(player).replace(socket.clone());
+        if let Some(previous) = previous.filter(|previous| previous != &socket) {
+            // Rejoined from somewhere else, such as another tab, so the old socket is left out
+            let message = "Joined this game from another window".to_string();
+            previous.do_send(SocketGameMessage(GameMessage::Error { message }));
+        }
+        let waiting = self.seat(player.invert()).is_none();
         let addr = ctx.address();
         let room_key = self.key;
         let msg = JoinedRoom::Success {
@@ -246,10 +648,23 @@ impl Handler<JoinRoom> for OnitamaRoom {
             player,
             waiting,
             state: self.game_state,
+            clock: self.current_clock(),
+            token: self.token(player),
+            settings: self.settings.clone(),
         };
         socket.do_send(msg);
+        self.send_chat_history(&socket);
+        // The clock starts once both players are in
+        if self.red.is_some() && self.blue.is_some() && self.turn_started.is_none() {
+            self.start_turn(ctx);
+        }
+        if !self.spectators.is_empty() {
+            self.send_to_player(player, self.watchers());
+        }
         // Send join message
         self.send_to_player(player.invert(), GameMessage::Joined);
+        self.send_to_spectators(GameMessage::Joined);
+        self.update_listing();
     }
 }
 
@@ -258,78 +673,263 @@ async fn delay_exit(addr: Addr<OnitamaRoom>) {
     addr.do_send(CloseRoom {});
 }
 
+// How long a dropped player's seat is held for them
+const SEAT_GRACE_SECS: u64 = 60;
+
+async fn delay_release_seat(addr: Addr<OnitamaRoom>, player: Player) {
+    time::sleep(time::Duration::from_secs(SEAT_GRACE_SECS)).await;
+    addr.do_send(ReleaseSeat { player });
+}
+
 impl Handler<LeftRoom> for OnitamaRoom {
     type Result = ();
     fn handle(&mut self, msg: LeftRoom, ctx: &mut Self::Context) {
-        info!("Player left room: {}", self.key);
         let LeftRoom(addr) = msg;
-        if self.blue.as_ref() == Some(&addr) {
-            self.blue = None;
-        }
-        if self.red.as_ref() == Some(&addr) {
-            self.red = None;
+        if let Some(idx) = self.spectators.iter().position(|sock| sock == &addr) {
+            self.spectators.remove(idx);
+            info!("Spectator left room: {}", self.key);
+            self.broadcast(self.watchers());
+            return;
         }
-        match (&self.blue, &self.red) {
-            (None, None) => {
-                info!("Room Empty: {}", self.key.clone());
-                let addr = ctx.address();
-                let handle = tokio::spawn(delay_exit(addr));
-                self.close_room_handle = Some(handle);
-            }
-            _ => {
-                self.broadcast(GameMessage::Disconnected);
+        // Sockets replaced by the same player rejoining have already lost their seat
+        let player = match self.player_from_addr(&addr) {
+            Some(player) => player,
+            None => {
+                return;
             }
         };
+        info!("Player left room: {}", self.key);
+        *self.seat(player) = None;
+        self.reserve_seat(player, ctx);
+        self.broadcast(GameMessage::Reconnecting);
+        self.update_listing();
+    }
+}
+
+impl Handler<ReleaseSeat> for OnitamaRoom {
+    type Result = ();
+    fn handle(&mut self, msg: ReleaseSeat, ctx: &mut Self::Context) {
+        let ReleaseSeat { player } = msg;
+        if self.seat(player).is_some() || self.reserved.remove(&player).is_none() {
+            return;
+        }
+        info!("{:?} didn't reconnect in time: {}", player, self.key);
+        self.broadcast(GameMessage::Disconnected);
+        self.update_listing();
+        self.close_if_empty(ctx);
+    }
+}
+
+impl OnitamaRoom {
+    fn reserve_seat(&mut self, player: Player, ctx: &mut Context<Self>) {
+        let handle = tokio::spawn(delay_release_seat(ctx.address(), player));
+        if let Some(previous) = self.reserved.insert(player, handle) {
+            previous.abort();
+        }
+    }
+    // Neither player is in the room or coming back to it, spectators alone don't keep it open
+    fn is_empty(&self) -> bool {
+        self.blue.is_none() && self.red.is_none() && self.reserved.is_empty()
+    }
+    // Schedules the room to close once neither player is left
+    fn close_if_empty(&mut self, ctx: &mut Context<Self>) {
+        if !self.is_empty() {
+            return;
+        }
+        info!("Room Empty: {}", self.key.clone());
+        let addr = ctx.address();
+        let handle = tokio::spawn(delay_exit(addr));
+        self.close_room_handle = Some(handle);
     }
 }
 
 impl Handler<CloseRoom> for OnitamaRoom {
     type Result = ();
     fn handle(&mut self, _msg: CloseRoom, ctx: &mut Self::Context) {
-        match (&self.blue, &self.red) {
-            (None, None) => {
+        match self.is_empty() {
+            true => {
                 info!("Room Closing: {}", self.key.clone());
+                let message = "The players left, so the room has closed".to_string();
+                self.send_to_spectators(GameMessage::Error { message });
+                if let Err(err) = self.store.remove(self.key) {
+                    error!("Failed to remove room {}: {}", self.key, err);
+                }
+                if self.public {
+                    self.server.do_send(RoomListing {
+                        key: self.key,
+                        listing: None,
+                    });
+                }
                 ctx.stop();
             }
-            _ => {
+            false => {
                 error!("Almost closed running room ({}), this is a bug", self.key);
             }
         };
     }
 }
 
+async fn delay_flag_fall(addr: Addr<OnitamaRoom>, millis: u64, moves: usize) {
+    time::sleep(time::Duration::from_millis(millis)).await;
+    addr.do_send(FlagFall { moves });
+}
+
+fn millis_since(instant: Instant) -> u64 {
+    instant.elapsed().as_millis() as u64
+}
+
+impl OnitamaRoom {
+    // The clock as it stands now, counting the turn in progress
+    fn current_clock(&self) -> Option<Clock> {
+        let clock = self.clock?;
+        match (self.game_state, self.turn_started) {
+            (GameState::Playing { board }, Some(started)) => {
+                Some(clock.after(board.turn, millis_since(started)))
+            }
+            _ => Some(clock),
+        }
+    }
+    // Starts the clock for the player to move, if the room has one and the game is still going
+    fn start_turn(&mut self, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.flag_handle.take() {
+            handle.abort();
+        }
+        self.turn_started = None;
+        let (clock, board) = match (self.clock, self.game_state) {
+            (Some(clock), GameState::Playing { board }) => (clock, board),
+            _ => {
+                return;
+            }
+        };
+        self.turn_started = Some(Instant::now());
+        self.schedule_flag_fall(ctx, clock.remaining(board.turn));
+    }
+    fn schedule_flag_fall(&mut self, ctx: &mut Context<Self>, millis: u64) {
+        let handle = tokio::spawn(delay_flag_fall(ctx.address(), millis, self.moves.len()));
+        self.flag_handle = Some(handle);
+    }
+    // Ends the game as a loss for the player who ran out of time
+    fn flag(&mut self, player: Player) {
+        if self.game_state.finished() {
+            return;
+        }
+        info!("{:?} ran out of time: {}", player, self.key);
+        if let Some(clock) = self.clock.as_mut() {
+            clock.finish_turn(player, u64::MAX);
+        }
+        self.finish(Some(player.invert()), GameEnding::Timeout);
+        self.broadcast(GameMessage::Timeout { player });
+    }
+    // Ends a game still being played without a move, stopping the clock where it stands
+    fn finish(&mut self, winner: Option<Player>, ending: GameEnding) {
+        let board = match self.game_state {
+            GameState::Playing { board } => board,
+            GameState::Finished { .. } => {
+                return;
+            }
+        };
+        if let Some(handle) = self.flag_handle.take() {
+            handle.abort();
+        }
+        self.clock = self.current_clock();
+        self.turn_started = None;
+        self.offer = None;
+        self.game_state = GameState::Finished {
+            board,
+            winner,
+            ending: Some(ending),
+        };
+        self.save();
+    }
+}
+
+impl Handler<FlagFall> for OnitamaRoom {
+    type Result = ();
+    fn handle(&mut self, msg: FlagFall, ctx: &mut Self::Context) {
+        // A move was made in time, or the game has ended since the timer was set
+        if msg.moves != self.moves.len() || self.turn_started.is_none() {
+            return;
+        }
+        let (clock, board) = match (self.current_clock(), self.game_state) {
+            (Some(clock), GameState::Playing { board }) => (clock, board),
+            _ => {
+                return;
+            }
+        };
+        match clock.remaining(board.turn) {
+            0 => self.flag(board.turn),
+            remaining => self.schedule_flag_fall(ctx, remaining),
+        };
+    }
+}
+
 impl OnitamaRoom {
-    fn handle_move(&mut self, game_move: Move, player: Player) {
+    // Errors are sent back to the player, the move is otherwise ignored
+    fn handle_move(
+        &mut self,
+        game_move: Move,
+        player: Player,
+        ctx: &mut Context<Self>,
+    ) -> Result<(), String> {
         let board = match self.game_state {
             GameState::Playing { board } => board,
             GameState::Finished { .. } => {
                 info!("Attempted move on finished game");
-                return;
+                return Err("The game is over".to_string());
             }
         };
         if board.turn != player {
             error!("Not player's turn");
-            return;
+            return Err("It isn't your turn".to_string());
         }
         let new_state = match board.try_move(game_move) {
             Ok(new_state) => new_state,
             Err(err) => {
                 error!("Player played illegal move: {:?}", err);
-                return;
+                return Err(format!("Illegal move: {}", err));
             }
         };
-        self.game_state = new_state;
+        if let Some(mut clock) = self.clock {
+            let elapsed = self.turn_started.map_or(0, millis_since);
+            if !clock.finish_turn(player, elapsed) {
+                // Everyone hears of the timeout instead
+                self.flag(player);
+                return Ok(());
+            }
+            self.clock = Some(clock);
+        }
+        self.positions.push(self.game_state);
+        self.game_state = self.history.record(new_state);
+        self.moves.push(game_move);
+        // Moving lets any offer lapse
+        self.offer = None;
+        self.start_turn(ctx);
+        self.save();
         let next_player = player.invert();
-        let msg = GameMessage::Move { game_move };
-        self.send_to_player(next_player, msg);
+        let msg = GameMessage::Move {
+            game_move,
+            clock: self.clock,
+        };
+        self.send_to_player(next_player, msg.clone());
+        self.send_to_spectators(msg);
+        if let Some(clock) = self.clock {
+            self.send_to_player(player, GameMessage::Clock { clock });
+        }
+        if let GameState::Finished { winner: None, .. } = self.game_state {
+            info!("Game drawn: {}", self.key);
+            self.broadcast(GameMessage::Drawn);
+        }
+        Ok(())
     }
-    fn handle_rematch_request(&mut self, player: Player) {
+    fn handle_rematch_request(&mut self, player: Player, ctx: &mut Context<Self>) {
         let requested_player = match self.requested_rematch {
             None => {
                 self.requested_rematch = Some(player);
+                self.save();
                 let msg = GameMessage::RequestRematch;
                 let other_player = player.invert();
-                self.send_to_player(other_player, msg);
+                self.send_to_player(other_player, msg.clone());
+                self.send_to_spectators(msg);
                 return;
             }
             Some(requested_player) => requested_player,
@@ -338,8 +938,15 @@ impl OnitamaRoom {
             info!("Player requsted rematch multiple times");
         } else {
             self.requested_rematch = None;
-            let state = GameState::new();
+            let state = self.settings.new_game();
             self.game_state = state;
+            self.history = PositionHistory::new(&state, None);
+            self.moves = vec![];
+            self.positions = vec![];
+            self.offer = None;
+            self.clock = self.clock.map(|clock| Clock::new(clock.control));
+            self.start_turn(ctx);
+            self.save();
             self.send_to_player(
                 Player::Red,
                 GameMessage::Initialize {
@@ -347,6 +954,9 @@ impl OnitamaRoom {
                     room_id: self.key.to_string(),
                     player: Player::Red,
                     waiting: false,
+                    clock: self.clock,
+                    token: Some(self.red_token.to_string()),
+                    settings: Some(self.settings.clone()),
                 },
             );
             self.send_to_player(
@@ -356,18 +966,126 @@ impl OnitamaRoom {
                     room_id: self.key.to_string(),
                     player: Player::Blue,
                     waiting: false,
+                    clock: self.clock,
+                    token: Some(self.blue_token.to_string()),
+                    settings: Some(self.settings.clone()),
                 },
             );
+            self.send_to_spectators(GameMessage::Spectate {
+                state,
+                room_id: self.key.to_string(),
+                clock: self.clock,
+            });
+        }
+    }
+}
+
+impl OnitamaRoom {
+    fn handle_resign(&mut self, player: Player) {
+        if self.game_state.finished() {
+            info!("Attempted to resign finished game");
+            return;
+        }
+        info!("{:?} resigned: {}", player, self.key);
+        self.finish(Some(player.invert()), GameEnding::Resignation);
+        self.broadcast(GameMessage::Resigned { player });
+    }
+    // Moves a takeback asked for by the player would undo, back to before their last move
+    fn takeback_moves(&self, player: Player) -> Option<usize> {
+        let moves = match self.game_state {
+            GameState::Playing { board } if board.turn == player => 2,
+            GameState::Playing { .. } => 1,
+            GameState::Finished { .. } => {
+                return None;
+            }
+        };
+        match self.positions.len() >= moves {
+            true => Some(moves),
+            false => None,
+        }
+    }
+    fn handle_offer(&mut self, offer: Offer, player: Player, ctx: &mut Context<Self>) {
+        let opponent = player.invert();
+        // Offering what the opponent has already offered agrees to it
+        if self.offer == Some((opponent, offer)) {
+            self.handle_accept(offer, player, ctx);
+            return;
+        }
+        // Already waiting on an answer, the opponent isn't asked again
+        if self.offer == Some((player, offer)) {
+            return;
+        }
+        let valid = match offer {
+            Offer::Draw => !self.game_state.finished(),
+            Offer::Takeback => self.takeback_moves(player).is_some(),
+        };
+        if !valid || self.seat(opponent).is_none() {
+            info!("Ignoring {:?} offer from {:?}: {}", offer, player, self.key);
+            return;
+        }
+        self.offer = Some((player, offer));
+        self.send_to_player(opponent, GameMessage::Offer { offer });
+    }
+    fn handle_accept(&mut self, offer: Offer, player: Player, ctx: &mut Context<Self>) {
+        let offered_by = player.invert();
+        if self.offer != Some((offered_by, offer)) {
+            info!("Accepted {:?} that wasn't offered: {}", offer, self.key);
+            return;
+        }
+        self.offer = None;
+        match offer {
+            Offer::Draw => {
+                info!("Draw agreed: {}", self.key);
+                self.finish(None, GameEnding::DrawAgreed);
+                self.broadcast(GameMessage::Accept { offer });
+            }
+            Offer::Takeback => {
+                let moves = match self.takeback_moves(offered_by) {
+                    Some(moves) => moves,
+                    None => {
+                        return;
+                    }
+                };
+                info!("Taking back {} moves: {}", moves, self.key);
+                // Time already spent on the current turn stays spent
+                self.clock = self.current_clock();
+                for _ in 0..moves {
+                    if let Some(state) = self.positions.pop() {
+                        self.history.forget(&self.game_state);
+                        self.game_state = state;
+                        self.moves.pop();
+                    }
+                }
+                self.start_turn(ctx);
+                self.save();
+                let msg = GameMessage::TakenBack {
+                    state: self.game_state,
+                    clock: self.clock,
+                };
+                self.broadcast(msg);
+            }
+        };
+    }
+    fn handle_decline(&mut self, offer: Offer, player: Player) {
+        let offered_by = player.invert();
+        if self.offer != Some((offered_by, offer)) {
+            return;
         }
+        self.offer = None;
+        self.send_to_player(offered_by, GameMessage::Decline { offer });
     }
 }
 
 impl Handler<AddressedGameMessage> for OnitamaRoom {
     type Result = ();
-    fn handle(&mut self, msg: AddressedGameMessage, _ctx: &mut Self::Context) {
+    fn handle(&mut self, msg: AddressedGameMessage, ctx: &mut Self::Context) {
         let AddressedGameMessage { sender, msg } = msg;
         let player = match self.player_from_addr(&sender) {
             Some(player) => player,
+            None if self.spectators.contains(&sender) => {
+                warn!("Ignoring message from spectator: {:?}", msg);
+                return;
+            }
             None => {
                 error!("Received game message from socket that's not in game");
                 let msg = GameMessage::Error {
@@ -378,11 +1096,30 @@ impl Handler<AddressedGameMessage> for OnitamaRoom {
             }
         };
         match msg {
-            GameMessage::Move { game_move } => {
-                self.handle_move(game_move, player);
+            GameMessage::Move { game_move, .. } => {
+                if let Err(message) = self.handle_move(game_move, player, ctx) {
+                    sender.do_send(SocketGameMessage(GameMessage::Error { message }));
+                }
             }
             GameMessage::RequestRematch => {
-                self.handle_rematch_request(player);
+                self.handle_rematch_request(player, ctx);
+            }
+            GameMessage::Resign => {
+                self.handle_resign(player);
+            }
+            GameMessage::Offer { offer } => {
+                self.handle_offer(offer, player, ctx);
+            }
+            GameMessage::Accept { offer } => {
+                self.handle_accept(offer, player, ctx);
+            }
+            GameMessage::Decline { offer } => {
+                self.handle_decline(offer, player);
+            }
+            GameMessage::SendChat { content } => {
+                if let Err(message) = self.handle_chat(content, player) {
+                    sender.do_send(SocketGameMessage(GameMessage::ChatRejected { message }));
+                }
             }
             GameMessage::Error { message } => {
                 error!("Received error from client: {}", message);
@@ -400,18 +1137,50 @@ impl Handler<AddressedGameMessage> for OnitamaRoom {
 ///
 pub struct OnitamaServer {
     rooms: HashMap<Uuid, Addr<OnitamaRoom>>,
+    lobby: Lobby,
+    // Connected bots by name, each can be challenged to games
+    bots: HashMap<String, Addr<BotWs>>,
+    store: SharedStore,
 }
 
 impl OnitamaServer {
-    pub fn new() -> OnitamaServer {
+    pub fn new(store: SharedStore) -> OnitamaServer {
         OnitamaServer {
             rooms: HashMap::new(),
+            lobby: Lobby::default(),
+            bots: HashMap::new(),
+            store,
         }
     }
+    // Rooms challenging a bot are invited, keeping the seat left for it
+    fn create_room(
+        &mut self,
+        settings: RoomSettings,
+        public: bool,
+        invited: Option<String>,
+        ctx: &mut Context<Self>,
+    ) -> (Uuid, Addr<OnitamaRoom>) {
+        let mut room = OnitamaRoom::new(ctx.address(), self.store.clone(), settings, public);
+        room.invited = invited;
+        let room_key = room.key;
+        room.save();
+        let room = room.start();
+        self.rooms.insert(room_key, room.clone());
+        (room_key, room)
+    }
 }
 
 impl Actor for OnitamaServer {
     type Context = Context<Self>;
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let restored = restore_rooms(&self.store);
+        info!("Restoring {} rooms", restored.len());
+        for (key, stored) in restored {
+            let room = OnitamaRoom::restore(key, stored, ctx.address(), self.store.clone());
+            let room = room.start();
+            self.rooms.insert(key, room);
+        }
+    }
 }
 
 impl Handler<JoinRoom> for OnitamaServer {
@@ -445,16 +1214,125 @@ impl Handler<JoinRoom> for OnitamaServer {
 
 impl Handler<CreateRoom> for OnitamaServer {
     type Result = ();
-    fn handle(&mut self, msg: CreateRoom, _: &mut Self::Context) {
+    fn handle(&mut self, msg: CreateRoom, ctx: &mut Self::Context) {
         println!("Server received create room request");
-        let room = OnitamaRoom::new();
-        let room_key = room.key;
-        let room = room.start();
-        self.rooms.insert(room_key, room.clone());
-        let msg = JoinRoom {
-            addr: msg.0,
+        let bot = match &msg.bot {
+            None => None,
+            Some(name) => match self.bots.get(name) {
+                Some(bot) => Some(bot.clone()),
+                None => {
+                    let message = format!("{} isn't online", name);
+                    msg.addr
+                        .do_send(SocketGameMessage(GameMessage::Error { message }));
+                    return;
+                }
+            },
+        };
+        // Games against bots are never listed, the bot is the only one invited
+        let public = msg.public && bot.is_none();
+        let (room_key, room) = self.create_room(msg.settings.clone(), public, msg.bot, ctx);
+        let join = JoinRoom {
+            addr: msg.addr,
             room_key,
+            spectate: false,
+            token: None,
+            bot: None,
+        };
+        room.do_send(join);
+        if let Some(bot) = bot {
+            let challenge = BotMessage::Challenge {
+                room_id: room_key.to_string(),
+                settings: msg.settings,
+            };
+            bot.do_send(SocketBotMessage(challenge));
+        }
+    }
+}
+
+impl Handler<JoinedBots> for OnitamaServer {
+    type Result = ();
+    fn handle(&mut self, msg: JoinedBots, _: &mut Self::Context) {
+        let JoinedBots { name, addr } = msg;
+        // A bot reconnecting takes over from its old connection
+        if let Some(old) = self.bots.insert(name.clone(), addr.clone()) {
+            let message = "Replaced by a newer connection".to_string();
+            old.do_send(SocketBotMessage(BotMessage::Error { message }));
+        }
+        addr.do_send(SocketBotMessage(BotMessage::Registered { name }));
+    }
+}
+
+impl Handler<LeftBots> for OnitamaServer {
+    type Result = ();
+    fn handle(&mut self, msg: LeftBots, _: &mut Self::Context) {
+        if self.bots.get(&msg.name) == Some(&msg.addr) {
+            self.bots.remove(&msg.name);
+        }
+    }
+}
+
+impl Handler<ListBots> for OnitamaServer {
+    type Result = Vec<String>;
+    fn handle(&mut self, _: ListBots, _: &mut Self::Context) -> Self::Result {
+        let mut names: Vec<String> = self.bots.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl Handler<RoomListing> for OnitamaServer {
+    type Result = ();
+    fn handle(&mut self, msg: RoomListing, _: &mut Self::Context) {
+        self.lobby.update_room(msg.key, msg.listing);
+    }
+}
+
+impl Handler<JoinedLobby> for OnitamaServer {
+    type Result = ();
+    fn handle(&mut self, msg: JoinedLobby, _: &mut Self::Context) {
+        self.lobby.join(msg.0);
+    }
+}
+
+impl Handler<LeftLobby> for OnitamaServer {
+    type Result = ();
+    fn handle(&mut self, msg: LeftLobby, _: &mut Self::Context) {
+        self.lobby.leave(&msg.0);
+    }
+}
+
+impl Handler<AddressedLobbyMessage> for OnitamaServer {
+    type Result = ();
+    fn handle(&mut self, msg: AddressedLobbyMessage, ctx: &mut Self::Context) {
+        let AddressedLobbyMessage { sender, msg } = msg;
+        match msg {
+            LobbyMessage::Seek { settings } => {
+                if let Err(message) = settings.validate() {
+                    sender.do_send(SocketLobbyMessage(LobbyMessage::Error { message }));
+                    return;
+                }
+                let settings = settings.normalized();
+                let opponent = match self.lobby.seek(sender.clone(), settings.clone()) {
+                    Some(opponent) => opponent,
+                    None => {
+                        sender.do_send(SocketLobbyMessage(LobbyMessage::Seeking));
+                        return;
+                    }
+                };
+                let (room_key, _) = self.create_room(settings, false, None, ctx);
+                info!("Matched players into room {}", room_key);
+                let msg = LobbyMessage::Matched {
+                    room_id: room_key.to_string(),
+                };
+                opponent.do_send(SocketLobbyMessage(msg.clone()));
+                sender.do_send(SocketLobbyMessage(msg));
+            }
+            LobbyMessage::CancelSeek => {
+                self.lobby.cancel_seek(&sender);
+            }
+            msg => {
+                info!("Unexpected lobby msg: {:?}", msg);
+            }
         };
-        room.do_send(msg);
     }
 }
@@ -1,18 +1,34 @@
 use std::str::FromStr;
 
-use crate::rooms::{OnitamaServer, RoomWs};
-use crate::utils::{get_identifier, get_useragent, get_ip};
+use crate::bots::{bot_authorized, BotWs};
+use crate::lobby::LobbyWs;
+use crate::messages::ListBots;
+use crate::rooms::{OnitamaServer, RoomRequest, RoomWs};
+use crate::utils::{get_identifier, get_ip, get_useragent};
 use actix::prelude::*;
-use actix_web::{error, web, Error, HttpRequest, HttpResponse};
+use actix_web::{error, http::header, web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
 use futures::StreamExt;
-use onitamalib::GameEvent;
+use onitamalib::clock::TimeControl;
+use onitamalib::{GameEvent, RoomSettings};
+use serde::Deserialize;
 use serde_cbor::de;
 use uuid::Uuid;
 
+#[derive(Deserialize)]
+pub struct JoinRoomQuery {
+    // Session token from an earlier join of the room
+    token: Option<String>,
+    // "json" to send and receive JSON text instead of CBOR, for bots
+    format: Option<String>,
+    // Name of the bot account joining, needed with "json"
+    bot: Option<String>,
+}
+
 pub async fn join_room(
     req: HttpRequest,
     path: web::Path<String>,
+    query: web::Query<JoinRoomQuery>,
     stream: web::Payload,
     data: web::Data<ServerData>,
 ) -> Result<HttpResponse, Error> {
@@ -25,37 +41,218 @@ pub async fn join_room(
             return Err(error::ErrorBadRequest("Invalid UUID"));
         }
     };
-    let actor = RoomWs::new(server, Some(key), id);
+    // A malformed token is treated as none, the player just gets a free seat
+    let token = query
+        .token
+        .as_ref()
+        .and_then(|token| Uuid::from_str(token).ok());
+    let json = query.format.as_deref() == Some("json");
+    // Only bots speak JSON, and they sign in with the same secret as on their bot socket
+    let bot = match (json, &query.bot) {
+        (false, _) => None,
+        (true, Some(name)) if bot_authorized(name, bearer_secret(&req)) => Some(name.clone()),
+        (true, name) => {
+            warn!(
+                "Bot {:?} failed to authenticate joining room {}: {}",
+                name, key, id
+            );
+            return Err(error::ErrorForbidden("Unknown bot or wrong secret"));
+        }
+    };
+    let actor = RoomWs::new(
+        server,
+        RoomRequest::Join {
+            room_key: key,
+            token,
+            bot,
+        },
+        id,
+        json,
+    );
     let resp = ws::start(actor, &req, stream);
     resp
 }
 
+pub async fn watch_room(
+    req: HttpRequest,
+    path: web::Path<String>,
+    stream: web::Payload,
+    data: web::Data<ServerData>,
+) -> Result<HttpResponse, Error> {
+    let key = path.to_string();
+    let server: Addr<OnitamaServer> = data.server_addr.clone();
+    let id = get_identifier(&req);
+    let key = match Uuid::from_str(&key) {
+        Ok(key) => key,
+        Err(_) => {
+            return Err(error::ErrorBadRequest("Invalid UUID"));
+        }
+    };
+    let actor = RoomWs::new(server, RoomRequest::Watch { room_key: key }, id, false);
+    ws::start(actor, &req, stream)
+}
+
+#[derive(Deserialize)]
+pub struct CreateRoomQuery {
+    // Time control for the room, such as "fischer:300:5", untimed if missing
+    time: Option<String>,
+    // Card sets to deal from, such as "Base,SenseiPath", every card if missing
+    sets: Option<String>,
+    // Exact cards to deal from instead, such as "Tiger,Crab,Frog,Eel,Ox"
+    cards: Option<String>,
+    // List the room in the lobby until an opponent joins
+    public: Option<bool>,
+}
+
+// Parses a comma separated list, such as card names
+fn parse_list<T: FromStr<Err = String>>(list: &Option<String>) -> Result<Vec<T>, String> {
+    match list {
+        None => Ok(vec![]),
+        Some(list) => list.split(',').map(T::from_str).collect(),
+    }
+}
+
+// Settings for a new room from its query
+fn room_settings(query: &CreateRoomQuery) -> Result<RoomSettings, Error> {
+    let time_control = match &query.time {
+        None => None,
+        Some(time) => match TimeControl::from_str(time) {
+            Ok(time_control) => Some(time_control),
+            Err(err) => {
+                return Err(error::ErrorBadRequest(err));
+            }
+        },
+    };
+    let settings = RoomSettings {
+        card_sets: parse_list(&query.sets).map_err(error::ErrorBadRequest)?,
+        cards: parse_list(&query.cards).map_err(error::ErrorBadRequest)?,
+        time_control,
+    };
+    settings.validate().map_err(error::ErrorBadRequest)?;
+    Ok(settings.normalized())
+}
+
 pub async fn create_room(
     req: HttpRequest,
+    query: web::Query<CreateRoomQuery>,
     stream: web::Payload,
     data: web::Data<ServerData>,
 ) -> Result<HttpResponse, Error> {
     let id = get_identifier(&req);
     let server: Addr<OnitamaServer> = data.server_addr.clone();
-    let actor = RoomWs::new(server, None, id);
+    let settings = room_settings(&query)?;
+    let public = query.public.unwrap_or(false);
+    let request = RoomRequest::Create {
+        settings,
+        public,
+        bot: None,
+    };
+    let actor = RoomWs::new(server, request, id, false);
     let resp = ws::start(actor, &req, stream);
     resp
 }
 
+// Creates a room and challenges a connected bot to join it
+pub async fn bot_room(
+    req: HttpRequest,
+    name: web::Path<String>,
+    query: web::Query<CreateRoomQuery>,
+    stream: web::Payload,
+    data: web::Data<ServerData>,
+) -> Result<HttpResponse, Error> {
+    let id = get_identifier(&req);
+    let server: Addr<OnitamaServer> = data.server_addr.clone();
+    let settings = room_settings(&query)?;
+    let request = RoomRequest::Create {
+        settings,
+        public: false,
+        bot: Some(name.to_string()),
+    };
+    info!("Bot Game Start: {}, ({})", &id, name.as_str());
+    let actor = RoomWs::new(server, request, id, false);
+    ws::start(actor, &req, stream)
+}
+
+// Connects a bot, which then gets told about games it's challenged to.
+// Bot secrets come in an "Authorization: Bearer" header, so they stay out of access logs
+fn bearer_secret(req: &HttpRequest) -> &str {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default()
+        .trim()
+}
+
+pub async fn connect_bot(
+    req: HttpRequest,
+    name: web::Path<String>,
+    stream: web::Payload,
+    data: web::Data<ServerData>,
+) -> Result<HttpResponse, Error> {
+    let name = name.to_string();
+    if !bot_authorized(&name, bearer_secret(&req)) {
+        warn!(
+            "Bot {} failed to authenticate: {}",
+            name,
+            get_identifier(&req)
+        );
+        return Err(error::ErrorForbidden("Unknown bot or wrong secret"));
+    }
+    let id = get_identifier(&req);
+    let server: Addr<OnitamaServer> = data.server_addr.clone();
+    ws::start(BotWs::new(server, name, id), &req, stream)
+}
+
+pub async fn list_bots(data: web::Data<ServerData>) -> Result<web::Json<Vec<String>>, Error> {
+    let names = data
+        .server_addr
+        .send(ListBots)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(web::Json(names))
+}
+
+pub async fn join_lobby(
+    req: HttpRequest,
+    stream: web::Payload,
+    data: web::Data<ServerData>,
+) -> Result<HttpResponse, Error> {
+    let id = get_identifier(&req);
+    let server: Addr<OnitamaServer> = data.server_addr.clone();
+    ws::start(LobbyWs::new(server, id), &req, stream)
+}
+
 pub struct ServerData {
     pub logger: slog::Logger,
     pub server_addr: Addr<OnitamaServer>,
+    // Played from by agents asked to use it
+    #[cfg(feature = "agent")]
+    pub book: Option<std::sync::Arc<onitamalib::book::OpeningBook>>,
 }
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "agent")] {
-        use onitamalib::AiAgent;
+        use onitamalib::{analyse, AiAgent, Analysis, AnalysisRequest};
 
         use crate::agents::AgentWs;
+
+        // Longest an analysis request can think for, in milliseconds
+        const MAX_ANALYSIS_TIME: u64 = 10_000;
+
+        #[derive(Deserialize)]
+        pub struct AiRoomQuery {
+            // Open from the server's book, if it has one
+            #[serde(default)]
+            book: bool,
+        }
+
         pub async fn ai_room(
             req: HttpRequest,
             difficulty: web::Path<String>,
+            query: web::Query<AiRoomQuery>,
             stream: web::Payload,
+            data: web::Data<ServerData>,
         ) -> Result<HttpResponse, Error> {
             let id = get_identifier(&req);
             let difficulty = difficulty.as_str();
@@ -63,13 +260,34 @@ cfg_if::cfg_if! {
                 "easy" => AiAgent::Greedy,
                 "medium" => AiAgent::PureMonteCarlo,
                 "hard" => AiAgent::HybridMonteCarlo,
+                "mcts" => AiAgent::MonteCarloTreeSearch,
                 _ => AiAgent::PureMonteCarlo,
             };
             info!("AI Game Start: {}, ({:?})", &id, ai);
-            let actor = AgentWs::new(id, ai);
+            let book = data.book.clone().filter(|_| query.book);
+            let actor = AgentWs::new(id, ai, book);
             let resp = ws::start(actor, &req, stream);
             resp
         }
+
+        // Best move for a position, without playing a game
+        pub async fn analyse_position(
+            req: HttpRequest,
+            request: web::Json<AnalysisRequest>,
+            data: web::Data<ServerData>,
+        ) -> Result<web::Json<Analysis>, Error> {
+            let request = request.into_inner();
+            if request.time == 0 || request.time > MAX_ANALYSIS_TIME {
+                let message = format!("Time must be between 1 and {}ms", MAX_ANALYSIS_TIME);
+                return Err(error::ErrorBadRequest(message));
+            }
+            info!("Analysis: {} ({:?}, {}ms)", get_identifier(&req), request.agent, request.time);
+            let book = data.book.clone();
+            let analysis = web::block(move || analyse(&request, book))
+                .await
+                .map_err(error::ErrorInternalServerError)?;
+            analysis.map(web::Json).map_err(error::ErrorBadRequest)
+        }
     }
 }
 
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use onitamalib::clock::Clock;
+use onitamalib::history::PositionHistory;
+use onitamalib::{ChatMessage, GameState, Move, Player, RoomSettings};
+
+// Rooms untouched for this long are dropped when the server starts
+const ROOM_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+// How often sled writes saved rooms to disk, moves made since are lost if the server crashes
+const FLUSH_EVERY_MS: u64 = 500;
+
+/// Everything needed to bring a room back after a restart
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredRoom {
+    pub game_state: GameState,
+    pub moves: Vec<Move>,
+    // The state before each move, for takebacks
+    #[serde(default)]
+    pub positions: Vec<GameState>,
+    pub history: PositionHistory,
+    pub requested_rematch: Option<Player>,
+    #[serde(default)]
+    pub settings: RoomSettings,
+    #[serde(default)]
+    pub public: bool,
+    #[serde(default)]
+    pub clock: Option<Clock>,
+    // Red and blue session tokens, so players can reclaim their seats after a restart
+    #[serde(default)]
+    pub tokens: Option<(Uuid, Uuid)>,
+    #[serde(default)]
+    pub chat: Vec<ChatMessage>,
+    // The bot challenged to the game, the seat left is kept for it
+    #[serde(default)]
+    pub invited: Option<String>,
+    // Bot accounts sitting in each seat
+    #[serde(default)]
+    pub bots: HashMap<Player, String>,
+    // Seconds since the unix epoch, zero for rooms saved before it was kept
+    #[serde(default)]
+    pub created: u64,
+    // Seconds since the unix epoch, rooms expire this long after their last save
+    pub updated: u64,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl StoredRoom {
+    fn expired(&self) -> bool {
+        now_secs().saturating_sub(self.updated) > ROOM_EXPIRY.as_secs()
+    }
+}
+
+pub trait RoomStore: Send + Sync {
+    fn save(&self, key: Uuid, room: &StoredRoom) -> Result<(), String>;
+    fn remove(&self, key: Uuid) -> Result<(), String>;
+    fn load_all(&self) -> Result<Vec<(Uuid, StoredRoom)>, String>;
+    // Writes saved rooms to disk now rather than waiting, such as when a game ends
+    fn flush(&self) {}
+}
+
+pub type SharedStore = Arc<dyn RoomStore>;
+
+/// Keeps rooms for as long as the server runs, for when nothing should be written to disk
+#[derive(Default)]
+pub struct MemoryStore {
+    rooms: Mutex<HashMap<Uuid, StoredRoom>>,
+}
+
+impl RoomStore for MemoryStore {
+    fn save(&self, key: Uuid, room: &StoredRoom) -> Result<(), String> {
+        let mut rooms = self.rooms.lock().map_err(|err| err.to_string())?;
+        rooms.insert(key, room.clone());
+        Ok(())
+    }
+    fn remove(&self, key: Uuid) -> Result<(), String> {
+        let mut rooms = self.rooms.lock().map_err(|err| err.to_string())?;
+        rooms.remove(&key);
+        Ok(())
+    }
+    fn load_all(&self) -> Result<Vec<(Uuid, StoredRoom)>, String> {
+        let rooms = self.rooms.lock().map_err(|err| err.to_string())?;
+        Ok(rooms
+            .iter()
+            .map(|(key, room)| (*key, room.clone()))
+            .collect())
+    }
+}
+
+/// Rooms in an embedded sled database, keyed by room key and encoded as CBOR
+pub struct SledStore {
+    db: sled::Db,
+}
+
+impl SledStore {
+    pub fn open(path: &str) -> Result<SledStore, String> {
+        let db = sled::Config::default()
+            .path(path)
+            .flush_every_ms(Some(FLUSH_EVERY_MS))
+            .open()
+            .map_err(|err| err.to_string())?;
+        Ok(SledStore { db })
+    }
+}
+
+impl RoomStore for SledStore {
+    fn save(&self, key: Uuid, room: &StoredRoom) -> Result<(), String> {
+        let value = serde_cbor::to_vec(room).map_err(|err| err.to_string())?;
+        self.db
+            .insert(key.as_bytes(), value)
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+    fn remove(&self, key: Uuid) -> Result<(), String> {
+        self.db
+            .remove(key.as_bytes())
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+    fn load_all(&self) -> Result<Vec<(Uuid, StoredRoom)>, String> {
+        let mut rooms = vec![];
+        for entry in self.db.iter() {
+            let (key, value) = entry.map_err(|err| err.to_string())?;
+            let key = match Uuid::from_slice(&key) {
+                Ok(key) => key,
+                Err(err) => {
+                    warn!("Skipping stored room with invalid key: {}", err);
+                    continue;
+                }
+            };
+            // Rooms saved by an incompatible build are dropped rather than stopping the server
+            match serde_cbor::from_slice(&value) {
+                Ok(room) => rooms.push((key, room)),
+                Err(err) => {
+                    warn!("Skipping unreadable stored room {}: {}", key, err);
+                    self.remove(key)?;
+                }
+            };
+        }
+        Ok(rooms)
+    }
+    // Flushes in the background so the room's actor isn't kept waiting on the disk
+    fn flush(&self) {
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            if let Err(err) = db.flush_async().await {
+                error!("Failed to flush room store: {}", err);
+            }
+        });
+    }
+}
+
+/// Opens the store at ROOM_STORE_PATH, or keeps rooms in memory when it's set to "memory"
+pub fn open_store() -> SharedStore {
+    let path = env::var("ROOM_STORE_PATH").unwrap_or_else(|_| "./rooms.db".to_string());
+    if path == "memory" {
+        info!("Keeping rooms in memory only");
+        return Arc::new(MemoryStore::default());
+    }
+    match SledStore::open(&path) {
+        Ok(store) => {
+            info!("Storing rooms in {}", path);
+            Arc::new(store)
+        }
+        Err(err) => {
+            error!(
+                "Failed to open room store at {}, keeping rooms in memory: {}",
+                path, err
+            );
+            Arc::new(MemoryStore::default())
+        }
+    }
+}
+
+// Stored rooms worth restoring, dropping any that have expired
+pub fn restore_rooms(store: &SharedStore) -> Vec<(Uuid, StoredRoom)> {
+    let rooms = match store.load_all() {
+        Ok(rooms) => rooms,
+        Err(err) => {
+            error!("Failed to load stored rooms: {}", err);
+            return vec![];
+        }
+    };
+    let mut restored = vec![];
+    for (key, room) in rooms {
+        if room.expired() {
+            if let Err(err) = store.remove(key) {
+                warn!("Failed to remove expired room {}: {}", key, err);
+            }
+            continue;
+        }
+        restored.push((key, room));
+    }
+    restored
+}
@@ -0,0 +1,7 @@
+mod test_bots;
+mod test_chat;
+mod test_lobby;
+mod test_offers;
+mod test_rooms;
+mod test_store;
+mod utils;
@@ -0,0 +1,138 @@
+use std::env;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::test::{call_service, init_service, TestRequest};
+use actix_web::{web, App};
+use uuid::Uuid;
+
+use onitamalib::{BotMessage, GameMessage};
+
+use crate::bots::bot_authorized;
+use crate::rooms::RoomRequest;
+use crate::routes::{join_room, ServerData};
+use crate::tests::utils::{bot_socket, room_socket, seated, start_server};
+
+const BOT: &str = "test-bot";
+const SECRET: &str = "correct-horse";
+
+// Every test sets the same accounts, so it doesn't matter which runs first
+fn set_accounts() {
+    env::set_var(
+        "BOT_ACCOUNTS",
+        format!("{}:{},other-bot:other", BOT, SECRET),
+    );
+}
+
+#[test]
+fn secrets_are_checked() {
+    set_accounts();
+    assert!(bot_authorized(BOT, SECRET));
+    assert!(!bot_authorized(BOT, "correct-horsf"));
+    assert!(!bot_authorized(BOT, "correct-hors"));
+    assert!(!bot_authorized(BOT, &format!("{}e", SECRET)));
+    assert!(!bot_authorized(BOT, ""));
+    assert!(!bot_authorized(BOT, "other"));
+    assert!(!bot_authorized("unknown-bot", SECRET));
+}
+
+#[actix_web::test]
+async fn bot_room_joins_need_the_secret() {
+    set_accounts();
+    let data = ServerData {
+        logger: slog::Logger::root(slog::Discard, slog::o!()),
+        server_addr: start_server(),
+        #[cfg(feature = "agent")]
+        book: None,
+    };
+    let app = App::new()
+        .app_data(web::Data::new(data))
+        .route("/ws/{key}", web::get().to(join_room));
+    let app = init_service(app).await;
+    let key = Uuid::new_v4();
+    let join = |query: &str, secret: Option<&str>| {
+        let request = TestRequest::get()
+            .uri(&format!("/ws/{}?{}", key, query))
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="));
+        match secret {
+            Some(secret) => {
+                let bearer = format!("Bearer {}", secret);
+                request.insert_header((header::AUTHORIZATION, bearer))
+            }
+            None => request,
+        }
+        .to_request()
+    };
+    let bot = format!("format=json&bot={}", BOT);
+    let requests = vec![
+        (join("format=json", Some(SECRET)), StatusCode::FORBIDDEN),
+        (join(&bot, None), StatusCode::FORBIDDEN),
+        (join(&bot, Some("other")), StatusCode::FORBIDDEN),
+        (join(&bot, Some(SECRET)), StatusCode::SWITCHING_PROTOCOLS),
+        // Players in the app don't sign in
+        (join("", None), StatusCode::SWITCHING_PROTOCOLS),
+    ];
+    for (request, status) in requests {
+        let response = call_service(&app, request).await;
+        assert_eq!(response.status(), status);
+    }
+}
+
+#[actix_web::test]
+async fn challenges_keep_a_seat_for_the_bot() {
+    let server = start_server();
+    let mut bot = bot_socket(&server, BOT);
+    bot.receive_until(|msg| matches!(msg, BotMessage::Registered { .. }))
+        .await;
+    let request = RoomRequest::Create {
+        settings: Default::default(),
+        public: false,
+        bot: Some(BOT.to_string()),
+    };
+    let (player, _) = seated(room_socket(&server, request)).await;
+    let room_key = match bot.receive().await {
+        BotMessage::Challenge { room_id, .. } => Uuid::parse_str(&room_id).unwrap(),
+        msg => panic!("Expected a challenge, got {:?}", msg),
+    };
+    // Anyone else only gets to watch
+    for name in [None, Some("other-bot")] {
+        let request = RoomRequest::Join {
+            room_key,
+            token: None,
+            bot: name.map(str::to_string),
+        };
+        let mut socket = room_socket(&server, request);
+        match socket.receive().await {
+            GameMessage::Spectate { .. } => {}
+            msg => panic!("Expected to spectate, got {:?}", msg),
+        };
+    }
+    let request = RoomRequest::Join {
+        room_key,
+        token: None,
+        bot: Some(BOT.to_string()),
+    };
+    let (mut seat, _) = seated(room_socket(&server, request)).await;
+    assert_eq!(seat.player, player.player.invert());
+    // Only the bot can take its seat back with the token
+    seat.socket.drop_connection();
+    let request = RoomRequest::Join {
+        room_key,
+        token: Some(seat.token),
+        bot: None,
+    };
+    let mut socket = room_socket(&server, request);
+    match socket.receive().await {
+        GameMessage::Spectate { .. } => {}
+        msg => panic!("Expected to spectate, got {:?}", msg),
+    };
+    let request = RoomRequest::Join {
+        room_key,
+        token: Some(seat.token),
+        bot: Some(BOT.to_string()),
+    };
+    let (rejoined, _) = seated(room_socket(&server, request)).await;
+    assert_eq!(rejoined.player, seat.player);
+}
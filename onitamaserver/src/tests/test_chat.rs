@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use onitamalib::{ChatContent, GameMessage, Player, MAX_CHAT_LENGTH};
+
+use crate::rooms::{within_chat_limit, CHAT_BURST, CHAT_WINDOW};
+use crate::tests::utils::{start_game, start_server, TestSocket};
+
+fn send_chat(socket: &TestSocket<GameMessage>, text: &str) {
+    let content = ChatContent::Text {
+        text: text.to_string(),
+    };
+    socket.send(&GameMessage::SendChat { content });
+}
+
+fn is_chat(msg: &GameMessage) -> bool {
+    matches!(msg, GameMessage::Chat { .. })
+}
+
+fn is_rejected(msg: &GameMessage) -> bool {
+    matches!(msg, GameMessage::ChatRejected { .. })
+}
+
+#[test]
+fn chat_is_limited_within_a_window() {
+    let start = Instant::now();
+    let mut sent = VecDeque::new();
+    for sent_at in 0..CHAT_BURST {
+        let now = start + Duration::from_secs(sent_at as u64);
+        assert!(within_chat_limit(&mut sent, now));
+    }
+    let last = start + Duration::from_secs(CHAT_BURST as u64 - 1);
+    assert!(!within_chat_limit(&mut sent, last));
+    // The first message only stops counting once it's more than a window old
+    assert!(!within_chat_limit(&mut sent, start + CHAT_WINDOW));
+    let later = start + CHAT_WINDOW + Duration::from_millis(1);
+    assert!(within_chat_limit(&mut sent, later));
+    // Refused messages don't count, so there's still exactly one free
+    assert!(!within_chat_limit(&mut sent, later));
+    assert_eq!(sent.len(), CHAT_BURST);
+}
+
+#[actix_web::test]
+async fn chat_is_relayed_to_both_players() {
+    let server = start_server();
+    let (mut red, mut blue, _) = start_game(&server).await;
+    send_chat(&red.socket, "  good luck  ");
+    for socket in [&mut red.socket, &mut blue.socket] {
+        match socket.receive_until(is_chat).await {
+            GameMessage::Chat { message } => {
+                assert_eq!(message.from, Player::Red);
+                let text = "good luck".to_string();
+                assert_eq!(message.content, ChatContent::Text { text });
+            }
+            _ => unreachable!(),
+        };
+    }
+}
+
+#[actix_web::test]
+async fn invalid_chat_is_rejected() {
+    let server = start_server();
+    let (mut red, mut blue, _) = start_game(&server).await;
+    let longest = "a".repeat(MAX_CHAT_LENGTH);
+    let too_long = format!("{}!", longest);
+    for text in [too_long.as_str(), "line\nbreak", "\u{1b}[2J"] {
+        send_chat(&red.socket, text);
+        red.socket.receive_until(is_rejected).await;
+    }
+    send_chat(&red.socket, &longest);
+    // The opponent only ever hears of the one that was allowed
+    match blue.socket.receive_until(is_chat).await {
+        GameMessage::Chat { message } => {
+            assert_eq!(message.content, ChatContent::Text { text: longest });
+        }
+        _ => unreachable!(),
+    };
+    blue.socket.assert_quiet().await;
+}
+
+#[actix_web::test]
+async fn fast_chat_is_rejected() {
+    let server = start_server();
+    let (mut red, mut blue, _) = start_game(&server).await;
+    for _ in 0..CHAT_BURST {
+        send_chat(&red.socket, "hi");
+        red.socket.receive_until(is_chat).await;
+    }
+    send_chat(&red.socket, "hi");
+    red.socket.receive_until(is_rejected).await;
+    // Each player has their own limit
+    send_chat(&blue.socket, "hello");
+    let from_blue = |msg: &GameMessage| match msg {
+        GameMessage::Chat { message } => message.from == Player::Blue,
+        msg => is_rejected(msg),
+    };
+    match blue.socket.receive_until(from_blue).await {
+        GameMessage::Chat { .. } => {}
+        msg => panic!("Expected blue's chat, got {:?}", msg),
+    };
+}
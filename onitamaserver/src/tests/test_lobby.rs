@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use tokio::time;
+use uuid::Uuid;
+
+use onitamalib::{CardSet, GameMessage, LobbyMessage, PublicRoom, RoomSettings};
+
+use crate::lobby::Lobby;
+use crate::rooms::RoomRequest;
+use crate::tests::utils::{lobby_socket, room_socket, start_server};
+
+fn base_only() -> RoomSettings {
+    RoomSettings {
+        card_sets: vec![CardSet::Base],
+        ..Default::default()
+    }
+}
+
+// The rooms listed by a Rooms message
+fn listed(msg: LobbyMessage) -> Vec<PublicRoom> {
+    match msg {
+        LobbyMessage::Rooms { rooms } => rooms,
+        msg => panic!("Expected rooms, got {:?}", msg),
+    }
+}
+
+#[actix_web::test]
+async fn compatible_seeks_are_paired() {
+    let server = start_server();
+    let (first, _) = lobby_socket(&server);
+    let (second, _) = lobby_socket(&server);
+    let (third, _) = lobby_socket(&server);
+    let mut lobby = Lobby::default();
+    assert!(lobby.seek(first.clone(), base_only()).is_none());
+    assert!(lobby.seek(second.clone(), Default::default()).is_none());
+    assert!(lobby.seek(third.clone(), base_only()) == Some(first));
+    // Both players are out of the queue once they're paired
+    assert!(lobby.seek(third, Default::default()) == Some(second));
+}
+
+#[actix_web::test]
+async fn own_seek_is_ignored() {
+    let server = start_server();
+    let (first, _) = lobby_socket(&server);
+    let (second, _) = lobby_socket(&server);
+    let mut lobby = Lobby::default();
+    assert!(lobby.seek(first.clone(), base_only()).is_none());
+    assert!(lobby.seek(first.clone(), base_only()).is_none());
+    // Seeking again replaces the earlier seek rather than queueing twice
+    assert!(lobby.seek(first.clone(), Default::default()).is_none());
+    assert!(lobby.seek(second.clone(), base_only()).is_none());
+    assert!(lobby.seek(second, Default::default()) == Some(first));
+}
+
+#[actix_web::test]
+async fn cancelled_seeks_are_not_paired() {
+    let server = start_server();
+    let (first, _) = lobby_socket(&server);
+    let (second, _) = lobby_socket(&server);
+    let (third, _) = lobby_socket(&server);
+    let mut lobby = Lobby::default();
+    assert!(lobby.seek(first.clone(), base_only()).is_none());
+    lobby.cancel_seek(&first);
+    assert!(lobby.seek(second.clone(), base_only()).is_none());
+    // Leaving the lobby cancels too
+    lobby.leave(&second);
+    assert!(lobby.seek(third, base_only()).is_none());
+}
+
+#[actix_web::test]
+async fn lobby_hears_of_listing_changes() {
+    let server = start_server();
+    let (addr, mut socket) = lobby_socket(&server);
+    // Told of the server's own, empty, lobby on connecting
+    assert!(listed(socket.receive().await).is_empty());
+    let mut lobby = Lobby::default();
+    lobby.join(addr);
+    assert!(listed(socket.receive().await).is_empty());
+    let key = Uuid::new_v4();
+    let listing = PublicRoom {
+        room_id: key.to_string(),
+        settings: Default::default(),
+        created: 0,
+    };
+    lobby.update_room(key, Some(listing.clone()));
+    assert_eq!(listed(socket.receive().await), vec![listing.clone()]);
+    // Nothing changed, so nothing is sent
+    lobby.update_room(key, Some(listing));
+    lobby.update_room(Uuid::new_v4(), None);
+    socket.assert_quiet().await;
+    lobby.update_room(key, None);
+    assert!(listed(socket.receive().await).is_empty());
+}
+
+#[actix_web::test]
+async fn listings_are_removed_when_rooms_fill() {
+    let server = start_server();
+    let (_, mut lobby) = lobby_socket(&server);
+    assert!(listed(lobby.receive().await).is_empty());
+    let request = RoomRequest::Create {
+        settings: Default::default(),
+        public: true,
+        bot: None,
+    };
+    let _creator = room_socket(&server, request);
+    let rooms = listed(lobby.receive().await);
+    assert_eq!(rooms.len(), 1);
+    let room_key = Uuid::parse_str(&rooms[0].room_id).unwrap();
+    let request = RoomRequest::Join {
+        room_key,
+        token: None,
+        bot: None,
+    };
+    let mut joiner = room_socket(&server, request);
+    joiner
+        .receive_until(|msg| matches!(msg, GameMessage::Initialize { .. }))
+        .await;
+    assert!(listed(lobby.receive().await).is_empty());
+}
+
+#[actix_web::test]
+async fn listings_are_removed_when_rooms_close() {
+    time::pause();
+    let server = start_server();
+    let (_, mut lobby) = lobby_socket(&server);
+    assert!(listed(lobby.receive().await).is_empty());
+    let request = RoomRequest::Create {
+        settings: Default::default(),
+        public: true,
+        bot: None,
+    };
+    let mut creator = room_socket(&server, request);
+    creator
+        .receive_until(|msg| matches!(msg, GameMessage::Initialize { .. }))
+        .await;
+    assert_eq!(listed(lobby.receive().await).len(), 1);
+    // Still listed while the creator's seat is held for them
+    creator.drop_connection();
+    time::sleep(Duration::from_secs(120)).await;
+    assert!(listed(lobby.receive().await).is_empty());
+}
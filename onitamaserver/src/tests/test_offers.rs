@@ -0,0 +1,94 @@
+use onitamalib::{GameMessage, Offer};
+
+use crate::tests::utils::{by_turn, first_move, play, start_game, start_server};
+
+fn is_offer(msg: &GameMessage) -> bool {
+    matches!(msg, GameMessage::Offer { .. })
+}
+
+#[actix_web::test]
+async fn repeated_offers_are_sent_once() {
+    let server = start_server();
+    let (red, mut blue, _) = start_game(&server).await;
+    let offer = Offer::Draw;
+    red.socket.send(&GameMessage::Offer { offer });
+    red.socket.send(&GameMessage::Offer { offer });
+    blue.socket.receive_until(is_offer).await;
+    blue.socket.assert_quiet().await;
+}
+
+#[actix_web::test]
+async fn offers_lapse_with_a_move() {
+    let server = start_server();
+    let (red, blue, _) = start_game(&server).await;
+    let (mut mover, mut waiting) = by_turn(red, blue);
+    let offer = Offer::Draw;
+    mover.socket.send(&GameMessage::Offer { offer });
+    waiting.socket.receive_until(is_offer).await;
+    play(&mover, first_move(mover.state));
+    waiting
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Move { .. }))
+        .await;
+    // Too late, the game carries on
+    waiting.socket.send(&GameMessage::Accept { offer });
+    mover.socket.assert_quiet().await;
+    waiting.socket.assert_quiet().await;
+}
+
+#[actix_web::test]
+async fn takebacks_need_a_move_to_undo() {
+    let server = start_server();
+    let (red, blue, _) = start_game(&server).await;
+    let (mut mover, mut waiting) = by_turn(red, blue);
+    let offer = Offer::Takeback;
+    // Neither player has moved yet
+    mover.socket.send(&GameMessage::Offer { offer });
+    waiting.socket.send(&GameMessage::Offer { offer });
+    mover.socket.assert_quiet().await;
+    waiting.socket.assert_quiet().await;
+    play(&mover, first_move(mover.state));
+    waiting
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Move { .. }))
+        .await;
+    // The waiting player would have to undo two moves, only one has been played
+    waiting.socket.send(&GameMessage::Offer { offer });
+    mover.socket.assert_quiet().await;
+    mover.socket.send(&GameMessage::Offer { offer });
+    waiting.socket.receive_until(is_offer).await;
+    waiting.socket.send(&GameMessage::Accept { offer });
+    match mover
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::TakenBack { .. }))
+        .await
+    {
+        GameMessage::TakenBack { state, .. } => assert_eq!(state, mover.state),
+        _ => unreachable!(),
+    };
+}
+
+#[actix_web::test]
+async fn finished_games_cant_be_taken_back() {
+    let server = start_server();
+    let (red, blue, _) = start_game(&server).await;
+    let (mut mover, mut waiting) = by_turn(red, blue);
+    play(&mover, first_move(mover.state));
+    waiting
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Move { .. }))
+        .await;
+    waiting.socket.send(&GameMessage::Resign);
+    mover
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Resigned { .. }))
+        .await;
+    mover.socket.send(&GameMessage::Offer {
+        offer: Offer::Takeback,
+    });
+    waiting
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Resigned { .. }))
+        .await;
+    waiting.socket.assert_quiet().await;
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use tokio::time;
+
+use onitamalib::{GameMessage, GameState, Move, Player};
+
+use crate::rooms::RoomRequest;
+use crate::tests::utils::{
+    by_turn, first_move, play, room_socket, seated, start_game, start_server, watch,
+};
+
+#[actix_web::test]
+async fn dropped_players_can_reclaim_their_seat() {
+    let server = start_server();
+    let (mut red, mut blue, room_key) = start_game(&server).await;
+    red.socket.drop_connection();
+    blue.socket
+        .receive_until(|msg| matches!(msg, GameMessage::Reconnecting))
+        .await;
+    let request = RoomRequest::Join {
+        room_key,
+        token: Some(red.token),
+        bot: None,
+    };
+    let (rejoined, _) = seated(room_socket(&server, request)).await;
+    assert_eq!(rejoined.player, Player::Red);
+    assert_eq!(rejoined.token, red.token);
+    blue.socket
+        .receive_until(|msg| matches!(msg, GameMessage::Joined))
+        .await;
+}
+
+#[actix_web::test]
+async fn dropped_spectators_stop_watching() {
+    let server = start_server();
+    let (mut red, _blue, room_key) = start_game(&server).await;
+    let mut spectator = watch(&server, room_key).await;
+    red.socket
+        .receive_until(|msg| matches!(msg, GameMessage::Watchers { count: 1 }))
+        .await;
+    spectator.drop_connection();
+    red.socket
+        .receive_until(|msg| matches!(msg, GameMessage::Watchers { count: 0 }))
+        .await;
+}
+
+#[actix_web::test]
+async fn spectators_dont_keep_rooms_open() {
+    time::pause();
+    let server = start_server();
+    let (mut red, mut blue, room_key) = start_game(&server).await;
+    let mut spectator = watch(&server, room_key).await;
+    red.socket.drop_connection();
+    blue.socket.drop_connection();
+    // Past both seats' grace period and the delay before an empty room closes
+    time::sleep(Duration::from_secs(120)).await;
+    spectator
+        .receive_until(|msg| matches!(msg, GameMessage::Error { .. }))
+        .await;
+    let request = RoomRequest::Join {
+        room_key,
+        token: Some(red.token),
+        bot: None,
+    };
+    let mut rejoined = room_socket(&server, request);
+    match rejoined.receive().await {
+        GameMessage::Error { .. } => {}
+        msg => panic!("Expected the room to be closed, got {:?}", msg),
+    };
+}
+
+#[actix_web::test]
+async fn bad_moves_are_answered_with_errors() {
+    let server = start_server();
+    let (red, blue, _) = start_game(&server).await;
+    let (mut mover, mut waiting) = by_turn(red, blue);
+    let is_error = |msg: &GameMessage| matches!(msg, GameMessage::Error { .. });
+    let game_move = first_move(mover.state);
+    play(&waiting, game_move);
+    waiting.socket.receive_until(is_error).await;
+    let spare_card = match mover.state {
+        GameState::Playing { board } => board.spare_card,
+        GameState::Finished { .. } => unreachable!(),
+    };
+    play(&mover, Move::Discard { card: spare_card });
+    mover.socket.receive_until(is_error).await;
+    waiting.socket.send(&GameMessage::Resign);
+    mover
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Resigned { .. }))
+        .await;
+    play(&mover, game_move);
+    mover.socket.receive_until(is_error).await;
+}
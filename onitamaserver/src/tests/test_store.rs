@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use onitamalib::history::PositionHistory;
+use onitamalib::{GameState, Player};
+
+use crate::store::{now_secs, restore_rooms, MemoryStore, SharedStore, StoredRoom};
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn stored_room(created: u64, updated: u64) -> StoredRoom {
+    let game_state = GameState::new();
+    let game_move = match game_state {
+        GameState::Playing { board } => board.legal_moves()[0],
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    };
+    let mut history = PositionHistory::new(&game_state, None);
+    let next = history.record(game_state.try_move(game_move).unwrap());
+    StoredRoom {
+        game_state: next,
+        moves: vec![game_move],
+        positions: vec![game_state],
+        history,
+        requested_rematch: Some(Player::Blue),
+        settings: Default::default(),
+        public: true,
+        clock: None,
+        tokens: Some((Uuid::new_v4(), Uuid::new_v4())),
+        invited: Some("test-bot".to_string()),
+        bots: vec![(Player::Red, "test-bot".to_string())]
+            .into_iter()
+            .collect(),
+        chat: vec![],
+        created,
+        updated,
+    }
+}
+
+#[test]
+fn rooms_survive_saving() {
+    let store: SharedStore = Arc::new(MemoryStore::default());
+    let key = Uuid::new_v4();
+    let now = now_secs();
+    let room = stored_room(now - DAY, now);
+    store.save(key, &room).unwrap();
+    let restored = restore_rooms(&store);
+    assert_eq!(restored.len(), 1);
+    let (restored_key, restored) = &restored[0];
+    assert_eq!(*restored_key, key);
+    assert_eq!(restored.game_state, room.game_state);
+    assert_eq!(restored.moves, room.moves);
+    assert_eq!(restored.positions, room.positions);
+    assert_eq!(restored.requested_rematch, room.requested_rematch);
+    assert_eq!(restored.tokens, room.tokens);
+    assert_eq!(restored.invited, room.invited);
+    assert_eq!(restored.bots, room.bots);
+    assert_eq!(restored.public, room.public);
+    assert_eq!(
+        (restored.created, restored.updated),
+        (room.created, room.updated)
+    );
+    // Removed rooms stay gone
+    store.remove(key).unwrap();
+    assert!(restore_rooms(&store).is_empty());
+}
+
+#[test]
+fn expired_rooms_are_dropped() {
+    let store: SharedStore = Arc::new(MemoryStore::default());
+    let now = now_secs();
+    // Expiry goes by the last save however long ago the room was created,
+    // and rooms saved before their creation time was kept still restore
+    let (legacy, active, stale) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    store
+        .save(active, &stored_room(now - 30 * DAY, now - DAY))
+        .unwrap();
+    store
+        .save(stale, &stored_room(now - 30 * DAY, now - 8 * DAY))
+        .unwrap();
+    store.save(legacy, &stored_room(0, now)).unwrap();
+    let mut restored: Vec<Uuid> = restore_rooms(&store)
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    restored.sort();
+    let mut expected = vec![legacy, active];
+    expected.sort();
+    assert_eq!(restored, expected);
+    // Expired rooms are removed from the store, not just skipped
+    assert_eq!(store.load_all().unwrap().len(), 2);
+}
@@ -0,0 +1,254 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::{Actor, Addr, StreamHandler};
+use actix_web::error::PayloadError;
+use actix_web::web::Bytes;
+use actix_web_actors::ws;
+use futures::channel::mpsc as futures_mpsc;
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::time;
+use uuid::Uuid;
+
+use onitamalib::{BotMessage, GameMessage, GameState, LobbyMessage, Move, Player};
+
+use crate::bots::BotWs;
+use crate::lobby::LobbyWs;
+use crate::rooms::{OnitamaServer, RoomRequest, RoomWs};
+use crate::store::MemoryStore;
+
+// How long to wait for a message the test expects
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// A websocket client talking straight to an actor, without a real connection,
+// receiving JSON messages of type M
+pub struct TestSocket<M> {
+    input: Option<futures_mpsc::UnboundedSender<Result<Bytes, PayloadError>>>,
+    output: mpsc::UnboundedReceiver<String>,
+    message: PhantomData<M>,
+}
+
+impl<M: DeserializeOwned> TestSocket<M> {
+    pub fn connect<A>(actor: A) -> (Addr<A>, TestSocket<M>)
+    where
+        A: Actor<Context = ws::WebsocketContext<A>>
+            + StreamHandler<Result<ws::Message, ws::ProtocolError>>,
+    {
+        let (input, incoming) = futures_mpsc::unbounded();
+        let (sender, output) = mpsc::unbounded_channel();
+        let (addr, frames) = ws::WebsocketContext::create_with_addr(actor, incoming);
+        let mut frames = Box::pin(frames);
+        actix::spawn(async move {
+            let mut buffer = vec![];
+            while let Some(Ok(bytes)) = frames.next().await {
+                buffer.extend_from_slice(&bytes);
+                while let Some((text, used)) = read_frame(&buffer) {
+                    buffer.drain(..used);
+                    if let Some(text) = text {
+                        let _ = sender.send(text);
+                    }
+                }
+            }
+        });
+        let socket = TestSocket {
+            input: Some(input),
+            output,
+            message: PhantomData,
+        };
+        (addr, socket)
+    }
+    pub fn send<T: Serialize>(&self, msg: &T) {
+        let text = serde_json::to_string(msg).unwrap();
+        if let Some(input) = &self.input {
+            let _ = input.unbounded_send(Ok(Bytes::from(text_frame(&text))));
+        }
+    }
+    // The next message sent to the client
+    pub async fn receive(&mut self) -> M {
+        let text = time::timeout(RECEIVE_TIMEOUT, self.output.recv())
+            .await
+            .expect("Timed out waiting for a message")
+            .expect("Socket closed");
+        serde_json::from_str(&text).unwrap()
+    }
+    // Skips messages until one matches
+    pub async fn receive_until<F: Fn(&M) -> bool>(&mut self, matches: F) -> M {
+        loop {
+            let msg = self.receive().await;
+            if matches(&msg) {
+                return msg;
+            }
+        }
+    }
+    // Nothing more is sent for a little while
+    pub async fn assert_quiet(&mut self) {
+        let received = time::timeout(Duration::from_millis(200), self.output.recv()).await;
+        if let Ok(Some(text)) = received {
+            panic!("Unexpected message: {}", text);
+        }
+    }
+    // Ends the connection the way a dropped network would, without a close frame
+    pub fn drop_connection(&mut self) {
+        self.input = None;
+    }
+}
+
+// Client frames are always masked, a zero key leaves the payload as it is
+fn text_frame(text: &str) -> Vec<u8> {
+    let payload = text.as_bytes();
+    let mut frame = vec![0x81];
+    match payload.len() {
+        len if len < 126 => frame.push(0x80 | len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    };
+    frame.extend_from_slice(&[0, 0, 0, 0]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// Reads an unmasked server frame, giving the text it carried and how many bytes it took
+fn read_frame(buffer: &[u8]) -> Option<(Option<String>, usize)> {
+    if buffer.len() < 2 {
+        return None;
+    }
+    let opcode = buffer[0] & 0x0f;
+    let (len, start) = match buffer[1] & 0x7f {
+        126 if buffer.len() >= 4 => (u16::from_be_bytes([buffer[2], buffer[3]]) as usize, 4),
+        127 if buffer.len() >= 10 => {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&buffer[2..10]);
+            (u64::from_be_bytes(bytes) as usize, 10)
+        }
+        126 | 127 => {
+            return None;
+        }
+        len => (len as usize, 2),
+    };
+    if buffer.len() < start + len {
+        return None;
+    }
+    let payload = &buffer[start..start + len];
+    let text = match opcode {
+        0x1 => Some(String::from_utf8(payload.to_vec()).unwrap()),
+        _ => None,
+    };
+    Some((text, start + len))
+}
+
+pub fn start_server() -> Addr<OnitamaServer> {
+    OnitamaServer::new(Arc::new(MemoryStore::default())).start()
+}
+
+// A bot style socket, talking JSON, making the given request of the server
+pub fn room_socket(server: &Addr<OnitamaServer>, request: RoomRequest) -> TestSocket<GameMessage> {
+    let actor = RoomWs::new(server.clone(), request, "test".to_string(), true);
+    TestSocket::connect(actor).1
+}
+
+pub fn bot_socket(server: &Addr<OnitamaServer>, name: &str) -> TestSocket<BotMessage> {
+    let actor = BotWs::new(server.clone(), name.to_string(), "test".to_string());
+    TestSocket::connect(actor).1
+}
+
+pub fn lobby_socket(server: &Addr<OnitamaServer>) -> (Addr<LobbyWs>, TestSocket<LobbyMessage>) {
+    TestSocket::connect(LobbyWs::new(server.clone(), "test".to_string()))
+}
+
+pub struct Seat {
+    pub socket: TestSocket<GameMessage>,
+    pub player: Player,
+    pub token: Uuid,
+    pub state: GameState,
+}
+
+// Waits for the socket to be seated, giving its seat
+pub async fn seated(mut socket: TestSocket<GameMessage>) -> (Seat, Uuid) {
+    match socket.receive().await {
+        GameMessage::Initialize {
+            player,
+            token,
+            room_id,
+            state,
+            ..
+        } => {
+            let token = Uuid::parse_str(&token.unwrap()).unwrap();
+            let room_key = Uuid::parse_str(&room_id).unwrap();
+            (
+                Seat {
+                    socket,
+                    player,
+                    token,
+                    state,
+                },
+                room_key,
+            )
+        }
+        msg => panic!("Expected to be seated, got {:?}", msg),
+    }
+}
+
+// A room with both players in it, giving the seats red first
+pub async fn start_game(server: &Addr<OnitamaServer>) -> (Seat, Seat, Uuid) {
+    let request = RoomRequest::Create {
+        settings: Default::default(),
+        public: false,
+        bot: None,
+    };
+    let (mut creator, room_key) = seated(room_socket(server, request)).await;
+    let request = RoomRequest::Join {
+        room_key,
+        token: None,
+        bot: None,
+    };
+    let (joiner, _) = seated(room_socket(server, request)).await;
+    creator
+        .socket
+        .receive_until(|msg| matches!(msg, GameMessage::Joined))
+        .await;
+    match creator.player {
+        Player::Red => (creator, joiner, room_key),
+        Player::Blue => (joiner, creator, room_key),
+    }
+}
+
+// Watches the room, waiting until the spectator is in
+pub async fn watch(server: &Addr<OnitamaServer>, room_key: Uuid) -> TestSocket<GameMessage> {
+    let mut socket = room_socket(server, RoomRequest::Watch { room_key });
+    socket
+        .receive_until(|msg| matches!(msg, GameMessage::Spectate { .. }))
+        .await;
+    socket
+}
+
+pub fn first_move(state: GameState) -> Move {
+    match state {
+        GameState::Playing { board } => board.legal_moves()[0],
+        GameState::Finished { .. } => panic!("Unexpected finished state"),
+    }
+}
+
+pub fn play(seat: &Seat, game_move: Move) {
+    seat.socket.send(&GameMessage::Move {
+        game_move,
+        clock: None,
+    });
+}
+
+// The players as the one to move and their opponent
+pub fn by_turn(red: Seat, blue: Seat) -> (Seat, Seat) {
+    match red.state {
+        GameState::Playing { board } if board.turn == blue.player => (blue, red),
+        _ => (red, blue),
+    }
+}
@@ -0,0 +1,58 @@
+import React, { useEffect, useState } from 'react';
+import { Box, Button, Typography } from '@material-ui/core';
+import { Link } from 'react-router-dom';
+import useStyles from './menuStyles';
+import GithubRibbon from './GithubRibbon';
+import { BOTS_URL } from './config';
+import logger from './logger';
+
+// External engines connected to the server, each can be challenged to a game
+function Bots() {
+  const classes = useStyles();
+  const [bots, setBots] = useState(null);
+  useEffect(() => {
+    let mounted = true;
+    fetch(BOTS_URL)
+      .then((resp) => resp.json())
+      .then((names) => mounted && setBots(names))
+      .catch((err) => {
+        logger.log('Failed to list bots', err);
+        if (mounted) setBots([]);
+      });
+    return () => {
+      mounted = false;
+    };
+  }, []);
+  return (
+    <Box className={classes.outer}>
+      <Typography variant="h2">Bots</Typography>
+      {bots && bots.length === 0 && <Typography>No bots are online</Typography>}
+      {bots &&
+        bots.map((name) => (
+          <Button
+            key={name}
+            component={Link}
+            to={`/vs/${encodeURIComponent(name)}`}
+            variant="contained"
+            color="primary"
+            className={classes.button}
+          >
+            {name}
+          </Button>
+        ))}
+      <Box m={1} />
+      <Button
+        component={Link}
+        to="/online"
+        variant="outlined"
+        color="secondary"
+        className={classes.button}
+      >
+        Back
+      </Button>
+      <GithubRibbon />
+    </Box>
+  );
+}
+
+export default Bots;
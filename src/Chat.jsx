@@ -0,0 +1,148 @@
+import React, { useEffect, useMemo, useState } from 'react';
+import PropTypes from 'prop-types';
+import {
+  Badge,
+  Box,
+  Button,
+  Drawer,
+  IconButton,
+  makeStyles,
+  TextField,
+  Typography,
+} from '@material-ui/core';
+import ChatIcon from '@material-ui/icons/Chat';
+import { listEmotes } from './onitamalib';
+
+const useStyles = makeStyles((theme) => ({
+  toggle: {
+    position: 'fixed',
+    bottom: theme.spacing(1),
+    right: theme.spacing(1),
+  },
+  drawer: {
+    width: '320px',
+    maxWidth: '100vw',
+    height: '100%',
+    display: 'flex',
+    flexDirection: 'column',
+    padding: theme.spacing(1),
+  },
+  messages: {
+    flexGrow: 1,
+    overflowY: 'auto',
+  },
+  emotes: {
+    display: 'flex',
+    flexWrap: 'wrap',
+    gap: theme.spacing(0.5),
+  },
+}));
+
+// Sending only preset messages, and hiding typed ones, is chosen in settings
+const emotesOnlyEnabled = () => localStorage.getItem('chat_emotes_only') === 'true';
+
+function Chat({ chat, player, spectating, sendChat }) {
+  const classes = useStyles();
+  const [open, setOpen] = useState(false);
+  const [text, setText] = useState('');
+  const [seen, setSeen] = useState(0);
+  const emotes = useMemo(() => listEmotes(), []);
+  const emotesOnly = useMemo(emotesOnlyEnabled, []);
+  const messages = emotesOnly ? chat.filter(({ content }) => content.type === 'Emote') : chat;
+  useEffect(() => {
+    if (open) {
+      setSeen(messages.length);
+    }
+  }, [open, messages.length]);
+  const sender = (from) => {
+    if (spectating) {
+      return from;
+    }
+    return from === player ? 'You' : 'Opponent';
+  };
+  const describe = (content) =>
+    content.type === 'Emote'
+      ? emotes.find(({ id }) => id === content.emote)?.text || content.emote
+      : content.text;
+  const submit = (e) => {
+    e.preventDefault();
+    sendChat({ type: 'Text', text });
+    setText('');
+  };
+  return (
+    <>
+      <IconButton className={classes.toggle} onClick={() => setOpen(true)} aria-label="Chat">
+        <Badge badgeContent={messages.length - seen} color="secondary">
+          <ChatIcon />
+        </Badge>
+      </IconButton>
+      <Drawer anchor="right" open={open} onClose={() => setOpen(false)}>
+        <Box className={classes.drawer}>
+          <Typography variant="h6">Chat</Typography>
+          <Box className={classes.messages}>
+            {messages.length === 0 && <Typography variant="body2">No messages yet</Typography>}
+            {messages.map(({ from, content }, idx) => (
+              // Messages are only ever added to the end, so the index is stable
+              // eslint-disable-next-line react/no-array-index-key
+              <Typography key={idx} variant="body2">
+                <b>{`${sender(from)}: `}</b>
+                {describe(content)}
+              </Typography>
+            ))}
+          </Box>
+          {!spectating && (
+            <>
+              <Box className={classes.emotes}>
+                {emotes.map(({ id, text: emoteText }) => (
+                  <Button
+                    key={id}
+                    size="small"
+                    variant="outlined"
+                    onClick={() => sendChat({ type: 'Emote', emote: id })}
+                  >
+                    {emoteText}
+                  </Button>
+                ))}
+              </Box>
+              {!emotesOnly && (
+                <form onSubmit={submit}>
+                  <TextField
+                    value={text}
+                    onChange={(e) => setText(e.target.value)}
+                    placeholder="Send a message"
+                    inputProps={{ maxLength: 200 }}
+                    fullWidth
+                    margin="dense"
+                    variant="outlined"
+                  />
+                </form>
+              )}
+            </>
+          )}
+        </Box>
+      </Drawer>
+    </>
+  );
+}
+Chat.defaultProps = {
+  chat: [],
+  player: null,
+  spectating: false,
+};
+Chat.propTypes = {
+  chat: PropTypes.arrayOf(
+    PropTypes.shape({
+      from: PropTypes.string.isRequired,
+      content: PropTypes.shape({
+        type: PropTypes.string.isRequired,
+        text: PropTypes.string,
+        emote: PropTypes.string,
+      }).isRequired,
+    }),
+  ),
+  player: PropTypes.string,
+  spectating: PropTypes.bool,
+  sendChat: PropTypes.func.isRequired,
+};
+
+export default Chat;
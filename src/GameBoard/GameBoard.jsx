@@ -1,13 +1,14 @@
 import React, { useEffect, useState } from 'react';
 import PropTypes from 'prop-types';
 import { Link } from 'react-router-dom';
-import { Box, Button, useMediaQuery, useTheme } from '@material-ui/core';
+import { Box, Button, Typography, useMediaQuery, useTheme } from '@material-ui/core';
 import UndoIcon from '@material-ui/icons/Undo';
 import GameOver from './GameOver';
 import GameCard from './GameCard';
 import GameGrid from './GameGrid';
 import GameHand from './GameHand';
 import GameTurn from './GameTurn';
+import GameClock from './GameClock';
 import { CardPropType, PointPropType } from './props';
 import GameScore from './GameScore';
 
@@ -16,6 +17,8 @@ function GameBoard({
   setSrc,
   grid,
   winner,
+  ending,
+  finished,
   player,
   turn,
   spare,
@@ -33,19 +36,27 @@ function GameBoard({
   reset,
   canUndo,
   undo,
+  resign,
+  offerDraw,
+  requestTakeback,
+  offerSent,
   score,
   stale,
+  spectating,
+  watchers,
+  clock,
+  clockSynced,
 }) {
   const theme = useTheme();
   const [minimizedGameOver, setMinimizedGameOver] = useState(false);
   useEffect(() => {
-    if (!winner) {
+    if (!finished) {
       setMinimizedGameOver(false);
     }
-  }, [winner, setMinimizedGameOver]);
+  }, [finished, setMinimizedGameOver]);
   const hideSideSpare = useMediaQuery(theme.breakpoints.down('sm'));
-  // Whether it's the player's turn, always true if local multiplayer
-  const playerTurn = player ? player === turn : true;
+  // Whether it's the player's turn, always true if local multiplayer and never when spectating
+  const playerTurn = !spectating && (player ? player === turn : true);
   // Whether perspective should have red at bottom of screen
   const redOriented = player !== 'Blue';
   return (
@@ -53,6 +64,17 @@ function GameBoard({
       <Box display="flex" justifyContent="center">
         <GameTurn player={player} turn={turn} />
       </Box>
+      <GameClock
+        clock={clock}
+        clockSynced={clockSynced}
+        turn={turn}
+        running={!finished && connectionStatus !== 'Waiting'}
+      />
+      {watchers > 0 && (
+        <Box display="flex" justifyContent="center">
+          <Typography variant="caption">{`${watchers} watching`}</Typography>
+        </Box>
+      )}
       <Box display="flex" flexDirection={redOriented ? 'row' : 'row-reverse'}>
         <Box position="absolute" top="0" left="0">
           <Button component={Link} to="/">
@@ -62,7 +84,10 @@ function GameBoard({
         <GameOver
           reset={reset}
           winner={winner}
+          ending={ending}
+          finished={finished}
           player={player}
+          spectating={spectating}
           connectionStatus={connectionStatus}
           minimizedGameOver={minimizedGameOver}
           setMinimizedGameOver={setMinimizedGameOver}
@@ -155,7 +180,22 @@ function GameBoard({
           </Box>
         </Box>
       )}
-      {minimizedGameOver && Boolean(winner) && (
+      {resign && !finished && !spectating && (
+        <Box width="100%" display="flex" justifyContent="center" py={2}>
+          <Box display="flex" width="100%" maxWidth="320px" justifyContent="space-between">
+            <Button variant="outlined" onClick={resign}>
+              Resign
+            </Button>
+            <Button variant="outlined" disabled={!!offerSent} onClick={offerDraw}>
+              Offer draw
+            </Button>
+            <Button variant="outlined" disabled={!!offerSent} onClick={requestTakeback}>
+              Takeback
+            </Button>
+          </Box>
+        </Box>
+      )}
+      {minimizedGameOver && finished && !spectating && (
         <Box
           p={1}
           display="flex"
@@ -178,6 +218,8 @@ GameBoard.defaultProps = {
   card: null,
   src: null,
   winner: null,
+  ending: null,
+  finished: false,
   reset: null,
   player: null,
   lastMove: null,
@@ -185,14 +227,24 @@ GameBoard.defaultProps = {
   connectionStatus: null,
   canUndo: null,
   undo: null,
+  resign: null,
+  offerDraw: null,
+  requestTakeback: null,
+  offerSent: null,
   score: null,
   stale: true,
+  spectating: false,
+  watchers: 0,
+  clock: null,
+  clockSynced: 0,
 };
 GameBoard.propTypes = {
   src: PointPropType,
   setSrc: PropTypes.func.isRequired,
   grid: PropTypes.arrayOf(PropTypes.arrayOf(PropTypes.string).isRequired).isRequired,
   winner: PropTypes.oneOf(['Red', 'Blue', null]),
+  ending: PropTypes.string,
+  finished: PropTypes.bool,
   reset: PropTypes.func,
   turn: PropTypes.oneOf(['Red', 'Blue']).isRequired,
   player: PropTypes.oneOf(['Red', 'Blue', null]),
@@ -213,8 +265,19 @@ GameBoard.propTypes = {
   discard: PropTypes.func.isRequired,
   canUndo: PropTypes.bool,
   undo: PropTypes.func,
+  resign: PropTypes.func,
+  offerDraw: PropTypes.func,
+  requestTakeback: PropTypes.func,
+  offerSent: PropTypes.oneOf(['Draw', 'Takeback', null]),
   score: PropTypes.number,
   stale: PropTypes.bool,
+  spectating: PropTypes.bool,
+  watchers: PropTypes.number,
+  clock: PropTypes.shape({
+    red: PropTypes.number.isRequired,
+    blue: PropTypes.number.isRequired,
+  }),
+  clockSynced: PropTypes.number,
 };
 
 export default GameBoard;
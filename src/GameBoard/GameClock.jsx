@@ -0,0 +1,70 @@
+import React, { useEffect, useState } from 'react';
+import PropTypes from 'prop-types';
+import { Box, makeStyles, Typography } from '@material-ui/core';
+import clsx from 'clsx';
+
+const useStyles = makeStyles(() => ({
+  Red: {
+    color: '#f44336',
+  },
+  Blue: {
+    color: '#2196f3',
+  },
+  idle: {
+    opacity: 0.5,
+  },
+}));
+const formatTime = (millis) => {
+  const seconds = Math.ceil(millis / 1000);
+  const minutes = Math.floor(seconds / 60);
+  return `${minutes}:${String(seconds % 60).padStart(2, '0')}`;
+};
+function GameClock({ clock, clockSynced, turn, running }) {
+  const classes = useStyles();
+  const [now, setNow] = useState(Date.now());
+  useEffect(() => {
+    if (!running) {
+      return undefined;
+    }
+    const interval = setInterval(() => setNow(Date.now()), 200);
+    return () => clearInterval(interval);
+  }, [running]);
+  if (!clock) {
+    return null;
+  }
+  // The server sends the time left at its last update, count down the player to move since then
+  const elapsed = running ? Math.max(now - clockSynced, 0) : 0;
+  const remaining = (player) => {
+    const left = player === 'Red' ? clock.red : clock.blue;
+    return player === turn ? Math.max(left - elapsed, 0) : left;
+  };
+  return (
+    <Box display="flex" justifyContent="center">
+      {['Red', 'Blue'].map((player) => (
+        <Box key={player} px={2}>
+          <Typography
+            variant="h5"
+            className={clsx(classes[player], { [classes.idle]: !running || player !== turn })}
+          >
+            {formatTime(remaining(player))}
+          </Typography>
+        </Box>
+      ))}
+    </Box>
+  );
+}
+GameClock.defaultProps = {
+  clock: null,
+  clockSynced: 0,
+};
+GameClock.propTypes = {
+  clock: PropTypes.shape({
+    red: PropTypes.number.isRequired,
+    blue: PropTypes.number.isRequired,
+  }),
+  clockSynced: PropTypes.number,
+  turn: PropTypes.oneOf(['Red', 'Blue']).isRequired,
+  running: PropTypes.bool.isRequired,
+};
+
+export default GameClock;
@@ -36,10 +36,22 @@ const captionsFromStatus = {
   OpponentRematchRequested: 'Opponent requested a rematch',
   RematchRequested: 'Rematch request sent',
 };
+const endingText = {
+  KingCaptured: 'Master captured',
+  TempleReached: 'Temple reached',
+  Repetition: 'Draw by repetition',
+  MoveLimit: 'Move limit reached',
+  Timeout: 'Out of time',
+  Resignation: 'By resignation',
+  DrawAgreed: 'Draw agreed',
+};
 function GameOver({
   winner,
+  ending,
+  finished,
   reset,
   player,
+  spectating,
   connectionStatus,
   minimizedGameOver,
   setMinimizedGameOver,
@@ -47,10 +59,12 @@ function GameOver({
   const classes = useStyles();
   const relativeText = player === winner ? 'You Win!' : 'You Lose!';
   const absoluteText = `${winner} wins!`;
-  const text = player ? relativeText : absoluteText;
-  const caption = captionsFromStatus[connectionStatus];
+  const winnerText = player ? relativeText : absoluteText;
+  // Finished without a winner is a draw
+  const text = winner ? winnerText : 'Draw!';
+  const caption = spectating ? null : captionsFromStatus[connectionStatus];
   return (
-    <Dialog open={Boolean(winner) && !minimizedGameOver} classes={{ paper: classes.dialog }}>
+    <Dialog open={finished && !minimizedGameOver} classes={{ paper: classes.dialog }}>
       <Box position="absolute" top="0px" right="0px">
         <IconButton onClick={() => setMinimizedGameOver(true)}>
           <Close />
@@ -64,10 +78,13 @@ function GameOver({
       >
         {text}
       </DialogTitle>
+      {ending && <Typography variant="subtitle2">{endingText[ending]}</Typography>}
       <DialogActions>
-        <Button variant="contained" onClick={reset} color="primary">
-          Rematch
-        </Button>
+        {!spectating && (
+          <Button variant="contained" onClick={reset} color="primary">
+            Rematch
+          </Button>
+        )}
         <Button variant="outlined" color="secondary" component={Link} to="/">
           Main Menu
         </Button>
@@ -78,12 +95,17 @@ function GameOver({
 }
 GameOver.defaultProps = {
   winner: null,
+  ending: null,
   player: null,
+  spectating: false,
   connectionStatus: null,
 };
 GameOver.propTypes = {
   winner: PropTypes.oneOf(['Red', 'Blue', null]),
+  ending: PropTypes.string,
+  finished: PropTypes.bool.isRequired,
   player: PropTypes.oneOf(['Red', 'Blue', null]),
+  spectating: PropTypes.bool,
   connectionStatus: PropTypes.string,
   reset: PropTypes.func.isRequired,
   minimizedGameOver: PropTypes.bool.isRequired,
@@ -44,7 +44,7 @@ function Home() {
       </Button>
       <Button
         component={Link}
-        to="/r/"
+        to="/online"
         variant="contained"
         color="primary"
         className={classes.button}
@@ -0,0 +1,158 @@
+import React, { useEffect, useState } from 'react';
+import { Box, Button, Typography } from '@material-ui/core';
+import { Link } from 'react-router-dom';
+import { useHistory } from 'react-router';
+import { useSnackbar } from 'notistack';
+import useStyles from './menuStyles';
+import GithubRibbon from './GithubRibbon';
+import { WEBSOCKET_BASE } from './config';
+import { timeControls } from './OnlineSelect';
+import { describeCards, describeTime, enabledCardSets } from './roomSettings';
+import logger from './logger';
+
+// Converts "fischer:180:2" style time controls into what the server sends and expects
+const parseTime = (time) => {
+  if (!time) {
+    return null;
+  }
+  const [kind, ...seconds] = time.split(':');
+  const millis = seconds.map((part) => Number(part) * 1000);
+  switch (kind) {
+    case 'sudden':
+      return { type: 'suddenDeath', total: millis[0] };
+    case 'fischer':
+      return { type: 'fischer', initial: millis[0], increment: millis[1] };
+    case 'move':
+      return { type: 'perMove', per_move: millis[0] };
+    default:
+      return null;
+  }
+};
+
+function Lobby() {
+  const classes = useStyles();
+  const history = useHistory();
+  const { enqueueSnackbar } = useSnackbar();
+  const [rooms, setRooms] = useState([]);
+  const [seeking, setSeeking] = useState(null);
+  const [send, setSend] = useState(null);
+  useEffect(() => {
+    const sock = new WebSocket(`${WEBSOCKET_BASE}lobby`);
+    const keepAlive = setInterval(() => {
+      sock.send('ping');
+    }, 30000);
+    const onMessage = (e) => {
+      if (e.data === 'pong') {
+        return;
+      }
+      const msg = JSON.parse(e.data);
+      logger.log('Lobby message', msg);
+      switch (msg.type) {
+        case 'Rooms':
+          setRooms(msg.rooms);
+          break;
+        case 'Matched':
+          history.push(`/r/${msg.roomId}`);
+          break;
+        case 'Error':
+          setSeeking(null);
+          enqueueSnackbar(msg.message, { variant: 'error', persist: false });
+          break;
+        default:
+          break;
+      }
+    };
+    sock.addEventListener('message', onMessage);
+    sock.addEventListener('open', () => {
+      setSend(() => (msg) => sock.send(JSON.stringify(msg)));
+    });
+    sock.addEventListener('close', () => {
+      setSend(null);
+      setSeeking(null);
+    });
+    return () => {
+      clearInterval(keepAlive);
+      sock.close(1000);
+    };
+  }, [history, enqueueSnackbar]);
+  const seek = (label, time) => {
+    const settings = { cardSets: enabledCardSets(), timeControl: parseTime(time) };
+    send({ type: 'Seek', settings });
+    setSeeking(label);
+  };
+  const cancelSeek = () => {
+    send({ type: 'CancelSeek' });
+    setSeeking(null);
+  };
+  return (
+    <Box className={classes.outer}>
+      <Typography variant="h2">Lobby</Typography>
+      <Typography variant="h5">Open Games</Typography>
+      {rooms.length === 0 && <Typography>No games waiting for an opponent</Typography>}
+      {rooms.map(({ roomId, settings }) => (
+        <Button
+          key={roomId}
+          component={Link}
+          to={`/r/${roomId}`}
+          variant="outlined"
+          color="primary"
+          className={classes.button}
+        >
+          {`${describeTime(settings.timeControl)}, ${describeCards(settings)}`}
+        </Button>
+      ))}
+      <Typography variant="h5">Find Opponent</Typography>
+      {seeking ? (
+        <>
+          <Typography>{`Looking for an opponent (${seeking})`}</Typography>
+          <Button
+            onClick={cancelSeek}
+            variant="contained"
+            color="secondary"
+            className={classes.button}
+          >
+            Cancel
+          </Button>
+        </>
+      ) : (
+        timeControls.map(({ label, time }) => (
+          <Button
+            key={label}
+            onClick={() => seek(label, time)}
+            disabled={!send}
+            variant="contained"
+            color="primary"
+            className={classes.button}
+          >
+            {label}
+          </Button>
+        ))
+      )}
+      <Typography variant="h5">Host Open Game</Typography>
+      {timeControls.map(({ label, time }) => (
+        <Button
+          key={label}
+          component={Link}
+          to={time ? `/r/?public=true&time=${time}` : '/r/?public=true'}
+          variant="outlined"
+          color="primary"
+          className={classes.button}
+        >
+          {label}
+        </Button>
+      ))}
+      <Button
+        component={Link}
+        to="/online"
+        variant="outlined"
+        color="secondary"
+        className={classes.button}
+      >
+        Back
+      </Button>
+      <GithubRibbon />
+    </Box>
+  );
+}
+
+export default Lobby;
@@ -62,7 +62,7 @@ function LocalGame() {
   if (!state) {
     return <Loading />;
   }
-  const { blueCards, redCards, spare, turn, grid, canMove, winner } = state;
+  const { blueCards, redCards, spare, turn, grid, canMove, winner, status } = state;
   const isMoveValid = getMoves(src, card, turn);
   return (
     <GameBoard
@@ -77,6 +77,7 @@ function LocalGame() {
       canMove={canMove}
       reset={reset}
       winner={winner}
+      finished={status === 'Finished'}
       spare={spare}
       turn={turn}
       move={move}
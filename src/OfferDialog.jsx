@@ -0,0 +1,34 @@
+import React from 'react';
+import PropTypes from 'prop-types';
+import { Button, Dialog, DialogActions, DialogTitle } from '@material-ui/core';
+
+const offerText = {
+  Draw: 'Opponent offers a draw',
+  Takeback: 'Opponent asks to take back their last move',
+};
+
+function OfferDialog({ offer, accept, decline }) {
+  return (
+    <Dialog open={!!offer} onClose={decline}>
+      <DialogTitle>{offer && offerText[offer]}</DialogTitle>
+      <DialogActions>
+        <Button variant="contained" color="primary" onClick={accept}>
+          Accept
+        </Button>
+        <Button variant="outlined" onClick={decline}>
+          Decline
+        </Button>
+      </DialogActions>
+    </Dialog>
+  );
+}
+OfferDialog.defaultProps = {
+  offer: null,
+};
+OfferDialog.propTypes = {
+  offer: PropTypes.oneOf(['Draw', 'Takeback', null]),
+  accept: PropTypes.func.isRequired,
+  decline: PropTypes.func.isRequired,
+};
+
+export default OfferDialog;
@@ -0,0 +1,66 @@
+import React from 'react';
+import { Box, Button, Typography } from '@material-ui/core';
+import { Link } from 'react-router-dom';
+import useStyles from './menuStyles';
+import GithubRibbon from './GithubRibbon';
+
+// Time controls offered when creating a room, in the server's notation
+export const timeControls = [
+  { label: 'Untimed', time: null },
+  { label: '5 minutes', time: 'sudden:300' },
+  { label: '3 minutes + 2 seconds', time: 'fischer:180:2' },
+  { label: '30 seconds per move', time: 'move:30' },
+];
+
+function OnlineSelect() {
+  const classes = useStyles();
+  return (
+    <Box className={classes.outer}>
+      <Typography variant="h2">Online Multiplayer</Typography>
+      <Box m={1} />
+      {timeControls.map(({ label, time }) => (
+        <Button
+          key={label}
+          component={Link}
+          to={time ? `/r/?time=${time}` : '/r/'}
+          variant="contained"
+          color="primary"
+          className={classes.button}
+        >
+          {label}
+        </Button>
+      ))}
+      <Box m={1} />
+      <Button
+        component={Link}
+        to="/lobby"
+        variant="contained"
+        color="secondary"
+        className={classes.button}
+      >
+        Lobby
+      </Button>
+      <Button
+        component={Link}
+        to="/bots"
+        variant="contained"
+        color="secondary"
+        className={classes.button}
+      >
+        Bots
+      </Button>
+      <Button
+        component={Link}
+        to="/"
+        variant="outlined"
+        color="secondary"
+        className={classes.button}
+      >
+        Back
+      </Button>
+      <GithubRibbon />
+    </Box>
+  );
+}
+
+export default OnlineSelect;
@@ -1,11 +1,13 @@
 import React, { useCallback, useState } from 'react';
 import PropTypes from 'prop-types';
 import { useSnackbar } from 'notistack';
-import { useParams } from 'react-router';
+import { useLocation, useParams } from 'react-router';
 import Loading from './Loading';
 import GameBoard from './GameBoard';
 import useMultiplayer from './hooks/useMultiplayer';
 import WaitingOverlay from './WaitingOverlay';
+import Chat from './Chat';
+import OfferDialog from './OfferDialog';
 
 const getMoves = (src, card, turn) => {
   if (!src || !card) {
@@ -20,10 +22,25 @@ const getMoves = (src, card, turn) => {
   return (x, y) => dstSet.has(`${x},${y}`);
 };
 
-function RemoteGame({ isAi }) {
-  const { roomId = null } = useParams();
+function RemoteGame({ isAi, spectate }) {
+  const { roomId = null, botName = null } = useParams();
+  // Time control for a new room, and whether to list it in the lobby
+  const query = new URLSearchParams(useLocation().search);
+  const time = query.get('time');
+  const isPublic = query.get('public') === 'true';
   const { enqueueSnackbar } = useSnackbar();
-  const { playMove, state, reset, reconnect } = useMultiplayer(roomId, isAi);
+  const {
+    playMove,
+    state,
+    reset,
+    reconnect,
+    sendChat,
+    resign,
+    offerDraw,
+    requestTakeback,
+    acceptOffer,
+    declineOffer,
+  } = useMultiplayer(roomId, isAi, spectate, time, isPublic, botName);
   const [card, setCard] = useState(null);
   const [src, setSrc] = useState(null);
   const move = useCallback(
@@ -67,8 +84,27 @@ function RemoteGame({ isAi }) {
     return <Loading />;
   }
   // Host always creates game
-  const { blueCards, redCards, spare, turn, grid, canMove, winner, player, lastMove, connection } =
-    state;
+  const {
+    blueCards,
+    redCards,
+    spare,
+    turn,
+    grid,
+    canMove,
+    winner,
+    ending,
+    status,
+    player,
+    lastMove,
+    connection,
+    spectating,
+    watchers,
+    clock,
+    clockSynced,
+    chat,
+    offerSent,
+    offerReceived,
+  } = state;
   const isMoveValid = getMoves(src, card, turn);
   return (
     <>
@@ -85,21 +121,39 @@ function RemoteGame({ isAi }) {
         canMove={canMove}
         reset={reset}
         winner={winner}
+        ending={ending}
+        finished={status === 'Finished'}
         spare={spare}
         turn={turn}
-        player={player}
+        player={spectating ? null : player}
+        spectating={spectating}
+        watchers={watchers}
+        clock={clock}
+        clockSynced={clockSynced}
         move={move}
         discard={discard}
         lastMove={lastMove}
         connectionStatus={connection}
+        resign={isAi ? null : resign}
+        offerDraw={offerDraw}
+        requestTakeback={requestTakeback}
+        offerSent={offerSent}
       />
+      {!isAi && acceptOffer && (
+        <OfferDialog offer={offerReceived} accept={acceptOffer} decline={declineOffer} />
+      )}
+      {!isAi && sendChat && (
+        <Chat chat={chat} player={player} spectating={spectating} sendChat={sendChat} />
+      )}
     </>
   );
 }
 RemoteGame.defaultProps = {
   isAi: false,
+  spectate: false,
 };
 RemoteGame.propTypes = {
   isAi: PropTypes.bool,
+  spectate: PropTypes.bool,
 };
 export default RemoteGame;
@@ -5,8 +5,10 @@ import {
   Card,
   CardContent,
   CardHeader,
+  FormControlLabel,
   IconButton,
   makeStyles,
+  Switch,
   Typography,
   useMediaQuery,
   useTheme,
@@ -50,6 +52,17 @@ function Settings() {
       localStorage.setItem('disabled_card_sets', JSON.stringify(newDisabledIds));
     }
   };
+  const [emotesOnly, setEmotesOnly] = useState(
+    () => localStorage.getItem('chat_emotes_only') === 'true',
+  );
+  const toggleEmotesOnly = () => {
+    if (emotesOnly) {
+      localStorage.removeItem('chat_emotes_only');
+    } else {
+      localStorage.setItem('chat_emotes_only', 'true');
+    }
+    setEmotesOnly(!emotesOnly);
+  };
   const enabledCardCount = useMemo(() => {
     const enabledSets = cardSets.filter(({ id }) => !disabledCardSetIds.includes(id));
     return enabledSets.reduce((accumulator, set) => accumulator + set.cards.length, 0);
@@ -62,8 +75,8 @@ function Settings() {
           <Box my={2} />
           <Typography variant="h5">Card Sets</Typography>
           <Typography variant="body1">
-            Turn sets of cards on or off (currently only works for Single Player and Local
-            Multiplayer)
+            Turn sets of cards on or off, online games use them when you create a room or look for
+            an opponent
           </Typography>
           {enabledCardCount < 5 && (
             <Alert severity="error">
@@ -104,6 +117,15 @@ function Settings() {
               </Card>
             </Box>
           ))}
+          <Box my={2} />
+          <Typography variant="h5">Chat</Typography>
+          <Typography variant="body1">
+            Only send and show preset messages in online games, hiding anything typed
+          </Typography>
+          <FormControlLabel
+            control={<Switch checked={emotesOnly} onChange={toggleEmotesOnly} color="primary" />}
+            label="Preset messages only"
+          />
           <Box display="flex" mt={3}>
             <Button variant="outlined" color="secondary" component={Link} to="/">
               Back to Menu
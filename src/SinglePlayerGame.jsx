@@ -64,7 +64,8 @@ function SinglePlayerGame() {
   if (!state) {
     return <Loading />;
   }
-  const { blueCards, redCards, spare, turn, grid, canMove, winner, player, lastMove } = state;
+  const { blueCards, redCards, spare, turn, grid, canMove, winner, status, player, lastMove } =
+    state;
   const isMoveValid = getMoves(src, card, turn);
   return (
     <GameBoard
@@ -79,6 +80,7 @@ function SinglePlayerGame() {
       canMove={canMove}
       reset={reset}
       winner={winner}
+      finished={status === 'Finished'}
       spare={spare}
       turn={turn}
       move={move}
@@ -64,8 +64,19 @@ function TrainingGame() {
   if (!state) {
     return <Loading />;
   }
-  const { blueCards, redCards, spare, turn, grid, canMove, winner, player, lastMove, canUndo } =
-    state;
+  const {
+    blueCards,
+    redCards,
+    spare,
+    turn,
+    grid,
+    canMove,
+    winner,
+    status,
+    player,
+    lastMove,
+    canUndo,
+  } = state;
   const isMoveValid = getMoves(src, card, turn);
   const { max, min, ranksByCardSrc, stale } = moveRankings;
   const dstMoveRankings =
@@ -94,6 +105,7 @@ function TrainingGame() {
       canMove={canMove}
       reset={reset}
       winner={winner}
+      finished={status === 'Finished'}
       spare={spare}
       turn={turn}
       move={move}
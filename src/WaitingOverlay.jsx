@@ -12,6 +12,7 @@ import {
   TextField,
   Typography,
 } from '@material-ui/core';
+import { describeCards } from './roomSettings';
 
 const useStyles = makeStyles((theme) => ({
   dialog: {
@@ -32,20 +33,36 @@ const useStyles = makeStyles((theme) => ({
 }));
 const titleFromStatus = {
   Waiting: 'Waiting for Opponent',
+  OpponentReconnecting: 'Opponent lost connection, holding their seat while they reconnect',
   OpponentDisconnected: 'Opponent disconnected, waiting for them to re-connect',
   Disconnected: 'Disconnected from server',
   Errored: 'Game Error',
 };
-function WaitingOverlay({ state: { connection, roomId, error }, reconnect }) {
+const spectatorTitleFromStatus = {
+  OpponentReconnecting: 'A player lost connection, waiting for them to reconnect',
+  OpponentDisconnected: 'A player disconnected, waiting for them to re-connect',
+};
+function WaitingOverlay({
+  state: { connection, roomId, error, spectating, settings },
+  reconnect,
+}) {
   const classes = useStyles();
   const ref = useRef();
-  const title = titleFromStatus[connection] || connection;
+  const spectatorTitle = spectating && spectatorTitleFromStatus[connection];
+  const title = spectatorTitle || titleFromStatus[connection] || connection;
   const isWaiting = connection === 'Waiting';
   const isConnecting = connection === 'Connecting';
+  const isOpponentReconnecting = connection === 'OpponentReconnecting';
   const isOpponentDisconnected = connection === 'OpponentDisconnected';
   const isDisconnected = connection === 'Disconnected';
   const isErrored = connection === 'Errored';
-  const open = isWaiting || isConnecting || isDisconnected || isOpponentDisconnected || isErrored;
+  const open =
+    isWaiting ||
+    isConnecting ||
+    isDisconnected ||
+    isOpponentReconnecting ||
+    isOpponentDisconnected ||
+    isErrored;
   const showReconnect = isDisconnected || isErrored;
   const copyLink = useCallback(() => {
     ref.current.select();
@@ -56,7 +73,7 @@ function WaitingOverlay({ state: { connection, roomId, error }, reconnect }) {
     <Dialog open={open} classes={{ paper: classes.dialog }} transitionDuration={0}>
       <DialogTitle>{title}</DialogTitle>
       <DialogContent className={classes.content}>
-        {!isConnecting && !showReconnect && roomId && (
+        {!isConnecting && !showReconnect && !isOpponentReconnecting && !spectating && roomId && (
           <>
             <Box minWidth="280px">
               <Typography variant="subtitle1">Invite Link</Typography>
@@ -79,6 +96,9 @@ function WaitingOverlay({ state: { connection, roomId, error }, reconnect }) {
             </Box>
           </>
         )}
+        {isWaiting && settings && (
+          <Typography variant="body2">{`Dealing from: ${describeCards(settings)}`}</Typography>
+        )}
         {error && <Typography variant="body1">{error}</Typography>}
       </DialogContent>
       <DialogActions>
@@ -99,6 +119,11 @@ WaitingOverlay.propTypes = {
     connection: PropTypes.string.isRequired,
     roomId: PropTypes.string,
     error: PropTypes.string,
+    spectating: PropTypes.bool,
+    settings: PropTypes.shape({
+      cardSets: PropTypes.arrayOf(PropTypes.string).isRequired,
+      cards: PropTypes.arrayOf(PropTypes.string),
+    }),
   }).isRequired,
   reconnect: PropTypes.func.isRequired,
 };
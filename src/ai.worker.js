@@ -2,6 +2,7 @@ const onitamaLib = import('./onitamalib');
 
 onmessage = async (e) => {
   const { agentMove } = await onitamaLib;
-  const result = agentMove(e.data);
+  const onProgress = (info, summary) => postMessage({ progress: info, summary });
+  const result = agentMove(e.data, onProgress);
   postMessage(result);
 };
@@ -2,13 +2,17 @@ import { useState, useEffect, useCallback } from 'react';
 import { useSnackbar } from 'notistack';
 import { useHistory } from 'react-router';
 import { WEBSOCKET_BASE } from '../config';
+import { enabledCardSets } from '../roomSettings';
 import { MultiplayerGame } from '../onitamalib';
 import logger from '../logger';
 import onEvent from '../events';
 import getMeta from '../meta';
 import { useAppUpdater } from '../updateManager';
 
-const useMultiplayer = (roomId, isAi) => {
+// Session tokens let a player get their seat back after reloading or losing connection
+const tokenKey = (roomId) => `room_token_${roomId}`;
+
+const useMultiplayer = (roomId, isAi, spectate, time, isPublic, botName) => {
   const checkUpdate = useAppUpdater();
   const [state, setState] = useState(null);
   const [handlers, setHandlers] = useState({});
@@ -28,7 +32,27 @@ const useMultiplayer = (roomId, isAi) => {
       enqueueSnackbar(err, { variant: 'error', persist: false });
       checkUpdate();
     };
-    const roomUrl = `${WEBSOCKET_BASE}${isAi ? 'ai/' : ''}${roomId || ''}`;
+    const prefix =
+      (isAi && 'ai/') ||
+      (spectate && 'watch/') ||
+      (botName && `vs/${encodeURIComponent(botName)}`) ||
+      '';
+    const params = new URLSearchParams();
+    if (!roomId && time) {
+      params.set('time', time);
+    }
+    if (!roomId && isPublic) {
+      params.set('public', 'true');
+    }
+    if (!roomId && !isAi) {
+      params.set('sets', enabledCardSets().join(','));
+    }
+    const token = roomId && !isAi && !spectate && localStorage.getItem(tokenKey(roomId));
+    if (token) {
+      params.set('token', token);
+    }
+    const query = params.toString() ? `?${params}` : '';
+    const roomUrl = `${WEBSOCKET_BASE}${prefix}${roomId || ''}${query}`;
     const sock = new WebSocket(roomUrl);
     const keepAlive = setInterval(() => {
       sock.send('ping');
@@ -48,6 +72,12 @@ const useMultiplayer = (roomId, isAi) => {
     setHandlers({
       playMove: (m) => game.move(m),
       reset: () => game.reset(),
+      sendChat: (content) => game.sendChat(content),
+      resign: () => game.resign(),
+      offerDraw: () => game.offerDraw(),
+      requestTakeback: () => game.requestTakeback(),
+      acceptOffer: () => game.acceptOffer(),
+      declineOffer: () => game.declineOffer(),
     });
     const onClose = () => {
       logger.log('Disconnected');
@@ -60,8 +90,14 @@ const useMultiplayer = (roomId, isAi) => {
       mounted = false;
       sock.close(1000);
     };
-  }, [enqueueSnackbar, roomId, history, reconnectVal, isAi]);
+  }, [enqueueSnackbar, roomId, history, reconnectVal, isAi, spectate, time, isPublic, botName]);
   const stateRoomId = state?.roomId;
+  const stateToken = state?.token;
+  useEffect(() => {
+    if (stateRoomId && stateToken) {
+      localStorage.setItem(tokenKey(stateRoomId), stateToken);
+    }
+  }, [stateRoomId, stateToken]);
   useEffect(() => {
     if (stateRoomId && !roomId) {
       history.replace(`/r/${stateRoomId}`);
@@ -41,7 +41,14 @@ const useSingleplayer = (difficulty, trainingMode) => {
       requestMoveRanking,
       onEvent,
     );
-    worker.onmessage = (m) => game.move(m.data, false);
+    worker.onmessage = (m) => {
+      // The agent reports each depth it searches before sending its move
+      if (m.data.progress) {
+        logger.log(m.data.summary);
+      } else {
+        game.move(m.data, false);
+      }
+    };
     if (trainer) {
       trainer.onmessage = (m) => {
         const ranksByCardSrc = {};
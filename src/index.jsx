@@ -10,6 +10,9 @@ import theme from './theme';
 import Loading from './Loading';
 import Home from './Home';
 import AiSelect from './AiSelect';
+import OnlineSelect from './OnlineSelect';
+import Lobby from './Lobby';
+import Bots from './Bots';
 import TrainingSelect from './TrainingSelect';
 import { UpdateManager } from './updateManager';
 
@@ -43,11 +46,21 @@ root.render(
                 <Settings />
               </Suspense>
             </Route>
+            <Route path="/w/:roomId">
+              <Suspense fallback={<Loading />}>
+                <RemoteGame spectate />
+              </Suspense>
+            </Route>
             <Route path={['/r/:roomId', '/r/']}>
               <Suspense fallback={<Loading />}>
                 <RemoteGame />
               </Suspense>
             </Route>
+            <Route path="/vs/:botName">
+              <Suspense fallback={<Loading />}>
+                <RemoteGame />
+              </Suspense>
+            </Route>
             {process.env.REACT_APP_LOCAL_AI ? (
               <Route path="/ai/:difficulty">
                 <Suspense fallback={<Loading />}>
@@ -64,6 +77,15 @@ root.render(
             <Route path="/ai">
               <AiSelect />
             </Route>
+            <Route path="/online">
+              <OnlineSelect />
+            </Route>
+            <Route path="/lobby">
+              <Lobby />
+            </Route>
+            <Route path="/bots">
+              <Bots />
+            </Route>
             {process.env.REACT_APP_LOCAL_AI && (
               <Route path="/t/:difficulty">
                 <Suspense fallback={<Loading />}>
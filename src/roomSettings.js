@@ -0,0 +1,41 @@
+import { listCardSets } from './onitamalib';
+
+// Time controls come from the server in milliseconds
+export const describeTime = (timeControl) => {
+  if (!timeControl) {
+    return 'Untimed';
+  }
+  switch (timeControl.type) {
+    case 'suddenDeath':
+      return `${timeControl.total / 60000} minutes`;
+    case 'fischer':
+      return `${timeControl.initial / 60000} minutes + ${timeControl.increment / 1000} seconds`;
+    case 'perMove':
+      return `${timeControl.per_move / 1000} seconds per move`;
+    default:
+      return 'Unknown time control';
+  }
+};
+
+// Which cards a room deals from, explicit cards take priority over card sets
+export const describeCards = ({ cardSets, cards }) => {
+  if (cards && cards.length) {
+    return cards.join(', ');
+  }
+  if (!cardSets.length) {
+    return 'All cards';
+  }
+  return listCardSets()
+    .filter(({ id }) => cardSets.includes(id))
+    .map(({ name }) => name)
+    .join(', ');
+};
+
+// Card sets the player hasn't disabled in settings, used for new rooms and matchmaking
+export const enabledCardSets = () => {
+  const disabledCardSetsRaw = localStorage.getItem('disabled_card_sets');
+  const disabledCardSets = disabledCardSetsRaw ? JSON.parse(disabledCardSetsRaw) : [];
+  return listCardSets()
+    .map(({ id }) => id)
+    .filter((id) => !disabledCardSets.includes(id));
+};